@@ -1,17 +1,32 @@
-use std::{path::Path, sync::Arc, vec::Vec};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    vec::Vec,
+};
 
 use tauri::{async_runtime::Mutex, State};
+use tf_demo_parser::demo::parser::analyser::UserId;
 
 use crate::{
     demo::{
-        analyser::GameSummary, error::Result, filter_demos, read_demo_details,
-        read_demos_in_directory, sort_demos, Demo, DemoEvent, Filter, SortKey,
+        analyser::{
+            DemoPositions, GameState, GameStates, GameSummary, HighlightConfig, CHECKPOINT_INTERVAL,
+        },
+        error::Result,
+        export_demo_voice, filter_demos, read_demo_details, read_demo_positions,
+        read_demos_in_directory, sort_demos, write_chat_log, ChatLogFormat, Demo, DemoEvent,
+        Filter, ParsedDemoInfo, SortKey,
     },
     demo_cache::DemoMetadataCache,
     parsed_demo_cache::ParsedDemoCache,
     traits::Cache,
 };
 
+/// The game states of the demo that was queried last, with the path of the demo.
+/// Scrubbing through a demo queries many ticks, which should not read
+/// and deserialize the entire `GameStates` from the disk cache every time.
+pub type LoadedGameStates = Option<(String, GameStates)>;
+
 /// Log the invocation of a tauri command
 macro_rules! log_command {
     ($($arg:tt)+) => (log::trace!(target: "IPC", $($arg)+))
@@ -24,12 +39,28 @@ pub async fn get_demos_in_directory(
     reverse: bool,
     filters: Vec<Filter>,
     demo_list_cache: State<'_, Mutex<DemoMetadataCache>>,
+    info_cache: State<'_, ParsedDemoCache<ParsedDemoInfo>>,
 ) -> Result<Vec<Arc<Demo>>> {
     log_command!("get_demos_in_directory {dir_path}");
 
     let mut demo_cache = demo_list_cache.lock().await;
 
     let mut demos = read_demos_in_directory(dir_path, &mut demo_cache)?;
+
+    // Demos that were parsed in an earlier session
+    for demo in demos.iter_mut().filter(|demo| demo.match_info.is_none()) {
+        match info_cache.get(&demo.path).await {
+            Ok(Some(info)) => match demo_cache.set_parsed_info(&demo.path, info) {
+                Ok(updated_demo) => *demo = updated_demo,
+                Err(error) => log::warn!("could not update demo {}: {error}", demo.path),
+            },
+            Ok(None) => {}
+            Err(error) => {
+                log::warn!("could not read info cache entry for {}: {error}", demo.path);
+            }
+        }
+    }
+
     sort_demos(demos.as_mut_slice(), sort_key, reverse);
     let filtered_demos = filter_demos(&demos, &filters);
 
@@ -135,7 +166,10 @@ pub async fn rename_demo(
     demo_path: &str,
     new_path: &str,
     demo_cache: State<'_, Mutex<DemoMetadataCache>>,
-    disk_cache: State<'_, ParsedDemoCache>,
+    disk_cache: State<'_, ParsedDemoCache<GameSummary, HighlightConfig>>,
+    info_cache: State<'_, ParsedDemoCache<ParsedDemoInfo>>,
+    positions_cache: State<'_, ParsedDemoCache<DemoPositions>>,
+    states_cache: State<'_, ParsedDemoCache<GameStates>>,
 ) -> Result<()> {
     log_command!("rename_demo {demo_path} {new_path}");
 
@@ -144,6 +178,18 @@ pub async fn rename_demo(
     demo_cache.rename(demo_path, new_path).await?;
     disk_cache.remove(demo_path).await?;
 
+    // Info is only cached for demos that were parsed, and positions only for demos
+    // that were opened in the positional review, so a missing entry is expected here.
+    if let Err(error) = info_cache.remove(demo_path).await {
+        log::trace!("could not remove info cache entry for {demo_path}: {error}");
+    }
+    if let Err(error) = positions_cache.remove(demo_path).await {
+        log::trace!("could not remove positions cache entry for {demo_path}: {error}");
+    }
+    if let Err(error) = states_cache.remove(demo_path).await {
+        log::trace!("could not remove game state cache entry for {demo_path}: {error}");
+    }
+
     Ok(())
 }
 
@@ -162,14 +208,44 @@ pub async fn get_demo(
 #[tauri::command]
 pub async fn get_demo_details(
     demo_path: &str,
-    disk_cache: State<'_, ParsedDemoCache>,
+    highlight_config: HighlightConfig,
+    disk_cache: State<'_, ParsedDemoCache<GameSummary, HighlightConfig>>,
+    info_cache: State<'_, ParsedDemoCache<ParsedDemoInfo>>,
+    states_cache: State<'_, ParsedDemoCache<GameStates>>,
+    demo_cache: State<'_, Mutex<DemoMetadataCache>>,
 ) -> Result<GameSummary> {
     log_command!("get_demo_details {}", demo_path);
 
-    match disk_cache.get(demo_path).await {
+    load_demo_details(
+        demo_path,
+        &highlight_config,
+        &disk_cache,
+        &info_cache,
+        &states_cache,
+        &demo_cache,
+    )
+    .await
+}
+
+/// Read the details of a demo from the disk cache, or parse the demo if necessary.
+/// Parsing also caches the game states, for `get_game_state`.
+async fn load_demo_details(
+    demo_path: &str,
+    highlight_config: &HighlightConfig,
+    disk_cache: &ParsedDemoCache<GameSummary, HighlightConfig>,
+    info_cache: &ParsedDemoCache<ParsedDemoInfo>,
+    states_cache: &ParsedDemoCache<GameStates>,
+    demo_cache: &Mutex<DemoMetadataCache>,
+) -> Result<GameSummary> {
+    match disk_cache
+        .get_with_options(demo_path, highlight_config)
+        .await
+    {
         Ok(Some(game_summary)) => {
             log::trace!("cache hit for {demo_path}");
 
+            remember_parsed_info(demo_path, &game_summary, info_cache, demo_cache).await;
+
             return Ok(game_summary);
         }
         Ok(None) => {
@@ -180,12 +256,176 @@ pub async fn get_demo_details(
         }
     }
 
-    let game_summary = read_demo_details(Path::new(demo_path))?;
+    let (game_summary, game_states) = read_demo_details(Path::new(demo_path), highlight_config)?;
 
-    if let Err(error) = disk_cache.insert(demo_path, &game_summary).await {
+    if let Err(error) = disk_cache
+        .insert_with_options(demo_path, highlight_config, &game_summary)
+        .await
+    {
         // Log the error, but don't fail the entire operation
         log::error!("Could not insert cache entry: {error}");
     }
+    if let Err(error) = states_cache.insert(demo_path, &game_states).await {
+        log::error!("Could not insert game state cache entry: {error}");
+    }
+
+    remember_parsed_info(demo_path, &game_summary, info_cache, demo_cache).await;
 
     Ok(game_summary)
 }
+
+/// Update the demo list with the results of parsing a demo, in memory and on disk.
+/// Failing to do so only affects filtering, so errors are logged instead of returned.
+async fn remember_parsed_info(
+    demo_path: &str,
+    game_summary: &GameSummary,
+    info_cache: &ParsedDemoCache<ParsedDemoInfo>,
+    demo_cache: &Mutex<DemoMetadataCache>,
+) {
+    let info = ParsedDemoInfo::from(game_summary);
+
+    if let Err(error) = info_cache.insert(demo_path, &info).await {
+        log::error!("Could not insert info cache entry: {error}");
+    }
+
+    if let Err(error) = demo_cache.lock().await.set_parsed_info(demo_path, info) {
+        log::error!("Could not update demo {demo_path}: {error}");
+    }
+}
+
+#[tauri::command]
+pub async fn get_demo_positions(
+    demo_path: &str,
+    sample_interval: u32,
+    positions_cache: State<'_, ParsedDemoCache<DemoPositions>>,
+) -> Result<DemoPositions> {
+    log_command!("get_demo_positions {demo_path} {sample_interval}");
+
+    // The analyser samples at least once per tick, normalize so cache entries still match
+    let sample_interval = sample_interval.max(1);
+
+    match positions_cache.get(demo_path).await {
+        Ok(Some(positions)) if positions.sample_interval == sample_interval => {
+            log::trace!("cache hit for {demo_path}");
+
+            return Ok(positions);
+        }
+        Ok(_) => {
+            log::trace!("cache miss for {demo_path}");
+        }
+        Err(error) => {
+            log::warn!("could not read positions cache entry for {demo_path}: {error}");
+        }
+    }
+
+    let positions = read_demo_positions(Path::new(demo_path), sample_interval)?;
+
+    if let Err(error) = positions_cache.insert(demo_path, &positions).await {
+        // Log the error, but don't fail the entire operation
+        log::error!("Could not insert cache entry: {error}");
+    }
+
+    Ok(positions)
+}
+
+/// Get the state of the game (team scores and every player's scoreboard, class, team
+/// and alive state) at `tick`. Returns `None` if `tick` is before the first checkpoint.
+#[tauri::command]
+pub async fn get_game_state(
+    demo_path: &str,
+    tick: u32,
+    states_cache: State<'_, ParsedDemoCache<GameStates>>,
+    loaded_states: State<'_, Mutex<LoadedGameStates>>,
+) -> Result<Option<GameState>> {
+    log_command!("get_game_state {demo_path} {tick}");
+
+    if let Some((path, states)) = loaded_states.lock().await.as_ref() {
+        if path == demo_path {
+            return Ok(states.state_at(tick.into()).cloned());
+        }
+    }
+
+    // Don't hold the lock while the demo may be parsed
+    let states = load_game_states(demo_path, &states_cache).await?;
+    let state = states.state_at(tick.into()).cloned();
+
+    *loaded_states.lock().await = Some((demo_path.into(), states));
+
+    Ok(state)
+}
+
+/// Read the game states of a demo from the disk cache, or parse the demo if necessary
+async fn load_game_states(
+    demo_path: &str,
+    states_cache: &ParsedDemoCache<GameStates>,
+) -> Result<GameStates> {
+    match states_cache.get(demo_path).await {
+        Ok(Some(states)) if states.checkpoint_interval == CHECKPOINT_INTERVAL => {
+            log::trace!("cache hit for {demo_path}");
+
+            return Ok(states);
+        }
+        Ok(_) => {
+            log::trace!("cache miss for {demo_path}");
+        }
+        Err(error) => {
+            log::warn!("could not read game state cache entry for {demo_path}: {error}");
+        }
+    }
+
+    // Usually the states were cached when the demo details were loaded.
+    // Highlights are not part of the game state, so the config does not matter here.
+    let path = PathBuf::from(demo_path);
+    let (_game_summary, states) = tauri::async_runtime::spawn_blocking(move || {
+        read_demo_details(&path, &HighlightConfig::default())
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+
+    if let Err(error) = states_cache.insert(demo_path, &states).await {
+        // Log the error, but don't fail the entire operation
+        log::error!("Could not insert cache entry: {error}");
+    }
+
+    Ok(states)
+}
+
+/// Export the raw voice packets of a single player into a container file at `output_path`,
+/// for offline decoding.
+#[tauri::command]
+pub async fn export_voice(demo_path: &str, user_id: u16, output_path: &str) -> Result<()> {
+    log_command!("export_voice {demo_path} {user_id} {output_path}");
+
+    export_demo_voice(
+        Path::new(demo_path),
+        UserId::from(user_id),
+        Path::new(output_path),
+    )
+}
+
+/// Export the chat log of a demo to `output_path`, either as plain text or as JSON.
+#[tauri::command]
+pub async fn export_chat_log(
+    demo_path: &str,
+    highlight_config: HighlightConfig,
+    format: ChatLogFormat,
+    output_path: &str,
+    disk_cache: State<'_, ParsedDemoCache<GameSummary, HighlightConfig>>,
+    info_cache: State<'_, ParsedDemoCache<ParsedDemoInfo>>,
+    states_cache: State<'_, ParsedDemoCache<GameStates>>,
+    demo_cache: State<'_, Mutex<DemoMetadataCache>>,
+) -> Result<()> {
+    log_command!("export_chat_log {demo_path} {output_path}");
+
+    let game_summary = load_demo_details(
+        demo_path,
+        &highlight_config,
+        &disk_cache,
+        &info_cache,
+        &states_cache,
+        &demo_cache,
+    )
+    .await?;
+
+    write_chat_log(&game_summary, format, Path::new(output_path))
+}
@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use tf_demo_parser::demo::data::DemoTick;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ViewAngles {
+    tick: DemoTick,
+    pitch: f32,
+    yaw: f32,
+}
+
+/// A flick, i.e. a sudden change of the view angles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flick {
+    /// In degrees
+    pub angle: f32,
+    /// How many ticks the flick took
+    pub ticks: u32,
+}
+
+/// Keeps the recent view angles of a single player (usually the local player of a POV demo)
+#[derive(Debug, Default)]
+pub struct ViewAngleHistory {
+    samples: VecDeque<ViewAngles>,
+}
+
+impl ViewAngleHistory {
+    /// Record the view angles at the given tick, keeping the last `window_ticks` ticks.
+    /// Angles that were not updated are carried over from the previous sample.
+    pub fn record(
+        &mut self,
+        tick: DemoTick,
+        pitch: Option<f32>,
+        yaw: Option<f32>,
+        window_ticks: u32,
+    ) {
+        let previous = self.samples.back().copied().unwrap_or_default();
+
+        self.samples.push_back(ViewAngles {
+            tick,
+            pitch: pitch.unwrap_or(previous.pitch),
+            yaw: yaw.unwrap_or(previous.yaw),
+        });
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| u32::from(tick - sample.tick) > window_ticks)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Find the largest change of the view angles of at least `min_angle` degrees
+    /// within `window_ticks` before the given tick.
+    pub fn find_flick(&self, tick: DemoTick, window_ticks: u32, min_angle: f32) -> Option<Flick> {
+        let current = self.samples.back()?;
+
+        self.samples
+            .iter()
+            .filter(|sample| sample.tick <= tick && u32::from(tick - sample.tick) <= window_ticks)
+            .map(|sample| Flick {
+                angle: angle_between(sample, current),
+                ticks: u32::from(current.tick - sample.tick),
+            })
+            .filter(|flick| flick.angle >= min_angle)
+            .max_by(|a, b| a.angle.total_cmp(&b.angle))
+    }
+}
+
+fn angle_between(a: &ViewAngles, b: &ViewAngles) -> f32 {
+    let pitch = b.pitch - a.pitch;
+    // Yaw wraps around, so turning from 170° to -170° is a change of 20°
+    let yaw = (b.yaw - a.yaw + 180.0).rem_euclid(360.0) - 180.0;
+
+    pitch.hypot(yaw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_flick() {
+        let mut history = ViewAngleHistory::default();
+
+        history.record(DemoTick::from(100), Some(0.0), Some(170.0), 10);
+        history.record(DemoTick::from(105), None, Some(175.0), 10);
+        assert_eq!(history.find_flick(DemoTick::from(105), 10, 45.0), None);
+
+        // Turn 60° to the left, across the wrap-around
+        history.record(DemoTick::from(108), Some(0.0), Some(-130.0), 10);
+
+        let flick = history
+            .find_flick(DemoTick::from(108), 10, 45.0)
+            .expect("expected a flick");
+        assert!((flick.angle - 60.0).abs() < 0.01);
+        assert_eq!(flick.ticks, 8);
+
+        // The turn is now outside of the window
+        history.record(DemoTick::from(200), None, None, 10);
+        assert_eq!(history.find_flick(DemoTick::from(200), 10, 45.0), None);
+    }
+}
@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, parser::analyser::UserId};
+
+use super::{BuildingKind, Highlight, HighlightEvent, RoundSummary, WinReason};
+
+/// Scored highlights that are less than this many seconds apart
+/// belong to the same moment.
+const MERGE_GAP_SECONDS: f32 = 5.0;
+
+/// How many seconds to show before the first and after the last highlight of a moment
+const LEAD_IN_SECONDS: f32 = 5.0;
+const LEAD_OUT_SECONDS: f32 = 2.0;
+
+/// A group of highlights that happened close together, ranked by how interesting they are.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BestMoment {
+    pub score: u32,
+    /// Suggested range of ticks to watch, including some lead-in
+    pub start_tick: DemoTick,
+    pub end_tick: DemoTick,
+    /// Indices into `GameSummary::highlights`, in order
+    pub highlights: Vec<usize>,
+}
+
+/// Score a single highlight. Highlights with a score of 0 are not part of any moment.
+/// `multikill_window` is `MultikillConfig::window`.
+fn score_highlight(highlight: &Highlight, multikill_window: u32) -> u32 {
+    match highlight {
+        Highlight::Kill {
+            streak,
+            drop,
+            airshot,
+            ..
+        } => {
+            let mut score = 1;
+            // Kills late in a streak matter more
+            if *streak >= 3 {
+                score += u32::try_from(*streak).unwrap_or(u32::MAX).min(20);
+            }
+            if *airshot {
+                score += 5;
+            }
+            if *drop {
+                score += 8;
+            }
+            score
+        }
+        Highlight::Multikill {
+            victims,
+            start_tick,
+            end_tick,
+            ..
+        } => {
+            let kills = u32::try_from(victims.len()).unwrap_or(u32::MAX).min(12);
+            let duration = u32::from(*end_tick - *start_tick).max(1);
+
+            // The more kills and the shorter the time between them, the better
+            kills * kills * 3 + (kills.saturating_mul(multikill_window) / duration).min(10)
+        }
+        Highlight::KillStreakEnded { streak, .. } => u32::from(*streak) / 2,
+        Highlight::Airshot { .. } | Highlight::Midair { .. } => 5,
+        Highlight::CrossbowAirshot { .. } | Highlight::Flick { .. } => 4,
+        Highlight::BuildingDestroyed {
+            kind: BuildingKind::Sentry,
+            level,
+            ..
+        } => u32::from(*level),
+        _ => 0,
+    }
+}
+
+/// The index of the last kill of every round that ended because the losing team was wiped
+fn round_deciding_kills(highlights: &[HighlightEvent], rounds: &[RoundSummary]) -> Vec<usize> {
+    rounds
+        .iter()
+        .filter(|round| round.win_reason == Some(WinReason::OpponentsDead))
+        .filter_map(|round| {
+            let end_tick = round.end_tick?;
+
+            highlights.iter().rposition(|highlight| {
+                highlight.tick >= round.start_tick
+                    && highlight.tick <= end_tick
+                    && matches!(
+                        &highlight.event,
+                        Highlight::Kill { killer, .. } if Some(killer.team) == round.winner
+                    )
+            })
+        })
+        .collect()
+}
+
+/// The killer and tick range of every multikill
+fn multikill_ranges(highlights: &[HighlightEvent]) -> Vec<(UserId, DemoTick, DemoTick)> {
+    highlights
+        .iter()
+        .filter_map(|highlight| match &highlight.event {
+            Highlight::Multikill {
+                killer,
+                start_tick,
+                end_tick,
+                ..
+            } => Some((killer.user_id, *start_tick, *end_tick)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Find the `count` most interesting moments of a demo, ordered by their score
+pub fn find_best_moments(
+    highlights: &[HighlightEvent],
+    rounds: &[RoundSummary],
+    interval_per_tick: f32,
+    multikill_window: u32,
+    count: usize,
+) -> Vec<BestMoment> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let seconds_to_ticks = |seconds: f32| -> u32 {
+        if interval_per_tick > 0.0 {
+            (seconds / interval_per_tick) as u32
+        } else {
+            // Assume the usual 66 ticks per second
+            (seconds * 66.0) as u32
+        }
+    };
+
+    let deciding_kills = round_deciding_kills(highlights, rounds);
+    let multikills = multikill_ranges(highlights);
+
+    // Kills of a multikill are already scored by the multikill itself
+    let is_multikill_kill = |highlight: &HighlightEvent| {
+        let Highlight::Kill { killer, .. } = &highlight.event else {
+            return false;
+        };
+        multikills
+            .iter()
+            .any(|(multikill_killer, start_tick, end_tick)| {
+                *multikill_killer == killer.user_id
+                    && highlight.tick >= *start_tick
+                    && highlight.tick <= *end_tick
+            })
+    };
+
+    // The highlights are sorted by tick (see `GameDetailsAnalyser::insert_highlight`),
+    // so moments can be built in a single pass.
+    let scored: Vec<(usize, u32)> = highlights
+        .iter()
+        .enumerate()
+        .filter_map(|(index, highlight)| {
+            // These are still part of the moment, just without a score of their own
+            let is_multikill_kill = is_multikill_kill(highlight);
+            let mut score = if is_multikill_kill {
+                0
+            } else {
+                score_highlight(&highlight.event, multikill_window)
+            };
+            if deciding_kills.contains(&index) {
+                score += 10;
+            }
+            (score > 0 || is_multikill_kill).then_some((index, score))
+        })
+        .collect();
+
+    let merge_gap = seconds_to_ticks(MERGE_GAP_SECONDS);
+
+    // (first tick, last tick, moment)
+    let mut moments: Vec<(DemoTick, DemoTick, BestMoment)> = Vec::new();
+
+    for (index, score) in scored {
+        let tick = highlights[index].tick;
+
+        match moments.last_mut() {
+            Some((_first_tick, last_tick, moment)) if u32::from(tick - *last_tick) <= merge_gap => {
+                *last_tick = tick;
+                moment.score += score;
+                moment.highlights.push(index);
+            }
+            _ => moments.push((
+                tick,
+                tick,
+                BestMoment {
+                    score,
+                    start_tick: tick,
+                    end_tick: tick,
+                    highlights: vec![index],
+                },
+            )),
+        }
+    }
+
+    let lead_in = seconds_to_ticks(LEAD_IN_SECONDS);
+    let lead_out = seconds_to_ticks(LEAD_OUT_SECONDS);
+
+    let mut moments: Vec<BestMoment> = moments
+        .into_iter()
+        .map(|(first_tick, last_tick, moment)| BestMoment {
+            start_tick: DemoTick::from(u32::from(first_tick).saturating_sub(lead_in)),
+            end_tick: DemoTick::from(u32::from(last_tick).saturating_add(lead_out)),
+            ..moment
+        })
+        .collect();
+
+    // Stable sort, so earlier moments win ties
+    moments.sort_by_key(|moment| std::cmp::Reverse(moment.score));
+    moments.truncate(count);
+
+    moments
+}
+
+#[cfg(test)]
+mod tests {
+    use tf_demo_parser::demo::parser::analyser::Team;
+
+    use super::*;
+    use crate::demo::analyser::{CritType, HighlightPlayerSnapshot};
+
+    fn kill(tick: u32, streak: usize, drop: bool) -> HighlightEvent {
+        HighlightEvent {
+            tick: DemoTick::from(tick),
+            event: Highlight::Kill {
+                killer: HighlightPlayerSnapshot {
+                    team: Team::Red,
+                    ..Default::default()
+                },
+                assister: None,
+                victim: HighlightPlayerSnapshot::default(),
+                weapon: String::new(),
+                kill_icon: String::new(),
+                streak,
+                drop,
+                airshot: false,
+                crit: CritType::Normal,
+                random_crit: false,
+            },
+        }
+    }
+
+    fn multikill(start_tick: u32, end_tick: u32, kills: usize) -> HighlightEvent {
+        HighlightEvent {
+            tick: DemoTick::from(end_tick),
+            event: Highlight::Multikill {
+                killer: HighlightPlayerSnapshot {
+                    team: Team::Red,
+                    ..Default::default()
+                },
+                victims: (0..kills)
+                    .map(|_| HighlightPlayerSnapshot::default())
+                    .collect(),
+                weapons: vec![String::new(); kills],
+                start_tick: DemoTick::from(start_tick),
+                end_tick: DemoTick::from(end_tick),
+            },
+        }
+    }
+
+    #[test]
+    fn test_best_moments() {
+        let highlights = vec![
+            kill(1000, 1, false),
+            // A medic drop 30 seconds later
+            kill(3000, 1, true),
+            // A triple kill
+            kill(4000, 1, false),
+            kill(4050, 2, false),
+            kill(4100, 3, false),
+            multikill(4000, 4100, 3),
+            // Two kills close together, the last one winning the round
+            kill(6000, 1, false),
+            kill(6100, 1, false),
+        ];
+        let rounds = vec![RoundSummary {
+            round: 0,
+            start_tick: DemoTick::from(0),
+            end_tick: Some(DemoTick::from(6200)),
+            duration: 6200,
+            winner: Some(Team::Red),
+            win_reason: Some(WinReason::OpponentsDead),
+            captures: Vec::new(),
+            first_blood: None,
+            stalemate: false,
+            sudden_death: false,
+        }];
+
+        let moments = find_best_moments(&highlights, &rounds, 0.015, 200, 3);
+        assert_eq!(moments.len(), 3);
+
+        // 3 * 3 * 3 + 3 * 200 / 100, the kills themselves are only scored by the multikill
+        assert_eq!(moments[0].score, 33);
+        assert_eq!(moments[0].highlights, vec![2, 3, 4, 5]);
+
+        // 1 + (1 + 10) for the round-deciding kill
+        assert_eq!(moments[1].score, 12);
+        assert_eq!(moments[1].highlights, vec![6, 7]);
+        // 5 seconds of lead-in
+        assert_eq!(moments[1].start_tick, DemoTick::from(6000 - 333));
+
+        assert_eq!(moments[2].score, 9);
+        assert_eq!(moments[2].highlights, vec![1]);
+    }
+
+    #[test]
+    fn test_multikill_score_uses_window() {
+        let multikill = Highlight::Multikill {
+            killer: HighlightPlayerSnapshot::default(),
+            victims: vec![
+                HighlightPlayerSnapshot::default(),
+                HighlightPlayerSnapshot::default(),
+            ],
+            weapons: vec![String::new(); 2],
+            start_tick: DemoTick::from(1000),
+            end_tick: DemoTick::from(1200),
+        };
+
+        // 2 * 2 * 3 + 2 * 200 / 200
+        assert_eq!(score_highlight(&multikill, 200), 14);
+        // The same kills are quicker relative to a longer window
+        assert_eq!(score_highlight(&multikill, 500), 17);
+    }
+}
@@ -0,0 +1,95 @@
+use num_derive::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, parser::analyser::UserId};
+
+use super::WeaponClass;
+
+/// Indexed by the "object" and "objecttype" fields of the building events.
+/// Sappers (3) are not tracked.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, FromPrimitive)]
+pub enum BuildingKind {
+    Dispenser = 0,
+    Teleporter = 1,
+    Sentry = 2,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum TeleporterMode {
+    #[default]
+    Entrance,
+    Exit,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct BuildingUpgrade {
+    pub tick: DemoTick,
+    pub level: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub enum BuildingEnd {
+    /// Destroyed by an enemy
+    Destroyed {
+        attacker: UserId,
+        assister: Option<UserId>,
+        /// The kill icon of the weapon
+        weapon: String,
+    },
+    /// Detonated by the engineer
+    Detonated,
+    /// Removed by the game, for example because the engineer changed class
+    Removed,
+}
+
+/// A single building, from the moment it was placed until it was destroyed
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Building {
+    pub builder: UserId,
+    pub kind: BuildingKind,
+
+    /// Only set for teleporters
+    pub teleporter_mode: Option<TeleporterMode>,
+    pub mini: bool,
+
+    pub built_tick: DemoTick,
+    pub upgrades: Vec<BuildingUpgrade>,
+
+    /// Kills made by this sentry
+    pub kills: u32,
+
+    /// Number of players that used this teleporter
+    pub uses: u32,
+
+    /// `None` if the building still existed when the demo ended
+    pub end_tick: Option<DemoTick>,
+    pub end: Option<BuildingEnd>,
+}
+
+impl Building {
+    pub fn level(&self) -> u8 {
+        self.upgrades.last().map_or(1, |upgrade| upgrade.level)
+    }
+}
+
+/// Whether a kill was made by a sentry, including wrangled sentries.
+/// The inflictor of a kill by a sentry rocket is the rocket, not the sentry.
+pub fn is_sentry_kill(kill_icon: &str, weapon: WeaponClass) -> bool {
+    matches!(
+        kill_icon,
+        "obj_sentrygun" | "obj_sentrygun2" | "obj_sentrygun3" | "obj_minisentry" | "wrangler_kill"
+    ) || matches!(
+        weapon,
+        WeaponClass::TF_WEAPON_SENTRY_BULLET | WeaponClass::TF_WEAPON_SENTRY_ROCKET
+    )
+}
+
+/// Statistics of all buildings of a single kind built by an engineer
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone)]
+pub struct BuildingStats {
+    pub built: u32,
+    pub upgrades: u32,
+    /// Buildings of this kind that were destroyed by enemies
+    pub destroyed: u32,
+    pub kills: u32,
+    pub uses: u32,
+}
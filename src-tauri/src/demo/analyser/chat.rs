@@ -0,0 +1,135 @@
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{
+    data::DemoTick,
+    message::usermessage::ChatMessageKind,
+    parser::analyser::{Team, UserId},
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ChatKind {
+    All,
+    Team,
+    AllDead,
+    TeamDead,
+    Spectator,
+    /// The text is the new name of the sender
+    NameChange,
+}
+
+impl From<ChatMessageKind> for ChatKind {
+    fn from(kind: ChatMessageKind) -> Self {
+        match kind {
+            ChatMessageKind::ChatAll | ChatMessageKind::Empty => Self::All,
+            ChatMessageKind::ChatTeam => Self::Team,
+            ChatMessageKind::ChatAllDead => Self::AllDead,
+            ChatMessageKind::ChatTeamDead => Self::TeamDead,
+            ChatMessageKind::ChatAllSpec => Self::Spectator,
+            ChatMessageKind::NameChange => Self::NameChange,
+        }
+    }
+}
+
+impl ChatKind {
+    /// The prefix the game shows in front of the sender's name
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::All | Self::NameChange => "",
+            Self::Team => "(TEAM) ",
+            Self::AllDead => "*DEAD* ",
+            Self::TeamDead => "*DEAD*(TEAM) ",
+            Self::Spectator => "*SPEC* ",
+        }
+    }
+}
+
+/// A single chat message, or a name change
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatLogEntry {
+    pub tick: DemoTick,
+    pub user_id: UserId,
+    pub name: String,
+    pub team: Team,
+    pub kind: ChatKind,
+    /// Whether the sender was alive when the message was sent
+    pub sender_alive: bool,
+    pub text: String,
+}
+
+/// Format the chat log like the in-game chat, with the time since the start of the demo
+pub fn format_chat_log(chat_log: &[ChatLogEntry], interval_per_tick: f32) -> String {
+    let mut output = String::new();
+
+    for entry in chat_log {
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss
+        )]
+        let seconds = (u32::from(entry.tick) as f32 * interval_per_tick) as u32;
+        let time = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+
+        let _ = if entry.kind == ChatKind::NameChange {
+            writeln!(
+                output,
+                "[{time}] {} changed their name to {}",
+                entry.name, entry.text
+            )
+        } else {
+            writeln!(
+                output,
+                "[{time}] {}{}: {}",
+                entry.kind.prefix(),
+                entry.name,
+                entry.text
+            )
+        };
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tick: u32, kind: ChatKind, text: &str) -> ChatLogEntry {
+        ChatLogEntry {
+            tick: DemoTick::from(tick),
+            user_id: UserId::from(2u16),
+            name: "Alice".into(),
+            team: Team::Red,
+            kind,
+            sender_alive: kind != ChatKind::AllDead,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_format_chat_log() {
+        let chat_log = vec![
+            entry(0, ChatKind::All, "gl hf"),
+            entry(4000, ChatKind::TeamDead, "push"),
+            entry(8000, ChatKind::NameChange, "Bob"),
+        ];
+
+        assert_eq!(
+            format_chat_log(&chat_log, 0.015),
+            "[00:00] Alice: gl hf\n\
+             [01:00] *DEAD*(TEAM) Alice: push\n\
+             [02:00] Alice changed their name to Bob\n"
+        );
+    }
+
+    #[test]
+    fn test_chat_kind() {
+        assert_eq!(ChatKind::from(ChatMessageKind::Empty), ChatKind::All);
+        assert_eq!(
+            ChatKind::from(ChatMessageKind::ChatAllSpec),
+            ChatKind::Spectator
+        );
+        assert_eq!(ChatKind::TeamDead.prefix(), "*DEAD*(TEAM) ");
+        assert_eq!(ChatKind::NameChange.prefix(), "");
+    }
+}
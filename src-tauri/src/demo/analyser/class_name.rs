@@ -0,0 +1,18 @@
+//! `Class` deserializes from either a number or a name,
+//! which bincode does not support, so we always (de)serialize it by name
+
+use std::str::FromStr;
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use tf_demo_parser::demo::parser::analyser::Class;
+
+// The signature is dictated by serde
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn serialize<S: Serializer>(class: &Class, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&class.to_string())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Class, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Class::from_str(&name).map_err(|_| D::Error::custom(format!("unknown class: {name}")))
+}
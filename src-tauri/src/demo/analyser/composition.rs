@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{
+    data::DemoTick,
+    parser::analyser::{Class, Team, UserId},
+};
+
+/// A player spawned as a different class than in their previous life
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassChange {
+    pub tick: DemoTick,
+    pub user_id: UserId,
+    pub team: Team,
+    #[serde(with = "super::class_name")]
+    pub from: Class,
+    #[serde(with = "super::class_name")]
+    pub to: Class,
+}
+
+/// The classes of a team, starting at a certain tick
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TeamComposition {
+    pub tick: DemoTick,
+    pub team: Team,
+    /// The number of players on each class, in the same order as `PlayerSummary::time_on_class`
+    pub classes: [u8; 9],
+}
+
+/// Records the composition of each team whenever it changes
+#[derive(Debug, Default)]
+pub struct CompositionTracker {
+    timeline: Vec<TeamComposition>,
+}
+
+impl CompositionTracker {
+    fn last_index(&self, team: Team) -> Option<usize> {
+        self.timeline
+            .iter()
+            .rposition(|composition| composition.team == team)
+    }
+
+    pub fn update(&mut self, tick: DemoTick, team: Team, classes: [u8; 9]) {
+        let mut last_index = self.last_index(team);
+
+        // Everyone spawns at the same time at the start of a round.
+        // Only keep the composition after the last spawn of a tick.
+        if let Some(index) = last_index {
+            if self.timeline[index].tick == tick {
+                self.timeline.remove(index);
+                last_index = self.last_index(team);
+            }
+        }
+
+        let unchanged = match last_index {
+            Some(index) => self.timeline[index].classes == classes,
+            None => classes.iter().all(|&count| count == 0),
+        };
+
+        if !unchanged {
+            self.timeline.push(TeamComposition {
+                tick,
+                team,
+                classes,
+            });
+        }
+    }
+
+    /// Returns the compositions of both teams, ordered by tick
+    pub fn finish(self) -> Vec<TeamComposition> {
+        self.timeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIXES: [u8; 9] = [2, 0, 2, 1, 1, 0, 0, 0, 0];
+    const OFFCLASS: [u8; 9] = [2, 0, 1, 1, 1, 1, 0, 0, 0];
+
+    #[test]
+    fn test_composition_changes() {
+        let mut tracker = CompositionTracker::default();
+
+        // An empty team is not a composition
+        tracker.update(DemoTick::from(100), Team::Red, [0; 9]);
+        tracker.update(DemoTick::from(200), Team::Red, SIXES);
+        // Respawning as the same class
+        tracker.update(DemoTick::from(300), Team::Red, SIXES);
+        tracker.update(DemoTick::from(400), Team::Red, OFFCLASS);
+
+        assert_eq!(
+            tracker.finish(),
+            vec![
+                TeamComposition {
+                    tick: DemoTick::from(200),
+                    team: Team::Red,
+                    classes: SIXES,
+                },
+                TeamComposition {
+                    tick: DemoTick::from(400),
+                    team: Team::Red,
+                    classes: OFFCLASS,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compositions_within_a_tick() {
+        let mut tracker = CompositionTracker::default();
+
+        // Players spawning one after another at the start of a round
+        tracker.update(DemoTick::from(200), Team::Red, [1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        tracker.update(DemoTick::from(200), Team::Red, SIXES);
+        tracker.update(DemoTick::from(200), Team::Blue, SIXES);
+        // Changed back within the same tick
+        tracker.update(DemoTick::from(500), Team::Blue, OFFCLASS);
+        tracker.update(DemoTick::from(500), Team::Blue, SIXES);
+
+        assert_eq!(
+            tracker.finish(),
+            vec![
+                TeamComposition {
+                    tick: DemoTick::from(200),
+                    team: Team::Red,
+                    classes: SIXES,
+                },
+                TeamComposition {
+                    tick: DemoTick::from(200),
+                    team: Team::Blue,
+                    classes: SIXES,
+                },
+            ]
+        );
+    }
+}
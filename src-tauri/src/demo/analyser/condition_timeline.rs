@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::data::DemoTick;
+
+use super::{crit::CRIT_BOOST_CONDITIONS, PlayerCondition};
+
+/// Groups of `PlayerCondition`s that are recorded in the condition timeline
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TrackedCondition {
+    Ubercharged,
+    Vaccinated,
+    Quickfixed,
+    CritBoosted,
+    Jarated,
+    Milked,
+    MarkedForDeath,
+    Bleeding,
+    Burning,
+    Cloaked,
+    Disguised,
+    Charging,
+}
+
+impl TrackedCondition {
+    pub const ALL: [Self; 12] = [
+        Self::Ubercharged,
+        Self::Vaccinated,
+        Self::Quickfixed,
+        Self::CritBoosted,
+        Self::Jarated,
+        Self::Milked,
+        Self::MarkedForDeath,
+        Self::Bleeding,
+        Self::Burning,
+        Self::Cloaked,
+        Self::Disguised,
+        Self::Charging,
+    ];
+
+    /// The condition is active if the player has any of these conditions
+    pub fn conditions(self) -> &'static [PlayerCondition] {
+        match self {
+            Self::Ubercharged => &[
+                PlayerCondition::TF_COND_INVULNERABLE,
+                PlayerCondition::TF_COND_INVULNERABLE_WEARINGOFF,
+            ],
+            Self::Vaccinated => &[
+                PlayerCondition::TF_COND_MEDIGUN_UBER_BULLET_RESIST,
+                PlayerCondition::TF_COND_MEDIGUN_UBER_BLAST_RESIST,
+                PlayerCondition::TF_COND_MEDIGUN_UBER_FIRE_RESIST,
+            ],
+            Self::Quickfixed => &[PlayerCondition::TF_COND_MEGAHEAL],
+            Self::CritBoosted => &CRIT_BOOST_CONDITIONS,
+            Self::Jarated => &[PlayerCondition::TF_COND_URINE],
+            Self::Milked => &[PlayerCondition::TF_COND_MAD_MILK],
+            Self::MarkedForDeath => &[
+                PlayerCondition::TF_COND_MARKEDFORDEATH,
+                PlayerCondition::TF_COND_MARKEDFORDEATH_SILENT,
+            ],
+            Self::Bleeding => &[
+                PlayerCondition::TF_COND_BLEEDING,
+                PlayerCondition::TF_COND_GRAPPLINGHOOK_BLEEDING,
+            ],
+            Self::Burning => &[
+                PlayerCondition::TF_COND_BURNING,
+                PlayerCondition::TF_COND_BURNING_PYRO,
+            ],
+            Self::Cloaked => &[PlayerCondition::TF_COND_STEALTHED],
+            Self::Disguised => &[PlayerCondition::TF_COND_DISGUISED],
+            Self::Charging => &[PlayerCondition::TF_COND_SHIELD_CHARGE],
+        }
+    }
+}
+
+/// A period of time during which a player had a condition
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ConditionInterval {
+    pub condition: TrackedCondition,
+    pub start_tick: DemoTick,
+    /// `None` if the condition was still active when the demo ended
+    pub end_tick: Option<DemoTick>,
+    /// In ticks, excluding pauses
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveCondition {
+    start_tick: DemoTick,
+    start_game_tick: u32,
+}
+
+/// Records the condition intervals of a single player.
+///
+/// Like the `RoundTracker`, ticks are passed twice: The demo tick is stored in
+/// the interval, while the game tick is used to measure its duration.
+#[derive(Debug, Default)]
+pub struct ConditionTimeline {
+    intervals: Vec<ConditionInterval>,
+    active: HashMap<TrackedCondition, ActiveCondition>,
+}
+
+impl ConditionTimeline {
+    /// Start and end intervals according to the currently active conditions
+    pub fn update(
+        &mut self,
+        is_active: impl Fn(TrackedCondition) -> bool,
+        tick: DemoTick,
+        game_tick: u32,
+    ) {
+        for condition in TrackedCondition::ALL {
+            match (is_active(condition), self.active.contains_key(&condition)) {
+                (true, false) => {
+                    self.active.insert(
+                        condition,
+                        ActiveCondition {
+                            start_tick: tick,
+                            start_game_tick: game_tick,
+                        },
+                    );
+                }
+                (false, true) => self.end(condition, Some(tick), game_tick),
+                _ => {}
+            }
+        }
+    }
+
+    /// End all active conditions, for example because the player left the game
+    pub fn end_all(&mut self, tick: Option<DemoTick>, game_tick: u32) {
+        for condition in TrackedCondition::ALL {
+            if self.active.contains_key(&condition) {
+                self.end(condition, tick, game_tick);
+            }
+        }
+    }
+
+    fn end(&mut self, condition: TrackedCondition, end_tick: Option<DemoTick>, game_tick: u32) {
+        if let Some(active) = self.active.remove(&condition) {
+            self.intervals.push(ConditionInterval {
+                condition,
+                start_tick: active.start_tick,
+                end_tick,
+                duration: game_tick.saturating_sub(active.start_game_tick),
+            });
+        }
+    }
+
+    /// Get all intervals, ordered by their start tick.
+    /// Conditions that are still active are ignored, call `end_all` first.
+    pub fn into_intervals(self) -> Vec<ConditionInterval> {
+        let mut intervals = self.intervals;
+        intervals.sort_by_key(|interval| interval.start_tick);
+        intervals
+    }
+}
+
+/// Total time (in ticks, excluding pauses) spent with each condition
+pub fn total_durations(intervals: &[ConditionInterval]) -> HashMap<TrackedCondition, u32> {
+    let mut totals = HashMap::new();
+
+    for interval in intervals {
+        *totals.entry(interval.condition).or_default() += interval.duration;
+    }
+
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_timeline() {
+        let mut timeline = ConditionTimeline::default();
+        let burning = |condition| condition == TrackedCondition::Burning;
+        let burning_and_jarated = |condition| {
+            matches!(
+                condition,
+                TrackedCondition::Burning | TrackedCondition::Jarated
+            )
+        };
+
+        timeline.update(burning, DemoTick::from(100), 100);
+        timeline.update(burning_and_jarated, DemoTick::from(150), 150);
+        // Paused for 50 ticks
+        timeline.update(|_| false, DemoTick::from(300), 250);
+        timeline.update(burning, DemoTick::from(400), 350);
+        timeline.end_all(None, 400);
+
+        let intervals = timeline.into_intervals();
+        assert_eq!(intervals.len(), 3);
+        assert_eq!(intervals[0].condition, TrackedCondition::Burning);
+        assert_eq!(intervals[0].end_tick, Some(DemoTick::from(300)));
+        assert_eq!(intervals[0].duration, 150);
+        assert_eq!(intervals[1].condition, TrackedCondition::Jarated);
+        assert_eq!(intervals[2].end_tick, None);
+
+        let totals = total_durations(&intervals);
+        assert_eq!(totals[&TrackedCondition::Burning], 200);
+        assert_eq!(totals[&TrackedCondition::Jarated], 100);
+    }
+}
@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::gameevent_gen::{PlayerDeathEvent, PlayerHurtEvent};
+
+use super::{CustomDamage, DamageFlag, PlayerCondition, WeaponClass};
+
+/// Conditions that make every shot of a player a full crit
+pub const CRIT_BOOST_CONDITIONS: [PlayerCondition; 11] = [
+    PlayerCondition::TF_COND_CRITBOOSTED,
+    PlayerCondition::TF_COND_CRITBOOSTED_PUMPKIN,
+    PlayerCondition::TF_COND_CRITBOOSTED_USER_BUFF,
+    PlayerCondition::TF_COND_CRITBOOSTED_DEMO_CHARGE,
+    PlayerCondition::TF_COND_CRITBOOSTED_FIRST_BLOOD,
+    PlayerCondition::TF_COND_CRITBOOSTED_BONUS_TIME,
+    PlayerCondition::TF_COND_CRITBOOSTED_CTF_CAPTURE,
+    PlayerCondition::TF_COND_CRITBOOSTED_ON_KILL,
+    PlayerCondition::TF_COND_CRITBOOSTED_RAGE_BUFF,
+    PlayerCondition::TF_COND_CRITBOOSTED_CARD_EFFECT,
+    PlayerCondition::TF_COND_CRITBOOSTED_RUNE_TEMP,
+];
+
+/// Weapon classes that only crit when the crit was earned,
+/// like the revenge crits of the Frontier Justice and the Manmelter
+const NO_RANDOM_CRIT_WEAPON_CLASSES: [WeaponClass; 2] = [
+    WeaponClass::TF_WEAPON_SENTRY_REVENGE,
+    WeaponClass::TF_WEAPON_FLAREGUN_REVENGE,
+];
+
+/// Items (definition index and weapon class) that cannot random crit, but
+/// share their weapon class with items that can.
+/// Their crits are guaranteed, e.g. the Market Gardener while blast jumping,
+/// or the Backburner and the Axtinguisher from behind.
+const NO_RANDOM_CRIT_ITEMS: [(u32, WeaponClass); 8] = [
+    // Axtinguisher, Postal Pummeler, Festive Axtinguisher
+    (38, WeaponClass::TF_WEAPON_FIREAXE),
+    (457, WeaponClass::TF_WEAPON_FIREAXE),
+    (1000, WeaponClass::TF_WEAPON_FIREAXE),
+    // Backburner, Festive Backburner
+    (40, WeaponClass::TF_WEAPON_FLAMETHROWER),
+    (1146, WeaponClass::TF_WEAPON_FLAMETHROWER),
+    // Bushwacka
+    (232, WeaponClass::TF_WEAPON_CLUB),
+    // Market Gardener
+    (416, WeaponClass::TF_WEAPON_SHOVEL),
+    // Diamondback
+    (525, WeaponClass::TF_WEAPON_REVOLVER),
+];
+
+/// Flare Gun and Festive Flare Gun, which always crit burning players
+const FLARE_GUN_ITEMS: [u32; 2] = [39, 1081];
+
+/// What is known about a hit, to tell random crits from earned ones
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CritContext<'a> {
+    pub attacker_crit_boosted: bool,
+    pub victim_burning: bool,
+    pub weapon: WeaponClass,
+    /// Definition indexes of the items the hit may have been dealt with.
+    /// Damage events do not report the item, so this is the attacker's loadout.
+    pub weapon_items: &'a [u32],
+    pub custom_damage: Option<CustomDamage>,
+}
+
+impl CritContext<'_> {
+    fn uses_item(&self, definition_index: u32, weapon: WeaponClass) -> bool {
+        self.weapon == weapon && self.weapon_items.contains(&definition_index)
+    }
+
+    fn is_guaranteed_crit(&self) -> bool {
+        if self.attacker_crit_boosted {
+            return true;
+        }
+
+        if matches!(
+            self.custom_damage,
+            Some(
+                CustomDamage::TF_DMG_CUSTOM_HEADSHOT
+                    | CustomDamage::TF_DMG_CUSTOM_HEADSHOT_DECAPITATION
+                    | CustomDamage::TF_DMG_CUSTOM_BACKSTAB
+            )
+        ) {
+            return true;
+        }
+
+        NO_RANDOM_CRIT_WEAPON_CLASSES.contains(&self.weapon)
+            || NO_RANDOM_CRIT_ITEMS
+                .iter()
+                .any(|&(item, weapon)| self.uses_item(item, weapon))
+            || (self.victim_burning
+                && FLARE_GUN_ITEMS
+                    .iter()
+                    .any(|&item| self.uses_item(item, WeaponClass::TF_WEAPON_FLAREGUN)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum CritType {
+    #[default]
+    Normal,
+    MiniCrit,
+    Crit,
+}
+
+impl CritType {
+    pub fn from_hurt_event(event: &PlayerHurtEvent) -> Self {
+        if event.crit {
+            Self::Crit
+        } else if event.mini_crit {
+            Self::MiniCrit
+        } else {
+            Self::Normal
+        }
+    }
+
+    pub fn from_death_event(event: &PlayerDeathEvent) -> Self {
+        match event.crit_type {
+            1 => Self::MiniCrit,
+            2 => Self::Crit,
+            // Older demos do not have a crit type, but still set the damage flag
+            _ if (event.damage_bits & DamageFlag::DMG_CRITICAL.bitmask()) != 0 => Self::Crit,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Whether this is a random crit, as opposed to a crit that was earned,
+    /// e.g. through a crit boost, a headshot, a backstab or a weapon's crit condition.
+    /// Minicrits are never random.
+    pub fn is_random(self, context: &CritContext) -> bool {
+        self == Self::Crit && !context.is_guaranteed_crit()
+    }
+}
+
+/// Damage and kills dealt with crits and minicrits.
+/// Random crits are included in the crit totals.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone)]
+pub struct CritStats {
+    pub crit_damage: u32,
+    pub crit_hits: u32,
+    pub crit_kills: u32,
+
+    pub random_crit_damage: u32,
+    pub random_crit_hits: u32,
+    pub random_crit_kills: u32,
+
+    pub minicrit_damage: u32,
+    pub minicrit_hits: u32,
+    pub minicrit_kills: u32,
+}
+
+impl CritStats {
+    pub fn add_hit(&mut self, crit_type: CritType, random: bool, damage: u32) {
+        match crit_type {
+            CritType::Normal => {}
+            CritType::MiniCrit => {
+                self.minicrit_damage += damage;
+                self.minicrit_hits += 1;
+            }
+            CritType::Crit => {
+                self.crit_damage += damage;
+                self.crit_hits += 1;
+                if random {
+                    self.random_crit_damage += damage;
+                    self.random_crit_hits += 1;
+                }
+            }
+        }
+    }
+
+    pub fn add_kill(&mut self, crit_type: CritType, random: bool) {
+        match crit_type {
+            CritType::Normal => {}
+            CritType::MiniCrit => self.minicrit_kills += 1,
+            CritType::Crit => {
+                self.crit_kills += 1;
+                if random {
+                    self.random_crit_kills += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_random() {
+        let rocket = CritContext {
+            weapon: WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+            weapon_items: &[18, 416],
+            ..CritContext::default()
+        };
+        assert!(CritType::Crit.is_random(&rocket));
+        assert!(!CritType::MiniCrit.is_random(&rocket));
+        assert!(!CritType::Normal.is_random(&rocket));
+
+        let crit_boosted = CritContext {
+            attacker_crit_boosted: true,
+            ..rocket
+        };
+        assert!(!CritType::Crit.is_random(&crit_boosted));
+
+        let backstab = CritContext {
+            weapon: WeaponClass::TF_WEAPON_KNIFE,
+            custom_damage: Some(CustomDamage::TF_DMG_CUSTOM_BACKSTAB),
+            ..CritContext::default()
+        };
+        assert!(!CritType::Crit.is_random(&backstab));
+    }
+
+    #[test]
+    fn test_is_random_earned_by_weapon() {
+        // Market Gardener, while the rocket launcher in the same loadout can random crit
+        let market_gardener = CritContext {
+            weapon: WeaponClass::TF_WEAPON_SHOVEL,
+            weapon_items: &[18, 416],
+            ..CritContext::default()
+        };
+        assert!(!CritType::Crit.is_random(&market_gardener));
+
+        let shovel = CritContext {
+            weapon_items: &[18, 6],
+            ..market_gardener
+        };
+        assert!(CritType::Crit.is_random(&shovel));
+
+        let frontier_justice = CritContext {
+            weapon: WeaponClass::TF_WEAPON_SENTRY_REVENGE,
+            weapon_items: &[141],
+            ..CritContext::default()
+        };
+        assert!(!CritType::Crit.is_random(&frontier_justice));
+
+        let backburner = CritContext {
+            weapon: WeaponClass::TF_WEAPON_FLAMETHROWER,
+            weapon_items: &[40, 39, 38],
+            ..CritContext::default()
+        };
+        assert!(!CritType::Crit.is_random(&backburner));
+
+        let axtinguisher = CritContext {
+            weapon: WeaponClass::TF_WEAPON_FIREAXE,
+            weapon_items: &[21, 39, 38],
+            victim_burning: true,
+            ..CritContext::default()
+        };
+        assert!(!CritType::Crit.is_random(&axtinguisher));
+    }
+
+    #[test]
+    fn test_is_random_earned_by_victim_condition() {
+        let flare_gun = CritContext {
+            weapon: WeaponClass::TF_WEAPON_FLAREGUN,
+            weapon_items: &[21, 39, 2],
+            victim_burning: true,
+            ..CritContext::default()
+        };
+        assert!(!CritType::Crit.is_random(&flare_gun));
+
+        let not_burning = CritContext {
+            victim_burning: false,
+            ..flare_gun
+        };
+        assert!(CritType::Crit.is_random(&not_burning));
+
+        // The Detonator only minicrits burning players
+        let detonator = CritContext {
+            weapon_items: &[21, 351, 2],
+            ..flare_gun
+        };
+        assert!(CritType::Crit.is_random(&detonator));
+    }
+
+    #[test]
+    fn test_crit_stats() {
+        let mut stats = CritStats::default();
+
+        stats.add_hit(CritType::Crit, true, 270);
+        stats.add_hit(CritType::Crit, false, 300);
+        stats.add_hit(CritType::MiniCrit, false, 120);
+        stats.add_hit(CritType::Normal, false, 90);
+        stats.add_kill(CritType::Crit, true);
+
+        assert_eq!(stats.crit_damage, 570);
+        assert_eq!(stats.crit_hits, 2);
+        assert_eq!(stats.random_crit_damage, 270);
+        assert_eq!(stats.random_crit_kills, 1);
+        assert_eq!(stats.minicrit_damage, 120);
+        assert_eq!(stats.minicrit_kills, 0);
+    }
+}
@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::parser::analyser::Team;
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    Unknown,
+    /// Symmetric control points, like 5CP
+    ControlPoints,
+    AttackDefend,
+    Koth,
+    Payload,
+    PayloadRace,
+    Ctf,
+    Arena,
+    Mvm,
+    Passtime,
+}
+
+impl GameMode {
+    /// The same name that is used when (de)serializing
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::ControlPoints => "control_points",
+            Self::AttackDefend => "attack_defend",
+            Self::Koth => "koth",
+            Self::Payload => "payload",
+            Self::PayloadRace => "payload_race",
+            Self::Ctf => "ctf",
+            Self::Arena => "arena",
+            Self::Mvm => "mvm",
+            Self::Passtime => "passtime",
+        }
+    }
+
+    /// Guess the game mode from the prefix of the map name.
+    ///
+    /// This is all we know about a demo before parsing it, so this is used for the demo list.
+    /// `cp_` maps are assumed to be symmetric, since A/D and 5CP maps share the prefix.
+    pub fn from_map_name(map_name: &str) -> Self {
+        let map_name = map_name.to_lowercase();
+        let (prefix, _rest) = map_name.split_once('_').unwrap_or_default();
+
+        match prefix {
+            "cp" => Self::ControlPoints,
+            "koth" => Self::Koth,
+            "pl" => Self::Payload,
+            "plr" => Self::PayloadRace,
+            "ctf" => Self::Ctf,
+            "arena" => Self::Arena,
+            "mvm" => Self::Mvm,
+            "pass" => Self::Passtime,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Values of `m_nGameType` in `DT_TFGameRules`
+mod game_type {
+    pub const CTF: i64 = 1;
+    pub const CP: i64 = 2;
+    pub const ESCORT: i64 = 3;
+    pub const ARENA: i64 = 4;
+    pub const MVM: i64 = 5;
+    pub const PASSTIME: i64 = 7;
+}
+
+/// Collects hints about the game mode from the game rules, map entities and the map name
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default)]
+pub struct GameModeDetector {
+    map_name: String,
+    game_type: Option<i64>,
+    playing_koth: bool,
+    playing_mvm: bool,
+    has_capture_flag: bool,
+    has_passtime_logic: bool,
+    /// Teams of the payload carts (one `CTeamTrainWatcher` per cart)
+    cart_teams: Vec<Team>,
+    num_control_points: usize,
+    /// Owners of the control points when they were first seen, `None` if not seen yet
+    initial_point_owners: Vec<Option<Team>>,
+}
+
+impl GameModeDetector {
+    pub fn set_map_name(&mut self, map_name: &str) {
+        map_name.clone_into(&mut self.map_name);
+    }
+
+    pub fn set_game_type(&mut self, game_type: i64) {
+        self.game_type = Some(game_type);
+    }
+
+    pub fn set_playing_koth(&mut self, playing_koth: bool) {
+        self.playing_koth = playing_koth;
+    }
+
+    pub fn set_playing_mvm(&mut self, playing_mvm: bool) {
+        self.playing_mvm = playing_mvm;
+    }
+
+    pub fn add_capture_flag(&mut self) {
+        self.has_capture_flag = true;
+    }
+
+    pub fn add_passtime_logic(&mut self) {
+        self.has_passtime_logic = true;
+    }
+
+    pub fn add_cart(&mut self, team: Team) {
+        if !self.cart_teams.contains(&team) {
+            self.cart_teams.push(team);
+        }
+    }
+
+    pub fn set_num_control_points(&mut self, num_control_points: usize) {
+        self.num_control_points = num_control_points;
+    }
+
+    /// Record the owner of a control point, unless it was already seen
+    pub fn add_point_owner(&mut self, index: usize, owner: Team) {
+        if index >= self.initial_point_owners.len() {
+            self.initial_point_owners.resize(index + 1, None);
+        }
+
+        self.initial_point_owners[index].get_or_insert(owner);
+    }
+
+    pub fn is_arena(&self) -> bool {
+        self.detect() == GameMode::Arena
+    }
+
+    pub fn is_koth(&self) -> bool {
+        self.detect() == GameMode::Koth
+    }
+
+    pub fn is_mvm(&self) -> bool {
+        self.detect() == GameMode::Mvm
+    }
+
+    pub fn detect(&self) -> GameMode {
+        if self.playing_mvm || self.game_type == Some(game_type::MVM) {
+            return GameMode::Mvm;
+        }
+        if self.has_passtime_logic || self.game_type == Some(game_type::PASSTIME) {
+            return GameMode::Passtime;
+        }
+        // KOTH maps use the control point game type
+        if self.playing_koth {
+            return GameMode::Koth;
+        }
+
+        match self.game_type {
+            Some(game_type::CTF) => GameMode::Ctf,
+            Some(game_type::CP) => self.detect_control_points(),
+            Some(game_type::ESCORT) => {
+                // Payload race has one cart per team
+                if self.cart_teams.len() > 1 {
+                    GameMode::PayloadRace
+                } else {
+                    GameMode::Payload
+                }
+            }
+            Some(game_type::ARENA) => GameMode::Arena,
+            _ if self.has_capture_flag => GameMode::Ctf,
+            _ => GameMode::from_map_name(&self.map_name),
+        }
+    }
+
+    fn detect_control_points(&self) -> GameMode {
+        // On A/D maps, RED owns every point at the start.
+        // 5CP maps start with each team owning their half of the map.
+        // The owner array always has room for 8 points, the rest are unused.
+        let owners = &self.initial_point_owners
+            [..self.num_control_points.min(self.initial_point_owners.len())];
+
+        let is_attack_defend =
+            !owners.is_empty() && owners.iter().all(|owner| *owner == Some(Team::Red));
+
+        if is_attack_defend {
+            GameMode::AttackDefend
+        } else {
+            GameMode::ControlPoints
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_mode_from_map_name() {
+        assert_eq!(
+            GameMode::from_map_name("koth_product_final"),
+            GameMode::Koth
+        );
+        assert_eq!(
+            GameMode::from_map_name("PLR_Hightower"),
+            GameMode::PayloadRace
+        );
+        assert_eq!(GameMode::from_map_name("itemtest"), GameMode::Unknown);
+    }
+
+    #[test]
+    fn test_detect_attack_defend() {
+        let mut detector = GameModeDetector::default();
+        // The map name alone cannot tell A/D and 5CP apart
+        detector.set_map_name("cp_gravelpit");
+        assert_eq!(detector.detect(), GameMode::ControlPoints);
+
+        detector.set_game_type(game_type::CP);
+        detector.set_num_control_points(3);
+        detector.add_point_owner(0, Team::Red);
+        detector.add_point_owner(1, Team::Red);
+        // Points that were captured later don't change the outcome
+        detector.add_point_owner(1, Team::Blue);
+        assert_eq!(detector.detect(), GameMode::AttackDefend);
+
+        detector.add_point_owner(2, Team::Blue);
+        assert_eq!(detector.detect(), GameMode::ControlPoints);
+    }
+
+    #[test]
+    fn test_detect_koth() {
+        let mut detector = GameModeDetector::default();
+        // KOTH maps use the control point game type
+        detector.set_game_type(game_type::CP);
+        detector.set_num_control_points(1);
+        assert_eq!(detector.detect(), GameMode::ControlPoints);
+
+        detector.set_playing_koth(true);
+        assert_eq!(detector.detect(), GameMode::Koth);
+    }
+
+    #[test]
+    fn test_detect_payload() {
+        let mut detector = GameModeDetector::default();
+        detector.set_game_type(game_type::ESCORT);
+        detector.add_cart(Team::Blue);
+        assert_eq!(detector.detect(), GameMode::Payload);
+
+        detector.add_cart(Team::Red);
+        assert_eq!(detector.detect(), GameMode::PayloadRace);
+    }
+
+    #[test]
+    fn test_point_owners_out_of_order() {
+        let mut detector = GameModeDetector::default();
+        detector.set_game_type(game_type::CP);
+        detector.set_num_control_points(3);
+
+        // The owners of later points may be received first
+        detector.add_point_owner(2, Team::Red);
+        assert_eq!(detector.detect(), GameMode::ControlPoints);
+        detector.add_point_owner(0, Team::Red);
+        detector.add_point_owner(1, Team::Red);
+        assert_eq!(detector.detect(), GameMode::AttackDefend);
+    }
+}
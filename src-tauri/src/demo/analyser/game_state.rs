@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::{
+    demo::{
+        data::DemoTick,
+        header::Header,
+        message::Message,
+        packet::{
+            datatable::{ParseSendTable, ServerClass},
+            stringtable::StringTableEntry,
+        },
+        parser::{
+            analyser::{Class, Team, UserId},
+            MessageHandler,
+        },
+    },
+    MessageType, ParserState,
+};
+
+use super::{GameDetailsAnalyser, GameSummary, HighlightConfig, PlayerLifeState, Scoreboard};
+
+/// About one second at the usual tick rate
+pub const CHECKPOINT_INTERVAL: u32 = 66;
+
+/// The state of a single player at a checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlayerGameState {
+    pub user_id: UserId,
+    pub name: String,
+    pub team: Team,
+    #[serde(with = "super::class_name")]
+    pub class: Class,
+    pub alive: bool,
+    /// The scoreboard for the entire match up to this point
+    pub scoreboard: Scoreboard,
+    /// The scoreboard for the current round
+    pub round_scoreboard: Scoreboard,
+}
+
+/// The state of the game at a single tick
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GameState {
+    pub tick: DemoTick,
+    pub round: u32,
+    pub red_team_score: u32,
+    pub blue_team_score: u32,
+    /// Only players that are connected at this tick
+    pub players: Vec<PlayerGameState>,
+}
+
+/// Periodic snapshots of the game state of an entire demo.
+///
+/// Like `DemoPositions`, this is kept separate from the `GameSummary`,
+/// since it is much larger and only needed when seeking through a demo.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct GameStates {
+    /// The number of ticks between two checkpoints
+    pub checkpoint_interval: u32,
+    /// Ordered by tick
+    pub checkpoints: Vec<GameState>,
+}
+
+impl GameStates {
+    /// Get the game state at `tick`, i.e. the last checkpoint at or before it.
+    /// Returns `None` if `tick` is before the first checkpoint.
+    pub fn state_at(&self, tick: DemoTick) -> Option<&GameState> {
+        let index = self
+            .checkpoints
+            .partition_point(|checkpoint| checkpoint.tick <= tick);
+
+        index.checked_sub(1).map(|index| &self.checkpoints[index])
+    }
+}
+
+/// Runs a `GameDetailsAnalyser` and records its state every `checkpoint_interval` ticks,
+/// so the game states are collected in the same pass as the `GameSummary`
+#[derive(Debug, Default)]
+pub struct GameStateAnalyser {
+    inner: GameDetailsAnalyser,
+    last_checkpoint_tick: Option<DemoTick>,
+    output: GameStates,
+}
+
+impl GameStateAnalyser {
+    pub fn new(config: HighlightConfig, checkpoint_interval: u32) -> Self {
+        Self {
+            inner: GameDetailsAnalyser::new(config),
+            last_checkpoint_tick: None,
+            output: GameStates {
+                // An interval of 0 would record a checkpoint on every message
+                checkpoint_interval: checkpoint_interval.max(1),
+                checkpoints: Vec::new(),
+            },
+        }
+    }
+
+    fn record_checkpoint(&mut self, tick: DemoTick) {
+        let is_due = self
+            .last_checkpoint_tick
+            .map_or(true, |last_checkpoint_tick| {
+                u32::from(tick - last_checkpoint_tick) >= self.output.checkpoint_interval
+            });
+
+        // The player list is only meaningful after the first PacketEntities message
+        if !is_due || !self.inner.initial_packet_entities_parsed {
+            return;
+        }
+
+        self.last_checkpoint_tick = Some(tick);
+
+        let round = self.inner.current_round;
+
+        let mut players: Vec<PlayerGameState> = self
+            .inner
+            .players
+            .players
+            .values()
+            .filter(|player| player.connected)
+            .map(|player| PlayerGameState {
+                user_id: player.user_id,
+                name: player.name.clone(),
+                team: player.team,
+                class: player.class,
+                alive: player.life_state == PlayerLifeState::Alive,
+                scoreboard: player.scoreboard.clone(),
+                round_scoreboard: player
+                    .round_scoreboards
+                    .get(&round)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        // HashMap iteration order is random, keep the output deterministic
+        players.sort_by_key(|player| player.user_id);
+
+        self.output.checkpoints.push(GameState {
+            tick,
+            round,
+            red_team_score: self.inner.red_team_score,
+            blue_team_score: self.inner.blue_team_score,
+            players,
+        });
+    }
+}
+
+impl MessageHandler for GameStateAnalyser {
+    type Output = (GameSummary, GameStates);
+
+    fn does_handle(message_type: MessageType) -> bool {
+        GameDetailsAnalyser::does_handle(message_type)
+    }
+
+    fn handle_message(&mut self, message: &Message, tick: DemoTick, parser_state: &ParserState) {
+        self.inner.handle_message(message, tick, parser_state);
+        self.record_checkpoint(tick);
+    }
+
+    fn handle_string_entry(
+        &mut self,
+        table: &str,
+        index: usize,
+        entry: &StringTableEntry,
+        parser_state: &ParserState,
+    ) {
+        self.inner
+            .handle_string_entry(table, index, entry, parser_state);
+    }
+
+    fn handle_data_tables(
+        &mut self,
+        parse_tables: &[ParseSendTable],
+        server_classes: &[ServerClass],
+        parser_state: &ParserState,
+    ) {
+        self.inner
+            .handle_data_tables(parse_tables, server_classes, parser_state);
+    }
+
+    fn handle_header(&mut self, header: &Header) {
+        self.inner.handle_header(header);
+    }
+
+    fn into_output(self, state: &ParserState) -> Self::Output {
+        (self.inner.into_output(state), self.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(tick: u32) -> GameState {
+        GameState {
+            tick: DemoTick::from(tick),
+            round: 0,
+            red_team_score: 0,
+            blue_team_score: 0,
+            players: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_state_at() {
+        let states = GameStates {
+            checkpoint_interval: 66,
+            checkpoints: vec![checkpoint(10), checkpoint(76), checkpoint(142)],
+        };
+
+        assert_eq!(states.state_at(DemoTick::from(0)), None);
+        assert_eq!(
+            states.state_at(DemoTick::from(10)).unwrap().tick,
+            DemoTick::from(10)
+        );
+        assert_eq!(
+            states.state_at(DemoTick::from(100)).unwrap().tick,
+            DemoTick::from(76)
+        );
+        assert_eq!(
+            states.state_at(DemoTick::from(10_000)).unwrap().tick,
+            DemoTick::from(142)
+        );
+    }
+}
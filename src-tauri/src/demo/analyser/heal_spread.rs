@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::parser::analyser::UserId;
+
+/// Key: heal target, value: amount healed
+pub type HealTargets = HashMap<UserId, u32>;
+
+/// Healing done by a single player, broken down by heal target,
+/// for the entire match and for every round.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct HealSpread {
+    pub targets: HealTargets,
+    pub round_targets: HashMap<u32, HealTargets>,
+}
+
+impl HealSpread {
+    pub fn add(&mut self, round: u32, target: UserId, amount: u32) {
+        *self.targets.entry(target).or_default() += amount;
+        *self
+            .round_targets
+            .entry(round)
+            .or_default()
+            .entry(target)
+            .or_default() += amount;
+    }
+
+    /// Replace every user ID by the ID it is aliased to,
+    /// merging the healing of players who reconnected.
+    pub fn resolve_aliases(self, aliases: &HashMap<UserId, UserId>) -> Self {
+        let resolve_targets = |targets: HealTargets| {
+            let mut resolved = HealTargets::new();
+
+            for (target, amount) in targets {
+                let target = aliases.get(&target).copied().unwrap_or(target);
+                *resolved.entry(target).or_default() += amount;
+            }
+
+            resolved
+        };
+
+        Self {
+            targets: resolve_targets(self.targets),
+            round_targets: self
+                .round_targets
+                .into_iter()
+                .map(|(round, targets)| (round, resolve_targets(targets)))
+                .collect(),
+        }
+    }
+}
+
+/// Healing reported during the current tick, before it is credited to heal targets.
+///
+/// The healing scoreboard prop of a medic includes crossbow heals, which are
+/// also reported by their own event. The part of a scoreboard increase that
+/// came from the crossbow is not credited to the medigun target.
+#[derive(Debug, Default)]
+pub struct PendingHealing {
+    // Key: healer, value: medigun target and the increase of the scoreboard
+    medigun: HashMap<UserId, (UserId, u32)>,
+    // Key: healer, value: amount healed with the crossbow
+    crossbow: HashMap<UserId, u32>,
+}
+
+impl PendingHealing {
+    pub fn add_medigun(&mut self, healer: UserId, target: UserId, amount: u32) {
+        let entry = self.medigun.entry(healer).or_insert((target, 0));
+        *entry = (target, entry.1 + amount);
+    }
+
+    pub fn add_crossbow(&mut self, healer: UserId, amount: u32) {
+        *self.crossbow.entry(healer).or_default() += amount;
+    }
+
+    /// Returns the medigun healing of the tick as `(healer, target, amount)`,
+    /// and starts a new tick
+    pub fn take(&mut self) -> Vec<(UserId, UserId, u32)> {
+        let crossbow = std::mem::take(&mut self.crossbow);
+
+        self.medigun
+            .drain()
+            .map(|(healer, (target, amount))| {
+                let crossbow_amount = crossbow.get(&healer).copied().unwrap_or_default();
+                (healer, target, amount.saturating_sub(crossbow_amount))
+            })
+            .filter(|&(_, _, amount)| amount > 0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heal_spread() {
+        let (old_id, new_id, other_id) =
+            (UserId::from(3u16), UserId::from(7u16), UserId::from(5u16));
+
+        let mut heal_spread = HealSpread::default();
+        heal_spread.add(0, old_id, 100);
+        heal_spread.add(1, new_id, 50);
+        heal_spread.add(1, other_id, 20);
+        heal_spread.add(1, other_id, 30);
+
+        let aliases = HashMap::from([(old_id, new_id)]);
+        let resolved = heal_spread.resolve_aliases(&aliases);
+
+        assert_eq!(resolved.targets[&new_id], 150);
+        assert_eq!(resolved.targets[&other_id], 50);
+        assert!(!resolved.targets.contains_key(&old_id));
+        assert_eq!(resolved.round_targets[&0][&new_id], 100);
+        assert_eq!(resolved.round_targets[&1][&other_id], 50);
+    }
+
+    #[test]
+    fn test_pending_healing() {
+        let (medic_id, other_medic_id, target_id) =
+            (UserId::from(2u16), UserId::from(4u16), UserId::from(6u16));
+
+        let mut pending = PendingHealing::default();
+        pending.add_medigun(medic_id, target_id, 30);
+        pending.add_crossbow(medic_id, 75);
+        pending.add_medigun(medic_id, target_id, 60);
+        pending.add_medigun(other_medic_id, target_id, 20);
+        // Crossbow healing of a medic without a medigun target
+        pending.add_crossbow(UserId::from(8u16), 40);
+
+        let mut healed = pending.take();
+        healed.sort_unstable_by_key(|&(healer, _, _)| healer);
+        assert_eq!(
+            healed,
+            vec![(medic_id, target_id, 15), (other_medic_id, target_id, 20)]
+        );
+
+        // Crossbow healing of the previous tick is not subtracted again
+        pending.add_medigun(medic_id, target_id, 10);
+        assert_eq!(pending.take(), vec![(medic_id, target_id, 10)]);
+        assert!(pending.take().is_empty());
+    }
+}
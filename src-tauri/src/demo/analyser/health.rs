@@ -0,0 +1,171 @@
+use tf_demo_parser::demo::data::DemoTick;
+
+/// A player's health dropped to or below the low health threshold,
+/// and they recovered without dying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowHealthSurvival {
+    /// The lowest health the player survived with
+    pub health: u32,
+    /// The tick at which the player had the lowest health
+    pub tick: DemoTick,
+}
+
+/// Damage and overheal of a single life, see `HealthTracker::end_life`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LifeHealth {
+    pub damage_absorbed: u32,
+    /// In game ticks, excluding pauses
+    pub overheal_ticks: u32,
+}
+
+/// Tracks the health of a single player
+#[derive(Debug, Default)]
+pub struct HealthTracker {
+    health: u32,
+    // The game tick since which the player is overhealed
+    overhealed_since: Option<u32>,
+    // Overhealed game ticks of the current life, without the ongoing interval
+    overheal_ticks: u32,
+    damage_absorbed: u32,
+    // The lowest health (and its tick) while at or below the low health threshold
+    low_health: Option<LowHealthSurvival>,
+}
+
+impl HealthTracker {
+    pub fn health(&self) -> u32 {
+        self.health
+    }
+
+    /// `max_health` is 0 while it is unknown
+    pub fn is_overhealed(&self, max_health: u32) -> bool {
+        max_health > 0 && self.health > max_health
+    }
+
+    /// Update the health of a living player.
+    /// A `low_health_threshold` of 0 disables low health survivals.
+    pub fn update(
+        &mut self,
+        health: u32,
+        max_health: u32,
+        low_health_threshold: u32,
+        tick: DemoTick,
+        game_tick: u32,
+    ) -> Option<LowHealthSurvival> {
+        self.health = health;
+
+        match (self.is_overhealed(max_health), self.overhealed_since) {
+            (true, None) => self.overhealed_since = Some(game_tick),
+            (false, Some(since)) => {
+                self.overheal_ticks += game_tick.saturating_sub(since);
+                self.overhealed_since = None;
+            }
+            _ => {}
+        }
+
+        if health == 0 || low_health_threshold == 0 {
+            return None;
+        }
+
+        if health <= low_health_threshold {
+            if self.low_health.map_or(true, |low| health < low.health) {
+                self.low_health = Some(LowHealthSurvival { health, tick });
+            }
+            None
+        } else {
+            self.low_health.take()
+        }
+    }
+
+    pub fn add_damage(&mut self, damage: u32) {
+        self.damage_absorbed += damage;
+    }
+
+    /// Returns the statistics of the life that just ended, and starts a new one
+    pub fn end_life(&mut self, game_tick: u32) -> LifeHealth {
+        if let Some(since) = self.overhealed_since.take() {
+            self.overheal_ticks += game_tick.saturating_sub(since);
+        }
+        self.low_health = None;
+
+        LifeHealth {
+            damage_absorbed: std::mem::take(&mut self.damage_absorbed),
+            overheal_ticks: std::mem::take(&mut self.overheal_ticks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_life_health() {
+        let mut tracker = HealthTracker::default();
+        let max_health = 150;
+
+        tracker.update(150, max_health, 15, DemoTick::from(0), 0);
+        tracker.update(200, max_health, 15, DemoTick::from(10), 10);
+        assert!(tracker.is_overhealed(max_health));
+
+        tracker.add_damage(190);
+        tracker.update(10, max_health, 15, DemoTick::from(50), 50);
+        tracker.add_damage(5);
+        // Overhealed again until the end of the life
+        tracker.update(160, max_health, 15, DemoTick::from(80), 80);
+
+        assert_eq!(
+            tracker.end_life(100),
+            LifeHealth {
+                damage_absorbed: 195,
+                overheal_ticks: 60,
+            }
+        );
+        assert_eq!(tracker.end_life(200), LifeHealth::default());
+    }
+
+    #[test]
+    fn test_low_health_survival() {
+        let mut tracker = HealthTracker::default();
+        let max_health = 150;
+
+        tracker.update(150, max_health, 15, DemoTick::from(0), 0);
+        assert_eq!(
+            tracker.update(10, max_health, 15, DemoTick::from(50), 50),
+            None
+        );
+        assert_eq!(
+            tracker.update(5, max_health, 15, DemoTick::from(60), 60),
+            None
+        );
+        assert_eq!(
+            tracker.update(80, max_health, 15, DemoTick::from(70), 70),
+            Some(LowHealthSurvival {
+                health: 5,
+                tick: DemoTick::from(60),
+            })
+        );
+
+        // A threshold of 0 disables them
+        tracker.update(5, max_health, 0, DemoTick::from(80), 80);
+        assert_eq!(
+            tracker.update(80, max_health, 0, DemoTick::from(90), 90),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dying_is_not_surviving() {
+        let mut tracker = HealthTracker::default();
+        let max_health = 150;
+
+        assert_eq!(
+            tracker.update(3, max_health, 15, DemoTick::from(200), 200),
+            None
+        );
+        tracker.end_life(210);
+        assert_eq!(
+            tracker.update(150, max_health, 15, DemoTick::from(300), 300),
+            None
+        );
+    }
+}
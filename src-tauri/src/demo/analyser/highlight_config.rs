@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use super::{multikill::MultikillConfig, WeaponClass};
+
+/// User-configurable rules for detecting highlights.
+///
+/// This is part of the header of cached `GameSummary`s,
+/// so changing it causes demos to be parsed again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HighlightConfig {
+    /// A `KillStreak` highlight is added every time
+    /// a player's streak reaches a multiple of this
+    pub killstreak_interval: u16,
+
+    /// The minimum streak that results in a `KillStreakEnded` highlight
+    pub killstreak_ended_threshold: u16,
+
+    /// Only hits with these weapons can be airshots
+    pub airshot_weapons: Vec<WeaponClass>,
+
+    pub multikill: MultikillConfig,
+
+    /// The minimum number of ticks a victim must be off the ground for a `Midair`
+    pub midair_min_ticks: u32,
+
+    /// Record the `Midair`s of every player in STV demos.
+    /// Otherwise only the local player's are recorded, so there are none in STV demos.
+    pub stv_midairs: bool,
+
+    /// The minimum change of the view angles (in degrees) for a `Flick`
+    pub flick_min_angle: f32,
+
+    /// How far back (in ticks) to look for a flick before a kill
+    pub flick_window_ticks: u32,
+
+    /// The number of moments in `GameSummary::best_moments`
+    pub best_moments: usize,
+
+    /// A `LowHealthSurvival` is added when a player's health drops
+    /// to or below this and they recover without dying. 0 disables them.
+    pub low_health_threshold: u32,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            killstreak_interval: 5,
+            killstreak_ended_threshold: 10,
+            airshot_weapons: vec![
+                WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+                WeaponClass::TF_WEAPON_ROCKETLAUNCHER_DIRECTHIT,
+                WeaponClass::TF_WEAPON_PARTICLE_CANNON, // Cow mangler
+                WeaponClass::TF_WEAPON_GRENADELAUNCHER,
+                WeaponClass::TF_WEAPON_CANNON, // Loose cannon
+                WeaponClass::TF_WEAPON_CROSSBOW,
+            ],
+            multikill: MultikillConfig::default(),
+            // This ignores small hops
+            midair_min_ticks: 10,
+            stv_midairs: false,
+            flick_min_angle: 45.0,
+            flick_window_ticks: 10,
+            best_moments: 5,
+            low_health_threshold: 10,
+        }
+    }
+}
+
+impl HighlightConfig {
+    pub fn is_killstreak(&self, streak: u16) -> bool {
+        // An interval of 0 disables killstreak highlights
+        self.killstreak_interval != 0 && streak > 0 && streak % self.killstreak_interval == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_config() {
+        // Missing fields fall back to their defaults
+        let config: HighlightConfig =
+            serde_json::from_str(r#"{ "killstreak_interval": 3, "multikill": { "window": 100 } }"#)
+                .unwrap();
+
+        assert_eq!(config.killstreak_ended_threshold, 10);
+        assert_eq!(config.multikill.min_kills, 2);
+        assert_eq!(config.multikill.window, 100);
+
+        assert!(config.is_killstreak(6));
+        assert!(!config.is_killstreak(0));
+        assert!(!config.is_killstreak(5));
+
+        let disabled = HighlightConfig {
+            killstreak_interval: 0,
+            ..config
+        };
+        assert!(!disabled.is_killstreak(6));
+    }
+}
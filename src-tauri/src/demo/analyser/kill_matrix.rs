@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::parser::analyser::UserId;
+
+/// Kills, assists and damage of one player against another
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone)]
+pub struct Matchup {
+    pub kills: u32,
+    pub assists: u32,
+    pub damage: u32,
+}
+
+impl Matchup {
+    fn merge(&mut self, other: &Self) {
+        self.kills += other.kills;
+        self.assists += other.assists;
+        self.damage += other.damage;
+    }
+}
+
+/// Key: attacker, value: matchups against each victim
+pub type Matchups = HashMap<UserId, HashMap<UserId, Matchup>>;
+
+/// Who-killed-whom statistics, for the entire match and for every round.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct KillMatrix {
+    pub matchups: Matchups,
+    pub round_matchups: HashMap<u32, Matchups>,
+}
+
+impl KillMatrix {
+    /// Update the matchup of `attacker` against `victim`, both for the match and the given round
+    pub fn update(
+        &mut self,
+        round: u32,
+        attacker: UserId,
+        victim: UserId,
+        update: impl Fn(&mut Matchup),
+    ) {
+        update(
+            self.matchups
+                .entry(attacker)
+                .or_default()
+                .entry(victim)
+                .or_default(),
+        );
+        update(
+            self.round_matchups
+                .entry(round)
+                .or_default()
+                .entry(attacker)
+                .or_default()
+                .entry(victim)
+                .or_default(),
+        );
+    }
+
+    /// Replace every user ID by the ID it is aliased to,
+    /// merging the matchups of players who reconnected.
+    pub fn resolve_aliases(self, aliases: &HashMap<UserId, UserId>) -> Self {
+        let resolve_matchups = |matchups: Matchups| {
+            let resolve = |user_id: UserId| aliases.get(&user_id).copied().unwrap_or(user_id);
+
+            let mut resolved = Matchups::new();
+
+            for (attacker, victims) in matchups {
+                let resolved_victims = resolved.entry(resolve(attacker)).or_default();
+
+                for (victim, matchup) in victims {
+                    resolved_victims
+                        .entry(resolve(victim))
+                        .or_default()
+                        .merge(&matchup);
+                }
+            }
+
+            resolved
+        };
+
+        Self {
+            matchups: resolve_matchups(self.matchups),
+            round_matchups: self
+                .round_matchups
+                .into_iter()
+                .map(|(round, matchups)| (round, resolve_matchups(matchups)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_aliases() {
+        let (old_id, new_id, victim_id) =
+            (UserId::from(3u16), UserId::from(7u16), UserId::from(5u16));
+
+        let mut kill_matrix = KillMatrix::default();
+        kill_matrix.update(0, old_id, victim_id, |matchup| matchup.kills += 1);
+        kill_matrix.update(1, new_id, victim_id, |matchup| matchup.kills += 1);
+        kill_matrix.update(1, victim_id, old_id, |matchup| matchup.damage += 50);
+
+        let aliases = HashMap::from([(old_id, new_id)]);
+        let resolved = kill_matrix.resolve_aliases(&aliases);
+
+        assert_eq!(resolved.matchups[&new_id][&victim_id].kills, 2);
+        assert_eq!(resolved.matchups[&victim_id][&new_id].damage, 50);
+        assert!(!resolved.matchups.contains_key(&old_id));
+        assert_eq!(resolved.round_matchups[&0][&new_id][&victim_id].kills, 1);
+    }
+}
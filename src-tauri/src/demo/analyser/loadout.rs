@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{
+    data::DemoTick,
+    packet::datatable::{ClassId, ParseSendTable, ServerClass},
+    parser::analyser::Class,
+};
+
+/// The weapons a player had equipped during a life, as item definition indexes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Loadout {
+    #[serde(with = "super::class_name")]
+    pub class: Class,
+    /// Sorted, so that the same weapons always result in the same loadout
+    pub weapons: Vec<u32>,
+}
+
+/// A single life of a player, from spawning until death or the end of the round
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Life {
+    pub end_tick: DemoTick,
+    /// In ticks, excluding pauses
+    pub duration: u32,
+    pub loadout: Loadout,
+    /// Item definition indexes of all wearables, including cosmetics
+    pub wearables: Vec<u32>,
+    /// Includes bonuses and penalties from items
+    pub max_health: u32,
+    /// All damage taken during this life, including self-damage
+    pub damage_absorbed: u32,
+    /// How long the player was overhealed, in ticks excluding pauses
+    pub overheal_ticks: u32,
+}
+
+/// An item (weapon or wearable) equipped by a player
+#[derive(Debug, Clone, Copy)]
+pub struct Item {
+    pub definition_index: u32,
+    pub is_weapon: bool,
+}
+
+/// Find the loadout a player spent the most time with
+pub fn most_used_loadout(lives: &[Life]) -> Option<Loadout> {
+    let mut durations: HashMap<&Loadout, u32> = HashMap::new();
+
+    for life in lives {
+        *durations.entry(&life.loadout).or_default() += life.duration;
+    }
+
+    durations
+        .into_iter()
+        .max_by_key(|(_loadout, duration)| *duration)
+        .map(|(loadout, _duration)| loadout.clone())
+}
+
+/// Find the server classes of all items, and whether they are weapons.
+///
+/// Items are all entities that have an item definition index,
+/// which is part of `DT_ScriptCreatedItem` somewhere in their nested send tables.
+/// Unlike wearables, weapons also include `DT_BaseCombatWeapon`.
+pub fn find_item_classes(
+    parse_tables: &[ParseSendTable],
+    server_classes: &[ServerClass],
+) -> HashMap<ClassId, bool> {
+    let tables: HashMap<&str, &ParseSendTable> = parse_tables
+        .iter()
+        .map(|table| (table.name.as_str(), table))
+        .collect();
+
+    server_classes
+        .iter()
+        .filter(|class| includes_table(&tables, class.data_table.as_str(), "DT_ScriptCreatedItem"))
+        .map(|class| {
+            let is_weapon =
+                includes_table(&tables, class.data_table.as_str(), "DT_BaseCombatWeapon");
+            (class.id, is_weapon)
+        })
+        .collect()
+}
+
+fn includes_table(tables: &HashMap<&str, &ParseSendTable>, table_name: &str, target: &str) -> bool {
+    if table_name == target {
+        return true;
+    }
+
+    tables.get(table_name).is_some_and(|table| {
+        table.props.iter().any(|prop| {
+            prop.table_name
+                .as_ref()
+                .is_some_and(|nested| includes_table(tables, nested.as_str(), target))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(class: Class, weapons: &[u32], duration: u32) -> Life {
+        Life {
+            end_tick: DemoTick::default(),
+            duration,
+            loadout: Loadout {
+                class,
+                weapons: weapons.to_vec(),
+            },
+            wearables: Vec::new(),
+            max_health: 0,
+            damage_absorbed: 0,
+            overheal_ticks: 0,
+        }
+    }
+
+    #[test]
+    fn test_most_used_loadout() {
+        let lives = [
+            life(Class::Demoman, &[19, 20, 132], 1000),
+            life(Class::Demoman, &[20, 132, 1151], 600),
+            life(Class::Demoman, &[20, 132, 1151], 600),
+            life(Class::Medic, &[29, 36, 37], 1100),
+        ];
+
+        let loadout = most_used_loadout(&lives).expect("expected a loadout");
+        assert_eq!(loadout.class, Class::Demoman);
+        assert_eq!(loadout.weapons, vec![20, 132, 1151]);
+
+        assert_eq!(most_used_loadout(&[]), None);
+    }
+
+    #[test]
+    fn test_loadout_bincode() {
+        let loadout = Loadout {
+            class: Class::Sniper,
+            weapons: vec![14, 16, 3],
+        };
+
+        let bytes = bincode::serialize(&loadout).unwrap();
+        assert_eq!(bincode::deserialize::<Loadout>(&bytes).unwrap(), loadout);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, parser::analyser::Team};
+
+/// The format of a match, based on the number of players per team
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchFormat {
+    #[default]
+    Unknown,
+    /// 2v2
+    Ultiduo,
+    /// 4v4
+    Fours,
+    /// 6v6
+    Sixes,
+    /// 9v9
+    Highlander,
+    /// 12v12, or anything close to it
+    Casual,
+}
+
+impl MatchFormat {
+    /// The same name that is used when (de)serializing
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Ultiduo => "ultiduo",
+            Self::Fours => "fours",
+            Self::Sixes => "sixes",
+            Self::Highlander => "highlander",
+            Self::Casual => "casual",
+        }
+    }
+
+    pub fn from_players_per_team(players_per_team: u32) -> Self {
+        match players_per_team {
+            2 => Self::Ultiduo,
+            4 => Self::Fours,
+            6 => Self::Sixes,
+            9 => Self::Highlander,
+            // Casual servers are rarely full
+            10.. => Self::Casual,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// How the match was set up
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchType {
+    #[default]
+    Public,
+    /// A server in tournament mode (`mp_tournament`), like league matches and scrims
+    Tournament,
+    ValveCompetitive,
+    ValveCasual,
+    ValveMvm,
+}
+
+impl MatchType {
+    /// The same name that is used when (de)serializing
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Tournament => "tournament",
+            Self::ValveCompetitive => "valve_competitive",
+            Self::ValveCasual => "valve_casual",
+            Self::ValveMvm => "valve_mvm",
+        }
+    }
+}
+
+/// Values of `m_nMatchGroupType` in `DT_TFGameRules`
+mod match_group {
+    pub const MVM_PRACTICE: i64 = 0;
+    pub const MVM_MANN_UP: i64 = 1;
+    pub const LADDER_6V6: i64 = 2;
+    pub const LADDER_9V9: i64 = 3;
+    pub const LADDER_12V12: i64 = 4;
+    pub const CASUAL_6V6: i64 = 5;
+    pub const CASUAL_9V9: i64 = 6;
+    pub const CASUAL_12V12: i64 = 7;
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MatchInfo {
+    pub format: MatchFormat,
+    pub match_type: MatchType,
+    /// The most common number of players on the larger team at the end of a round
+    pub players_per_team: u32,
+    pub tournament_mode: bool,
+    pub red_team_name: String,
+    pub blue_team_name: String,
+    /// Whether the teams had to ready up before the match started
+    pub ready_up: bool,
+    /// The tick at which the pre-game (waiting for players or ready-up) ended.
+    /// `None` if the recording started after the pre-game or ended before the match started.
+    pub pregame_end_tick: Option<DemoTick>,
+}
+
+/// Collects hints about the match format from the game rules, team entities and player counts
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default)]
+pub struct MatchInfoTracker {
+    red_team_name: String,
+    blue_team_name: String,
+    match_group: Option<i64>,
+    competitive_mode: bool,
+    // The value of `mp_tournament`, if the server sent it
+    tournament_convar: Option<bool>,
+    waiting_for_players: bool,
+    awaiting_ready_restart: bool,
+    ready_up: bool,
+    pregame_end_tick: Option<DemoTick>,
+    // Key: number of players, value: number of samples
+    team_sizes: HashMap<u32, u32>,
+}
+
+impl MatchInfoTracker {
+    pub fn set_team_name(&mut self, team: Team, name: String) {
+        match team {
+            Team::Red => self.red_team_name = name,
+            Team::Blue => self.blue_team_name = name,
+            _ => {}
+        }
+    }
+
+    pub fn set_match_group(&mut self, match_group: i64) {
+        // -1 if the match was not set up by matchmaking
+        self.match_group = (match_group >= 0).then_some(match_group);
+    }
+
+    pub fn set_competitive_mode(&mut self, competitive_mode: bool) {
+        self.competitive_mode = competitive_mode;
+    }
+
+    pub fn set_tournament_convar(&mut self, tournament: bool) {
+        self.tournament_convar = Some(tournament);
+    }
+
+    pub fn set_waiting_for_players(&mut self, waiting_for_players: bool, tick: DemoTick) {
+        let was_pregame = self.is_pregame();
+        self.waiting_for_players = waiting_for_players;
+        self.update_pregame(was_pregame, tick);
+    }
+
+    pub fn set_awaiting_ready_restart(&mut self, awaiting_ready_restart: bool, tick: DemoTick) {
+        let was_pregame = self.is_pregame();
+        self.awaiting_ready_restart = awaiting_ready_restart;
+        self.ready_up |= awaiting_ready_restart;
+        self.update_pregame(was_pregame, tick);
+    }
+
+    fn update_pregame(&mut self, was_pregame: bool, tick: DemoTick) {
+        if was_pregame && !self.is_pregame() {
+            self.pregame_end_tick = Some(tick);
+        }
+    }
+
+    pub fn is_pregame(&self) -> bool {
+        self.waiting_for_players || self.awaiting_ready_restart
+    }
+
+    /// Record the number of players on each team
+    pub fn record_team_sizes(&mut self, red: u32, blue: u32) {
+        *self.team_sizes.entry(red.max(blue)).or_default() += 1;
+    }
+
+    fn is_tournament_mode(&self) -> bool {
+        if let Some(tournament) = self.tournament_convar {
+            return tournament;
+        }
+
+        // Outside of tournament mode, the teams are always called "Red" and "Blue".
+        // In tournament mode, they default to "RED" and "BLU" and can be renamed.
+        let custom_team_names = !self.red_team_name.is_empty()
+            && (self.red_team_name != "Red" || self.blue_team_name != "Blue");
+
+        self.ready_up || self.competitive_mode || custom_team_names
+    }
+
+    pub fn finish(self) -> MatchInfo {
+        let tournament_mode = self.is_tournament_mode();
+
+        let players_per_team = self
+            .team_sizes
+            .iter()
+            // Prefer the larger size on ties, to keep the output deterministic
+            .max_by_key(|&(size, count)| (count, size))
+            .map_or(0, |(size, _count)| *size);
+
+        let match_type = match self.match_group {
+            Some(match_group::MVM_PRACTICE | match_group::MVM_MANN_UP) => MatchType::ValveMvm,
+            Some(match_group::LADDER_6V6 | match_group::LADDER_9V9 | match_group::LADDER_12V12) => {
+                MatchType::ValveCompetitive
+            }
+            Some(match_group::CASUAL_6V6 | match_group::CASUAL_9V9 | match_group::CASUAL_12V12) => {
+                MatchType::ValveCasual
+            }
+            _ if self.competitive_mode => MatchType::ValveCompetitive,
+            _ if tournament_mode => MatchType::Tournament,
+            _ => MatchType::Public,
+        };
+
+        // The match group knows the intended format, even if some players are missing
+        let format = match self.match_group {
+            Some(match_group::LADDER_6V6 | match_group::CASUAL_6V6) => MatchFormat::Sixes,
+            Some(match_group::LADDER_9V9 | match_group::CASUAL_9V9) => MatchFormat::Highlander,
+            Some(match_group::LADDER_12V12 | match_group::CASUAL_12V12) => MatchFormat::Casual,
+            _ => MatchFormat::from_players_per_team(players_per_team),
+        };
+
+        MatchInfo {
+            format,
+            match_type,
+            players_per_team,
+            tournament_mode,
+            red_team_name: self.red_team_name,
+            blue_team_name: self.blue_team_name,
+            ready_up: self.ready_up,
+            pregame_end_tick: self.pregame_end_tick,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_match() {
+        let mut tracker = MatchInfoTracker::default();
+        tracker.set_team_name(Team::Red, "Red".into());
+        tracker.set_team_name(Team::Blue, "Blue".into());
+        tracker.record_team_sizes(12, 11);
+        tracker.record_team_sizes(12, 12);
+
+        let info = tracker.finish();
+        assert_eq!(info.match_type, MatchType::Public);
+        assert_eq!(info.format, MatchFormat::Casual);
+        assert_eq!(info.pregame_end_tick, None);
+    }
+
+    #[test]
+    fn test_tournament_match() {
+        let mut tracker = MatchInfoTracker::default();
+        tracker.set_match_group(-1);
+        tracker.set_team_name(Team::Red, "RED".into());
+        tracker.set_team_name(Team::Blue, "BLU".into());
+        tracker.set_waiting_for_players(true, DemoTick::from(10));
+        tracker.set_waiting_for_players(false, DemoTick::from(20));
+        tracker.set_awaiting_ready_restart(true, DemoTick::from(20));
+        assert!(tracker.is_pregame());
+        tracker.set_awaiting_ready_restart(false, DemoTick::from(500));
+        assert!(!tracker.is_pregame());
+
+        // A substitute joined for one round
+        tracker.record_team_sizes(7, 6);
+        tracker.record_team_sizes(6, 6);
+        tracker.record_team_sizes(6, 6);
+
+        let info = tracker.finish();
+        assert_eq!(info.match_type, MatchType::Tournament);
+        assert_eq!(info.format, MatchFormat::Sixes);
+        assert!(info.ready_up);
+        assert_eq!(info.pregame_end_tick, Some(DemoTick::from(500)));
+    }
+
+    #[test]
+    fn test_tournament_convar() {
+        // Renamed teams, but the server says it's not in tournament mode
+        let mut tracker = MatchInfoTracker::default();
+        tracker.set_team_name(Team::Red, "froyotech".into());
+        tracker.set_team_name(Team::Blue, "Ascent".into());
+        tracker.set_tournament_convar(false);
+
+        let info = tracker.finish();
+        assert!(!info.tournament_mode);
+        assert_eq!(info.match_type, MatchType::Public);
+    }
+
+    #[test]
+    fn test_valve_competitive_match() {
+        let mut tracker = MatchInfoTracker::default();
+        tracker.set_match_group(match_group::LADDER_6V6);
+        tracker.set_competitive_mode(true);
+        tracker.record_team_sizes(5, 6);
+
+        let info = tracker.finish();
+        assert_eq!(info.match_type, MatchType::ValveCompetitive);
+        assert_eq!(info.format, MatchFormat::Sixes);
+    }
+}
@@ -1,11 +1,36 @@
+mod aim;
+mod best_moments;
+mod building;
+mod chat;
+mod class_name;
+mod composition;
+mod condition_timeline;
+mod crit;
 mod custom_damage;
 mod damage_flag;
+mod game_mode;
+mod game_state;
+mod heal_spread;
+mod health;
+mod highlight_config;
+mod kill_matrix;
+mod loadout;
+mod match_info;
+mod multikill;
+mod mvm;
+mod objectives;
+mod ping;
 mod player_condition;
+mod positions;
+mod round;
+mod server;
+mod ubercharge;
+mod voice;
 mod weapon_class;
 
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     ops::{Index, IndexMut},
     str::FromStr,
@@ -21,20 +46,23 @@ use tf_demo_parser::{
     demo::{
         data::{DemoTick, ServerTick, UserInfo},
         gameevent_gen::{
-            CrossbowHealEvent, PlayerConnectClientEvent, PlayerDeathEvent, PlayerDisconnectEvent,
-            PlayerHurtEvent, PlayerSpawnEvent, PlayerTeamEvent, TeamPlayPointCapturedEvent,
-            TeamPlayRoundStalemateEvent, TeamPlayRoundStartEvent, TeamPlayRoundWinEvent,
+            CrossbowHealEvent, MvmBeginWaveEvent, MvmPickupCurrencyEvent, NpcHurtEvent,
+            ObjectDestroyedEvent, ObjectDetonatedEvent, ObjectRemovedEvent, PlayerBuiltObjectEvent,
+            PlayerConnectClientEvent, PlayerDeathEvent, PlayerDisconnectEvent, PlayerHurtEvent,
+            PlayerSpawnEvent, PlayerTeamEvent, PlayerTeleportedEvent, TeamPlayFlagEventEvent,
+            TeamPlayPointCapturedEvent, TeamPlayRoundStalemateEvent, TeamPlayRoundStartEvent,
+            TeamPlayRoundWinEvent,
         },
         gamevent::GameEvent,
         header::Header,
         message::{
             gameevent::GameEventMessage,
-            packetentities::{EntityId, PacketEntity},
-            usermessage::{HudTextLocation, UserMessage},
+            packetentities::{EntityId, PacketEntity, UpdateType},
+            usermessage::{HudTextLocation, SayText2Message, UserMessage},
             Message,
         },
         packet::{
-            datatable::{ParseSendTable, ServerClass, ServerClassName},
+            datatable::{ClassId, ParseSendTable, ServerClass, ServerClassName},
             stringtable::StringTableEntry,
         },
         parser::{
@@ -46,11 +74,70 @@ use tf_demo_parser::{
     MessageType, ParserState, Stream,
 };
 
+pub use best_moments::BestMoment;
+pub use building::{
+    Building, BuildingEnd, BuildingKind, BuildingStats, BuildingUpgrade, TeleporterMode,
+};
+pub use chat::{format_chat_log, ChatLogEntry};
+pub use condition_timeline::{ConditionInterval, TrackedCondition};
+pub use crit::{CritStats, CritType};
 pub use custom_damage::CustomDamage;
 pub use damage_flag::DamageFlag;
+pub use game_mode::GameMode;
+pub use game_state::{GameState, GameStateAnalyser, GameStates, CHECKPOINT_INTERVAL};
+pub use heal_spread::HealSpread;
+pub use highlight_config::HighlightConfig;
+pub use kill_matrix::KillMatrix;
+pub use loadout::{Life, Loadout};
+pub use match_info::MatchInfo;
+pub use mvm::{MvmPlayerStats, WaveOutcome, WaveSummary};
+pub use objectives::{FlagEventKind, Objectives};
 pub use player_condition::PlayerCondition;
+pub use positions::{DemoPositions, PositionAnalyser};
+pub use round::{RoundSummary, WinReason};
+pub use server::ServerInfo;
+pub use ubercharge::{Ubercharge, UberchargeKind, UberchargeOutcome};
+pub use voice::{VoiceExtractor, VoiceInterval};
 pub use weapon_class::WeaponClass;
 
+use aim::ViewAngleHistory;
+use best_moments::find_best_moments;
+use building::is_sentry_kill;
+use chat::ChatKind;
+use composition::{ClassChange, CompositionTracker, TeamComposition};
+use condition_timeline::{total_durations, ConditionTimeline};
+use crit::{CritContext, CRIT_BOOST_CONDITIONS};
+use game_mode::GameModeDetector;
+use heal_spread::PendingHealing;
+use health::{HealthTracker, LifeHealth, LowHealthSurvival};
+use loadout::{find_item_classes, most_used_loadout, Item};
+use match_info::MatchInfoTracker;
+use multikill::{Multikill, MultikillTracker};
+use mvm::{MvmTracker, RobotKind};
+use objectives::ObjectiveTracker;
+use ping::{PingSpike, PingStats, PingTracker};
+use round::{PointCapture, RoundEnd, RoundTracker};
+use ubercharge::PendingUbercharge;
+use voice::{VoiceTracker, VOICE_GAP_SECONDS};
+
+/// Extract the entity index from an entity handle (`EHANDLE`) prop.
+/// The lower 11 bits hold the entity index, the rest is a serial number.
+fn entity_id_from_handle(value: &SendPropValue) -> Option<EntityId> {
+    const INDEX_MASK: i64 = (1 << 11) - 1;
+
+    let index = i64::try_from(value).ok()? & INDEX_MASK;
+
+    // An invalid handle has all bits set
+    if index == INDEX_MASK {
+        None
+    } else {
+        u32::try_from(index).ok().map(EntityId::from)
+    }
+}
+
+/// Set in `death_flags` of the `player_death` event when a spy fakes their death
+const TF_DEATH_FEIGN_DEATH: u16 = 0x0020;
+
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, FromPrimitive)]
 pub enum PlayerLifeState {
     #[default]
@@ -109,6 +196,9 @@ pub enum Highlight {
         streak: usize,
         drop: bool,
         airshot: bool,
+        crit: CritType,
+        /// A full crit without a crit boost, headshot or backstab
+        random_crit: bool,
     },
     KillStreak {
         player: HighlightPlayerSnapshot,
@@ -122,6 +212,8 @@ pub enum Highlight {
     ChatMessage {
         sender: HighlightPlayerSnapshot,
         text: String,
+        kind: ChatKind,
+        sender_alive: bool,
     },
     Message {
         text: String,
@@ -147,7 +239,7 @@ pub enum Highlight {
     },
     RoundWin {
         winner: Team,
-        // TODO: Win reason?
+        win_reason: Option<WinReason>,
     },
     PlayerConnected {
         player: HighlightPlayerSnapshot,
@@ -163,15 +255,115 @@ pub enum Highlight {
     Pause {
         pause: bool,
     },
-    // TODO:
-    // Multikill?
-    // Midair kills?
-    // Flicks?
+    /// A period of unusually high ping
+    PingSpike {
+        player: HighlightPlayerSnapshot,
+        /// In milliseconds
+        peak: u32,
+        /// The average ping before the spike, in milliseconds
+        baseline: u32,
+        start_tick: DemoTick,
+        end_tick: DemoTick,
+    },
+    Multikill {
+        killer: HighlightPlayerSnapshot,
+        victims: Vec<HighlightPlayerSnapshot>,
+        weapons: Vec<String>,
+        start_tick: DemoTick,
+        end_tick: DemoTick,
+    },
+    UberBuilt {
+        medic: HighlightPlayerSnapshot,
+    },
+    UberUsed {
+        medic: HighlightPlayerSnapshot,
+        target: Option<HighlightPlayerSnapshot>,
+        kind: UberchargeKind,
+        /// How long the full charge was held before it was used, excluding pauses
+        held_ticks: u32,
+    },
+    BuildingDestroyed {
+        builder: HighlightPlayerSnapshot,
+        attacker: HighlightPlayerSnapshot,
+        assister: Option<HighlightPlayerSnapshot>,
+        kind: BuildingKind,
+        level: u8,
+        weapon: String,
+    },
+    /// A kill on a player who was off the ground without blast jumping
+    Midair {
+        killer: HighlightPlayerSnapshot,
+        victim: HighlightPlayerSnapshot,
+        kill_icon: String,
+        /// How long the victim had been off the ground, in ticks
+        airborne_ticks: u32,
+        killer_airborne: bool,
+    },
+    /// A CTF flag was picked up, captured, defended, dropped or returned
+    Flag {
+        /// `None` if the flag returned on its own
+        player: Option<HighlightPlayerSnapshot>,
+        /// The team that owns the flag
+        team: Team,
+        kind: FlagEventKind,
+    },
+    /// The only player left alive on their team in an arena round
+    LastManStanding {
+        player: HighlightPlayerSnapshot,
+        enemies_alive: u32,
+    },
+    /// A Mann vs. Machine wave started. Waves are zero-based.
+    WaveStart {
+        wave: u16,
+        max_waves: u16,
+    },
+    WaveComplete {
+        wave: u16,
+    },
+    WaveFailed {
+        wave: u16,
+    },
+    /// A kill right after a sudden change of the local player's view angles
+    Flick {
+        killer: HighlightPlayerSnapshot,
+        victim: HighlightPlayerSnapshot,
+        kill_icon: String,
+        /// In degrees
+        angle: f32,
+        /// How many ticks the flick took
+        ticks: u32,
+    },
+    /// A player survived with low health, see `HighlightConfig::low_health_threshold`
+    LowHealthSurvival {
+        player: HighlightPlayerSnapshot,
+        /// The lowest health the player survived with
+        health: u32,
+    },
+    /// A kill by an overhealed player
+    OverhealedKill {
+        killer: HighlightPlayerSnapshot,
+        victim: HighlightPlayerSnapshot,
+        health: u32,
+        max_health: u32,
+        /// The medic who was healing the killer, if any
+        medic: Option<HighlightPlayerSnapshot>,
+    },
+    /// A player spawned as a different class than in their previous life
+    ClassChange {
+        player: HighlightPlayerSnapshot,
+        #[serde(with = "class_name")]
+        from: Class,
+        #[serde(with = "class_name")]
+        to: Class,
+    },
 }
 
 #[derive(Default, Debug, Deserialize, Serialize, PartialEq)]
 pub struct GameSummary {
     pub local_user_id: UserId,
+    pub game_mode: GameMode,
+    pub match_info: MatchInfo,
+    pub server: ServerInfo,
     pub highlights: Vec<HighlightEvent>,
     pub red_team_score: u32,
     pub blue_team_score: u32,
@@ -179,6 +371,33 @@ pub struct GameSummary {
     pub num_rounds: u32,
     pub players: Vec<PlayerSummary>,
     pub aliases: HashMap<UserId, UserId>,
+    pub ubercharges: Vec<Ubercharge>,
+    pub kill_matrix: KillMatrix,
+    pub rounds: Vec<RoundSummary>,
+    pub pauses: Vec<PauseInterval>,
+    pub buildings: Vec<Building>,
+    /// The most interesting moments of the demo, ordered by their score
+    pub best_moments: Vec<BestMoment>,
+    /// KOTH timers and payload progress
+    pub objectives: Objectives,
+    /// Mann vs. Machine waves, empty in other game modes
+    pub waves: Vec<WaveSummary>,
+    /// Who was talking when, ordered by the start of each interval
+    pub voice: Vec<VoiceInterval>,
+    /// Every chat message and name change, ordered by tick
+    pub chat_log: Vec<ChatLogEntry>,
+    /// Ordered by tick
+    pub class_changes: Vec<ClassChange>,
+    /// The classes of both teams whenever they changed, ordered by tick
+    pub team_compositions: Vec<TeamComposition>,
+}
+
+/// A period of time during which the game was paused
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct PauseInterval {
+    pub start_tick: DemoTick,
+    /// `None` if the demo ended while the game was paused
+    pub end_tick: Option<DemoTick>,
 }
 
 /**
@@ -205,6 +424,16 @@ pub struct Scoreboard {
     pub damage_dealt: u32,
 }
 
+/// Damage and kill statistics of a single player with a single weapon class
+#[derive(Debug, Serialize, Deserialize, PartialEq, Default, Clone)]
+pub struct WeaponStats {
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub hits: u32,
+}
+
 /// Store something for each of the nine classes, and the "other" class
 #[derive(Debug, Default, PartialEq)]
 pub struct Classes<T>([T; 10]);
@@ -257,6 +486,7 @@ impl<T> IndexMut<Team> for Teams<T> {
     }
 }
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Default)]
 pub struct PlayerState {
     name: String,
@@ -264,17 +494,62 @@ pub struct PlayerState {
     user_id: UserId,
     entity_id: EntityId,
 
+    /// `false` after the player left the game
+    connected: bool,
+    /// Bots, including the robots in Mann vs. Machine
+    is_bot: bool,
+    /// Players who left before the match started are not included in the output
+    left_during_pregame: bool,
+
     /// The scoreboard for the entire match
     scoreboard: Scoreboard,
 
     /// Per-round scoreboards
     round_scoreboards: HashMap<u32, Scoreboard>,
 
+    /// Damage and kills, broken down by weapon class
+    weapon_stats: HashMap<WeaponClass, WeaponStats>,
+
+    /// Statistics of the buildings this player built as an engineer
+    building_stats: HashMap<BuildingKind, BuildingStats>,
+
+    /// Healing done to each heal target
+    heal_spread: HealSpread,
+
+    /// Damage and kills dealt with crits and minicrits
+    crit_stats: CritStats,
+
+    condition_timeline: ConditionTimeline,
+
+    lives: Vec<Life>,
+
+    mvm_stats: MvmPlayerStats,
+
+    ping: PingTracker,
+
+    // The last value of `m_iScore` in the player resource
+    score: u32,
+
+    health: HealthTracker,
+
     // Temporary state data
     class: Class,
     team: Team,
     life_state: PlayerLifeState,
     charge: u8,
+    heal_target: Option<EntityId>,
+    // Only meaningful for MvM robots
+    robot_kind: RobotKind,
+    // Includes bonuses and penalties from items
+    max_health: u32,
+    // `m_bConnected` of the player resource, `false` while the player is still connecting
+    resource_connected: bool,
+
+    // Key: entity ID of the item
+    items: HashMap<EntityId, Item>,
+
+    // The game tick at which the player left the ground, `None` while on the ground
+    airborne_since: Option<u32>,
 
     player_cond: u32,
     player_cond_ex: u32,
@@ -286,12 +561,12 @@ pub struct PlayerState {
     // in this variable (bit 11), everything else is in player_cond.
     condition_bits: u32,
 
-    // TODO use server tick instead
+    // The game tick (see `GameDetailsAnalyser::game_tick`) of the last spawn.
     // Demo ticks continue running while the game is paused,
-    // making the class play durations inaccurate
-    last_spawn_tick: Option<DemoTick>,
+    // which would make the class play durations inaccurate.
+    last_spawn_tick: Option<u32>,
 
-    // Track time (ticks) spent on each class/team
+    // Track time (game ticks) spent on each class/team
     time_on_class: Classes<usize>,
     time_on_team: Teams<usize>,
 }
@@ -315,22 +590,31 @@ impl PlayerState {
         }
     }
 
-    #[allow(dead_code)]
-    fn format_conditions(&self) -> Vec<PlayerCondition> {
-        let mut conditions = Vec::new();
-        for i in 0..128u32 {
-            if let Some(cond) = PlayerCondition::from_u32(i) {
-                if self.has_cond(cond) {
-                    conditions.push(cond);
-                }
-            }
-        }
-        conditions
+    pub fn has_any_cond(&self, conds: &[PlayerCondition]) -> bool {
+        conds.iter().any(|&cond| self.has_cond(cond))
+    }
+
+    pub fn is_crit_boosted(&self) -> bool {
+        self.has_any_cond(&CRIT_BOOST_CONDITIONS)
     }
 
-    fn handle_life_end(&mut self, teams_switched: bool, tick: DemoTick) {
+    fn reset_stats(&mut self) {
+        self.scoreboard = Scoreboard::default();
+        self.round_scoreboards.clear();
+        self.weapon_stats.clear();
+        self.building_stats.clear();
+        self.heal_spread = HealSpread::default();
+        self.crit_stats = CritStats::default();
+    }
+
+    fn handle_life_end(&mut self, teams_switched: bool, game_tick: u32, tick: DemoTick) {
+        let LifeHealth {
+            damage_absorbed,
+            overheal_ticks,
+        } = self.health.end_life(game_tick);
+
         if let Some(last_spawn_tick) = self.last_spawn_tick {
-            let life_duration = u32::from(tick - last_spawn_tick) as usize;
+            let life_duration = game_tick.saturating_sub(last_spawn_tick);
 
             let team = if teams_switched {
                 match self.team {
@@ -342,8 +626,21 @@ impl PlayerState {
                 self.team
             };
 
-            self.time_on_class[self.class] += life_duration;
-            self.time_on_team[team] += life_duration;
+            self.time_on_class[self.class] += life_duration as usize;
+            self.time_on_team[team] += life_duration as usize;
+
+            self.lives.push(Life {
+                end_tick: tick,
+                duration: life_duration,
+                loadout: Loadout {
+                    class: self.class,
+                    weapons: self.item_definition_indexes(true),
+                },
+                wearables: self.item_definition_indexes(false),
+                max_health: self.max_health,
+                damage_absorbed,
+                overheal_ticks,
+            });
 
             // Prevent this life from contributing to class playtime a
             // second time, for example by dying after a round ended
@@ -351,6 +648,18 @@ impl PlayerState {
         }
     }
 
+    fn item_definition_indexes(&self, weapons: bool) -> Vec<u32> {
+        let mut definition_indexes: Vec<u32> = self
+            .items
+            .values()
+            .filter(|item| item.is_weapon == weapons)
+            .map(|item| item.definition_index)
+            .collect();
+
+        definition_indexes.sort_unstable();
+        definition_indexes
+    }
+
     fn snapshot(&self) -> HighlightPlayerSnapshot {
         HighlightPlayerSnapshot {
             user_id: self.user_id,
@@ -374,6 +683,37 @@ pub struct PlayerSummary {
 
     /// Per-round scoreboards
     round_scoreboards: HashMap<u32, Scoreboard>,
+
+    /// Damage and kills, broken down by weapon class
+    weapon_stats: HashMap<WeaponClass, WeaponStats>,
+
+    /// Statistics of the buildings this player built as an engineer
+    building_stats: HashMap<BuildingKind, BuildingStats>,
+
+    /// Healing done to each heal target
+    heal_spread: HealSpread,
+
+    /// Damage and kills dealt with crits and minicrits
+    crit_stats: CritStats,
+
+    /// Intervals of selected conditions, ordered by their start
+    conditions: Vec<ConditionInterval>,
+    /// Total time (game ticks) spent with each condition
+    condition_time: HashMap<TrackedCondition, u32>,
+
+    lives: Vec<Life>,
+    most_used_loadout: Option<Loadout>,
+
+    /// Only filled in Mann vs. Machine demos
+    mvm_stats: MvmPlayerStats,
+
+    /// `None` for bots
+    ping: Option<PingStats>,
+
+    /// The score on the in-game scoreboard at the end of the demo.
+    /// Unlike `scoreboard.points`, which is only sent for the local player,
+    /// this is known for every player.
+    score: u32,
 }
 
 impl From<PlayerState> for PlayerSummary {
@@ -384,6 +724,15 @@ impl From<PlayerState> for PlayerSummary {
             user_id,
             scoreboard,
             round_scoreboards,
+            weapon_stats,
+            building_stats,
+            heal_spread,
+            crit_stats,
+            condition_timeline,
+            lives,
+            mvm_stats,
+            ping,
+            score,
             time_on_class,
             time_on_team,
             ..
@@ -392,6 +741,9 @@ impl From<PlayerState> for PlayerSummary {
         let time_on_team = time_on_team.into_real_teams();
         let time_on_class = time_on_class.into_real_classes();
         let steam_id = steam_id.to_string();
+        let most_used_loadout = most_used_loadout(&lives);
+        let conditions = condition_timeline.into_intervals();
+        let condition_time = total_durations(&conditions);
 
         Self {
             name,
@@ -401,6 +753,17 @@ impl From<PlayerState> for PlayerSummary {
             time_on_team,
             scoreboard,
             round_scoreboards,
+            weapon_stats,
+            building_stats,
+            heal_spread,
+            crit_stats,
+            conditions,
+            condition_time,
+            lives,
+            most_used_loadout,
+            mvm_stats,
+            ping: ping.finish(),
+            score,
         }
     }
 }
@@ -457,24 +820,47 @@ impl Players {
             .unwrap_or_default()
     }
 
+    /// If `exclude_bots` is set, bots are left out of the player summaries (used for robots)
     pub fn finish(
         mut self,
         teams_switched: bool,
+        exclude_bots: bool,
+        game_tick: u32,
         tick: DemoTick,
     ) -> (Vec<PlayerSummary>, HashMap<UserId, UserId>) {
         for player in self.alive_players_mut() {
-            player.handle_life_end(teams_switched, tick);
+            player.handle_life_end(teams_switched, game_tick, tick);
+        }
+        for player in self.players.values_mut() {
+            player.condition_timeline.end_all(None, game_tick);
         }
 
         let Self { players, aliases } = self;
-        let player_summaries = players.into_values().map(PlayerSummary::from).collect();
+        let player_summaries = players
+            .into_values()
+            .filter(|player| !player.left_during_pregame)
+            .filter(|player| !(exclude_bots && player.is_bot))
+            .map(|mut player| {
+                player.heal_spread =
+                    std::mem::take(&mut player.heal_spread).resolve_aliases(&aliases);
+                PlayerSummary::from(player)
+            })
+            .collect();
 
         (player_summaries, aliases)
     }
 
-    pub fn player_leave(&mut self, teams_switched: bool, user_id: UserId, tick: DemoTick) {
+    pub fn player_leave(
+        &mut self,
+        teams_switched: bool,
+        user_id: UserId,
+        game_tick: u32,
+        tick: DemoTick,
+    ) {
         if let Some(player) = self.get_mut(user_id) {
-            player.handle_life_end(teams_switched, tick);
+            player.handle_life_end(teams_switched, game_tick, tick);
+            player.condition_timeline.end_all(Some(tick), game_tick);
+            player.connected = false;
         }
     }
 
@@ -484,6 +870,10 @@ impl Players {
             .filter(|player| player.life_state == PlayerLifeState::Alive)
     }
 
+    pub fn all_mut(&mut self) -> impl Iterator<Item = &mut PlayerState> {
+        self.players.values_mut()
+    }
+
     fn find_previous_user_id(&self, steam_id: u64) -> Option<UserId> {
         // Bot accounts all have the same steamID (0)
         if steam_id == 0 {
@@ -509,6 +899,7 @@ impl Players {
         let name = user_info.player_info.name;
         let user_id = user_info.player_info.user_id;
         let entity_id = user_info.entity_id;
+        let is_bot = user_info.player_info.is_fake_player != 0;
 
         if let Some(player) = self.players.get_mut(&user_id) {
             // Not sure if these can ever change during a game.
@@ -554,6 +945,8 @@ impl Players {
 
             player.user_id = user_id;
             player.entity_id = entity_id;
+            player.connected = true;
+            player.left_during_pregame = false;
             player.life_state = PlayerLifeState::Death;
 
             self.players.insert(user_id, player);
@@ -575,6 +968,8 @@ impl Players {
                     steam_id,
                     user_id,
                     entity_id,
+                    connected: true,
+                    is_bot,
                     life_state: PlayerLifeState::Death,
                     ..Default::default()
                 },
@@ -585,22 +980,62 @@ impl Players {
 
 #[derive(Default, Debug)]
 pub struct GameDetailsAnalyser {
+    config: HighlightConfig,
     highlights: Vec<HighlightEvent>,
     interval_per_tick: f32,
     is_stv: bool,
     players: Players,
     class_names: Vec<ServerClassName>,
     mediguns: HashMap<u32, EntityId>,
+    // Key: server class of the item, value: whether it is a weapon
+    item_classes: HashMap<ClassId, bool>,
+    // Key: entity ID of the item, value: entity ID of its owner
+    item_owners: HashMap<EntityId, EntityId>,
     red_team_entity_id: EntityId,
     blue_team_entity_id: EntityId,
     red_team_score: u32,
     blue_team_score: u32,
     teams_switched: bool,
     local_entity_id: EntityId,
+    // Only tracked in POV demos
+    local_view_angles: ViewAngleHistory,
 
     initial_packet_entities_parsed: bool,
 
     current_round: u32,
+    rounds: RoundTracker,
+
+    game_mode: GameModeDetector,
+    objectives: ObjectiveTracker,
+    mvm: MvmTracker,
+    match_info: MatchInfoTracker,
+    server: ServerInfo,
+    voice: VoiceTracker,
+    chat_log: Vec<ChatLogEntry>,
+    class_changes: Vec<ClassChange>,
+    compositions: CompositionTracker,
+
+    multikills: MultikillTracker,
+
+    kill_matrix: KillMatrix,
+
+    pending_healing: PendingHealing,
+
+    ubercharges: Vec<Ubercharge>,
+    // Key: user ID of the medic
+    pending_ubercharges: HashMap<UserId, PendingUbercharge>,
+
+    buildings: Vec<Building>,
+    // Key: entity ID of the building, value: index into `buildings`
+    active_buildings: HashMap<EntityId, usize>,
+    // Indexes into `buildings` of the buildings that had a player_builtobject event
+    built_buildings: HashSet<usize>,
+
+    pauses: Vec<PauseInterval>,
+    // Server ticks that passed while the game was paused.
+    // These are used to measure time in "game ticks".
+    pause_start_server_tick: Option<u32>,
+    paused_server_ticks: u32,
 
     server_tick: ServerTick,
     demo_tick: DemoTick,
@@ -616,22 +1051,37 @@ impl MessageHandler for GameDetailsAnalyser {
                 | MessageType::GameEvent
                 | MessageType::SetPause
                 | MessageType::ServerInfo
+                | MessageType::SetConVar
+                | MessageType::VoiceData
                 | MessageType::UserMessage
                 | MessageType::NetTick
         )
     }
 
     fn handle_message(&mut self, message: &Message, tick: DemoTick, parser_state: &ParserState) {
+        if tick != self.demo_tick {
+            self.flush_pending_healing();
+        }
         self.demo_tick = tick;
         match message {
             Message::NetTick(message) => {
                 self.server_tick = message.tick;
             }
             Message::PacketEntities(message) => {
+                if !self.initial_packet_entities_parsed {
+                    // The recording may have started in the middle of a round
+                    self.rounds
+                        .start_round(self.current_round, tick, self.game_tick());
+                }
                 self.initial_packet_entities_parsed = true;
                 for entity in &message.entities {
                     self.handle_entity(entity, parser_state);
                 }
+                for entity_id in &message.removed_entities {
+                    self.remove_item(*entity_id);
+                    self.objectives.remove_entity(*entity_id);
+                    self.mvm.remove_tank(*entity_id);
+                }
             }
             Message::GameEvent(GameEventMessage { event, .. }) => {
                 // Sigh...
@@ -649,6 +1099,7 @@ impl MessageHandler for GameDetailsAnalyser {
                 self.handle_usermessage(message);
             }
             Message::SetPause(message) => {
+                self.handle_pause(message.pause);
                 self.add_highlight(Highlight::Pause {
                     pause: message.pause,
                 });
@@ -656,6 +1107,28 @@ impl MessageHandler for GameDetailsAnalyser {
             Message::ServerInfo(message) => {
                 self.local_entity_id = EntityId::from(u32::from(message.player_slot) + 1);
                 self.interval_per_tick = message.interval_per_tick;
+                self.server.set_server_info(message);
+            }
+            Message::VoiceData(message) => {
+                // `client` is the player slot, which is one less than the entity ID
+                let entity_id = EntityId::from(u32::from(message.client) + 1);
+
+                if let Some(player) = self.players.get_by_entity_id(entity_id) {
+                    let max_gap = self.seconds_to_ticks(VOICE_GAP_SECONDS);
+                    self.voice.record_packet(player.user_id, tick, max_gap);
+                }
+            }
+            Message::SetConVar(message) => {
+                // The initial values are sent before the first PacketEntities message
+                let tick = self.initial_packet_entities_parsed.then_some(tick);
+
+                for convar in &message.vars {
+                    self.server.set_convar(&convar.key, &convar.value, tick);
+                }
+
+                if let Some(tournament) = self.server.convar("mp_tournament") {
+                    self.match_info.set_tournament_convar(tournament != "0");
+                }
             }
             _ => {}
         }
@@ -679,7 +1152,7 @@ impl MessageHandler for GameDetailsAnalyser {
 
     fn handle_data_tables(
         &mut self,
-        _parse_tables: &[ParseSendTable],
+        parse_tables: &[ParseSendTable],
         server_classes: &[ServerClass],
         _parser_state: &ParserState,
     ) {
@@ -688,15 +1161,25 @@ impl MessageHandler for GameDetailsAnalyser {
             .map(|class| &class.name)
             .cloned()
             .collect();
+        self.item_classes = find_item_classes(parse_tables, server_classes);
     }
 
     fn handle_header(&mut self, header: &Header) {
         // STV demos have an empty server field in their header
         self.is_stv = header.server.is_empty();
+        self.game_mode.set_map_name(&header.map);
     }
 
-    fn into_output(self, _state: &ParserState) -> Self::Output {
+    fn into_output(mut self, _state: &ParserState) -> Self::Output {
+        self.flush_multikills();
+        self.flush_pending_healing();
+        self.finish_ubercharges();
+        self.end_ping_spikes();
+
+        let game_tick = self.game_tick();
+
         let Self {
+            config,
             highlights,
             interval_per_tick,
             players,
@@ -705,6 +1188,20 @@ impl MessageHandler for GameDetailsAnalyser {
             local_entity_id,
             current_round,
             demo_tick,
+            ubercharges,
+            kill_matrix,
+            rounds,
+            pauses,
+            buildings,
+            game_mode,
+            objectives,
+            mvm,
+            match_info,
+            server,
+            voice,
+            chat_log,
+            class_changes,
+            compositions,
             ..
         } = self;
 
@@ -713,17 +1210,48 @@ impl MessageHandler for GameDetailsAnalyser {
             .map(|player| player.user_id)
             .unwrap_or_default();
 
-        let (players, aliases) = players.finish(self.teams_switched, demo_tick);
+        let game_mode = game_mode.detect();
+
+        let (players, aliases) = players.finish(
+            self.teams_switched,
+            game_mode == GameMode::Mvm,
+            game_tick,
+            demo_tick,
+        );
+
+        let rounds = rounds.finish(game_tick);
+        let best_moments = find_best_moments(
+            &highlights,
+            &rounds,
+            interval_per_tick,
+            config.multikill.window,
+            config.best_moments,
+        );
 
         Self::Output {
             local_user_id,
+            game_mode,
+            match_info: match_info.finish(),
+            server,
             highlights,
             red_team_score,
             blue_team_score,
             interval_per_tick,
             players,
             num_rounds: current_round,
+            kill_matrix: kill_matrix.resolve_aliases(&aliases),
             aliases,
+            ubercharges,
+            rounds,
+            pauses,
+            buildings,
+            best_moments,
+            objectives: objectives.finish(),
+            waves: mvm.finish(game_tick),
+            voice: voice.finish(),
+            chat_log,
+            class_changes,
+            team_compositions: compositions.finish(),
         }
     }
 }
@@ -775,12 +1303,105 @@ macro_rules! process_score_prop {
 }
 
 impl GameDetailsAnalyser {
+    pub fn new(config: HighlightConfig) -> Self {
+        Self {
+            multikills: MultikillTracker::new(config.multikill),
+            config,
+            ..Default::default()
+        }
+    }
+
     fn add_highlight(&mut self, event: Highlight) {
         let tick = self.demo_tick;
         self.highlights.push(HighlightEvent { tick, event });
     }
 
+    /// Insert a highlight that belongs to an earlier tick,
+    /// keeping the highlights sorted by tick.
+    fn insert_highlight(&mut self, tick: DemoTick, event: Highlight) {
+        let index = self
+            .highlights
+            .partition_point(|highlight| highlight.tick <= tick);
+        self.highlights
+            .insert(index, HighlightEvent { tick, event });
+    }
+
+    /// The current server tick, not counting ticks that passed while the game was paused.
+    ///
+    /// Demo ticks keep running while the game is paused, so all durations
+    /// in the summary are measured in game ticks instead.
+    fn game_tick(&self) -> u32 {
+        let server_tick = u32::from(self.server_tick);
+        let paused_ticks = self.paused_server_ticks
+            + self
+                .pause_start_server_tick
+                .map_or(0, |start| server_tick.saturating_sub(start));
+
+        server_tick.saturating_sub(paused_ticks)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn seconds_to_ticks(&self, seconds: f32) -> u32 {
+        if self.interval_per_tick > 0.0 {
+            (seconds / self.interval_per_tick) as u32
+        } else {
+            // Assume the usual 66 ticks per second
+            (seconds * 66.0) as u32
+        }
+    }
+
+    fn handle_pause(&mut self, pause: bool) {
+        let server_tick = u32::from(self.server_tick);
+
+        if pause {
+            if self.pause_start_server_tick.is_none() {
+                self.pause_start_server_tick = Some(server_tick);
+                self.pauses.push(PauseInterval {
+                    start_tick: self.demo_tick,
+                    end_tick: None,
+                });
+            }
+        } else if let Some(start) = self.pause_start_server_tick.take() {
+            self.paused_server_ticks += server_tick.saturating_sub(start);
+
+            if let Some(pause) = self.pauses.last_mut() {
+                pause.end_tick = Some(self.demo_tick);
+            }
+        }
+    }
+
+    fn add_multikill_highlight(&mut self, multikill: Multikill) {
+        let Multikill {
+            killer,
+            victims,
+            weapons,
+            start_tick,
+            end_tick,
+        } = multikill;
+
+        self.insert_highlight(
+            end_tick,
+            Highlight::Multikill {
+                killer,
+                victims,
+                weapons,
+                start_tick,
+                end_tick,
+            },
+        );
+    }
+
+    fn flush_multikills(&mut self) {
+        for multikill in self.multikills.flush() {
+            self.add_multikill_highlight(multikill);
+        }
+    }
+
     pub fn handle_entity(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        if let Some(&is_weapon) = self.item_classes.get(&entity.server_class) {
+            self.handle_item_entity(entity, parser_state, is_weapon);
+        }
+
         let class_name: &str = self
             .class_names
             .get(usize::from(entity.server_class))
@@ -791,6 +1412,21 @@ impl GameDetailsAnalyser {
             "CTFTeam" => self.handle_team(entity, parser_state),
             "CWeaponMedigun" => self.handle_medigun(entity, parser_state),
             "CTFGameRulesProxy" => self.handle_game_rules(entity, parser_state),
+            "CTFObjectiveResource" => self.handle_objective_resource(entity, parser_state),
+            "CTeamRoundTimer" => self.handle_round_timer(entity, parser_state),
+            "CTeamTrainWatcher" => self.handle_train_watcher(entity, parser_state),
+            "CCaptureFlag" => self.game_mode.add_capture_flag(),
+            "CTFPasstimeLogic" => self.game_mode.add_passtime_logic(),
+            "CTFTankBoss" => self.mvm.add_tank(entity.entity_index),
+            "CObjectSentrygun" => {
+                self.handle_building_entity(entity, parser_state, BuildingKind::Sentry);
+            }
+            "CObjectDispenser" => {
+                self.handle_building_entity(entity, parser_state, BuildingKind::Dispenser);
+            }
+            "CObjectTeleporter" => {
+                self.handle_building_entity(entity, parser_state, BuildingKind::Teleporter);
+            }
             _ => {}
         }
     }
@@ -830,9 +1466,45 @@ impl GameDetailsAnalyser {
             GameEvent::TeamPlayPointCaptured(event) => {
                 self.handle_point_captured_event(event);
             }
+            GameEvent::TeamPlayFlagEvent(event) => {
+                self.handle_flag_event(event);
+            }
             GameEvent::CrossbowHeal(event) => {
                 self.handle_crossbow_heal_event(event);
             }
+            GameEvent::PlayerBuiltObject(event) => {
+                self.handle_built_object_event(event);
+            }
+            GameEvent::ObjectDestroyed(event) => {
+                self.handle_object_destroyed_event(event);
+            }
+            GameEvent::ObjectDetonated(event) => {
+                self.handle_object_detonated_event(event);
+            }
+            GameEvent::ObjectRemoved(event) => {
+                self.handle_object_removed_event(event);
+            }
+            GameEvent::PlayerTeleported(event) => {
+                self.handle_player_teleported_event(event);
+            }
+            GameEvent::MvmBeginWave(event) => {
+                self.handle_begin_wave_event(event);
+            }
+            GameEvent::MvmWaveComplete(_) => {
+                self.handle_wave_end(WaveOutcome::Completed);
+            }
+            GameEvent::MvmWaveFailed(_) => {
+                self.handle_wave_end(WaveOutcome::Failed);
+            }
+            GameEvent::MvmPickupCurrency(event) => {
+                self.handle_pickup_currency_event(event);
+            }
+            GameEvent::PlayerUpgraded(_) => {
+                self.mvm.record_upgrade();
+            }
+            GameEvent::NpcHurt(event) => {
+                self.handle_npc_hurt_event(event);
+            }
             _ => {}
         }
     }
@@ -840,10 +1512,7 @@ impl GameDetailsAnalyser {
     fn handle_usermessage(&mut self, message: &UserMessage) {
         match message {
             UserMessage::SayText2(message) => {
-                self.add_highlight(Highlight::ChatMessage {
-                    sender: self.players.snapshot_by_entity_id(message.client),
-                    text: message.plain_text(),
-                });
+                self.handle_chat_message(message);
             }
             UserMessage::Text(message) if message.location == HudTextLocation::PrintTalk => {
                 self.add_highlight(Highlight::Message {
@@ -854,9 +1523,48 @@ impl GameDetailsAnalyser {
         }
     }
 
+    fn handle_chat_message(&mut self, message: &SayText2Message) {
+        let sender = self.players.snapshot_by_entity_id(message.client);
+        let kind = ChatKind::from(message.kind);
+        let sender_alive = self
+            .players
+            .get_by_entity_id(message.client)
+            .is_some_and(|player| player.life_state == PlayerLifeState::Alive);
+        let text = message.plain_text();
+
+        // For name changes, `from` is the old name
+        let name = match &message.from {
+            Some(from) if kind == ChatKind::NameChange => from.to_string(),
+            _ => sender.name.clone(),
+        };
+
+        self.chat_log.push(ChatLogEntry {
+            tick: self.demo_tick,
+            user_id: sender.user_id,
+            name,
+            team: sender.team,
+            kind,
+            sender_alive,
+            text: text.clone(),
+        });
+
+        self.add_highlight(Highlight::ChatMessage {
+            sender,
+            text,
+            kind,
+            sender_alive,
+        });
+    }
+
     pub fn handle_player_resource(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        let game_tick = self.game_tick();
         let tick = self.demo_tick;
 
+        let mut ended_spikes = Vec::new();
+        // The connection state may be sent after the ping in the same update,
+        // so the pings are only recorded once every prop has been read
+        let mut pings: Vec<(EntityId, i64)> = Vec::new();
+
         for prop in entity.props(parser_state) {
             if let Some((table_name, prop_name)) = prop.identifier.names() {
                 if let Ok(entity_id) = u32::from_str(prop_name.as_str()) {
@@ -870,7 +1578,7 @@ impl GameDetailsAnalyser {
 
                                 if player.team == Team::Other {
                                     if player.last_spawn_tick.is_none() {
-                                        player.last_spawn_tick = Some(tick);
+                                        player.last_spawn_tick = Some(game_tick);
                                     }
                                     player.team = new_team;
                                 }
@@ -886,22 +1594,109 @@ impl GameDetailsAnalyser {
                                     // Not quite accurate, but it's the best we can do.
                                     // This player was already alive when we started recording the demo.
                                     if player.last_spawn_tick.is_none() {
-                                        player.last_spawn_tick = Some(tick);
+                                        player.last_spawn_tick = Some(game_tick);
                                     }
                                     player.class = new_class;
                                 }
                             }
+                            "m_bConnected" => {
+                                player.resource_connected =
+                                    i64::try_from(&prop.value).unwrap_or_default() != 0;
+
+                                if !player.resource_connected {
+                                    if let Some(spike) = player.ping.pause(tick, game_tick) {
+                                        ended_spikes.push((player.snapshot(), spike));
+                                    }
+                                }
+                            }
+                            "m_iPing" => {
+                                pings.push((
+                                    EntityId::from(entity_id),
+                                    i64::try_from(&prop.value).unwrap_or_default(),
+                                ));
+                            }
+                            "m_iScore" => {
+                                player.score =
+                                    u32::try_from(i64::try_from(&prop.value).unwrap_or_default())
+                                        .unwrap_or_default();
+                            }
+                            "m_iMaxHealth" => {
+                                player.max_health =
+                                    u32::try_from(i64::try_from(&prop.value).unwrap_or_default())
+                                        .unwrap_or_default();
+                            }
                             _ => {}
                         }
                     }
                 }
             }
         }
+
+        for (entity_id, ping) in pings {
+            let Some(player) = self.players.get_by_entity_id_mut(entity_id) else {
+                continue;
+            };
+
+            // Bots always have a ping of 0, and so do players
+            // whose ping was not measured yet
+            if player.resource_connected && !player.is_bot && ping > 0 {
+                let ping = u32::try_from(ping).unwrap_or_default();
+
+                if let Some(spike) = player.ping.record(ping, tick, game_tick) {
+                    ended_spikes.push((player.snapshot(), spike));
+                }
+            }
+        }
+
+        for (player, spike) in ended_spikes {
+            self.add_ping_spike_highlight(player, spike);
+        }
+    }
+
+    fn add_ping_spike_highlight(&mut self, player: HighlightPlayerSnapshot, spike: PingSpike) {
+        let PingSpike {
+            start_tick,
+            end_tick,
+            peak,
+            baseline,
+        } = spike;
+
+        self.insert_highlight(
+            start_tick,
+            Highlight::PingSpike {
+                player,
+                peak,
+                baseline,
+                start_tick,
+                end_tick,
+            },
+        );
+    }
+
+    /// End the ping spikes that are still ongoing at the end of the demo
+    fn end_ping_spikes(&mut self) {
+        let tick = self.demo_tick;
+        let game_tick = self.game_tick();
+
+        let ended_spikes: Vec<_> = self
+            .players
+            .all_mut()
+            .filter_map(|player| Some((player.snapshot(), player.ping.pause(tick, game_tick)?)))
+            .collect();
+
+        for (player, spike) in ended_spikes {
+            self.add_ping_spike_highlight(player, spike);
+        }
     }
 
     #[allow(clippy::too_many_lines)]
     pub fn handle_player_entity(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
         let current_round = self.current_round;
+        let game_tick = self.game_tick();
+        let tick = self.demo_tick;
+
+        // Entity ID of the heal target, and the amount healed
+        let mut healed: Option<(EntityId, u32)> = None;
 
         if let Some(player) = self.players.get_by_entity_id_mut(entity.entity_index) {
             const LIFE_STATE_PROP: SendPropIdentifier =
@@ -965,6 +1760,29 @@ impl GameDetailsAnalyser {
             const POINTS_PROP: SendPropIdentifier =
                 SendPropIdentifier::new("DT_TFPlayerScoringDataExclusive", "m_iPoints");
 
+            const FLAGS_PROP: SendPropIdentifier =
+                SendPropIdentifier::new("DT_BasePlayer", "m_fFlags");
+            const HEALTH_PROP: SendPropIdentifier =
+                SendPropIdentifier::new("DT_BasePlayer", "m_iHealth");
+            const LOCAL_PITCH_PROP: SendPropIdentifier =
+                SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_angEyeAngles[0]");
+            const LOCAL_YAW_PROP: SendPropIdentifier =
+                SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_angEyeAngles[1]");
+
+            const IS_MINI_BOSS_PROP: SendPropIdentifier =
+                SendPropIdentifier::new("DT_TFPlayer", "m_bIsMiniBoss");
+            const USE_BOSS_HEALTH_BAR_PROP: SendPropIdentifier =
+                SendPropIdentifier::new("DT_TFPlayer", "m_bUseBossHealthBar");
+
+            const FL_ONGROUND: i64 = 1 << 0;
+
+            let mut conditions_changed = false;
+            let mut pitch: Option<f32> = None;
+            let mut yaw: Option<f32> = None;
+            let mut is_mini_boss: Option<bool> = None;
+            let mut use_boss_health_bar: Option<bool> = None;
+            let mut health: Option<u32> = None;
+
             for prop in entity.props(parser_state) {
                 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
                 match prop.identifier {
@@ -973,30 +1791,61 @@ impl GameDetailsAnalyser {
 
                         if player.life_state != life_state {
                             if life_state == PlayerLifeState::Alive {
-                                player.last_spawn_tick = Some(self.demo_tick);
+                                player.last_spawn_tick = Some(game_tick);
                             }
 
                             player.life_state = life_state;
                         }
                     }
+                    FLAGS_PROP => {
+                        let on_ground =
+                            (i64::try_from(&prop.value).unwrap_or_default() & FL_ONGROUND) != 0;
+
+                        if on_ground {
+                            player.airborne_since = None;
+                        } else if player.airborne_since.is_none() {
+                            player.airborne_since = Some(game_tick);
+                        }
+                    }
+                    HEALTH_PROP => {
+                        health = u32::try_from(i64::try_from(&prop.value).unwrap_or_default()).ok();
+                    }
+                    LOCAL_PITCH_PROP => {
+                        pitch = f32::try_from(&prop.value).ok();
+                    }
+                    LOCAL_YAW_PROP => {
+                        yaw = f32::try_from(&prop.value).ok();
+                    }
+                    IS_MINI_BOSS_PROP => {
+                        is_mini_boss = Some(i64::try_from(&prop.value).unwrap_or_default() != 0);
+                    }
+                    USE_BOSS_HEALTH_BAR_PROP => {
+                        use_boss_health_bar =
+                            Some(i64::try_from(&prop.value).unwrap_or_default() != 0);
+                    }
                     PLAYER_COND_PROP => {
                         player.player_cond = i64::try_from(&prop.value).unwrap_or_default() as u32;
+                        conditions_changed = true;
                     }
                     PLAYER_COND_EX_PROP => {
                         player.player_cond_ex =
                             i64::try_from(&prop.value).unwrap_or_default() as u32;
+                        conditions_changed = true;
                     }
                     PLAYER_COND_EX2_PROP => {
                         player.player_cond_ex2 =
                             i64::try_from(&prop.value).unwrap_or_default() as u32;
+                        conditions_changed = true;
                     }
                     PLAYER_COND_EX3_PROP => {
                         player.player_cond_ex3 =
                             i64::try_from(&prop.value).unwrap_or_default() as u32;
+                        conditions_changed = true;
                     }
                     PLAYER_CONDITION_BITS_PROP => {
                         player.condition_bits =
                             i64::try_from(&prop.value).unwrap_or_default() as u32;
+                        conditions_changed = true;
                     }
                     CAPTURES_PROP => {
                         process_score_prop!(prop, captures, player, current_round);
@@ -1026,7 +1875,15 @@ impl GameDetailsAnalyser {
                         process_score_prop!(prop, backstabs, player, current_round);
                     }
                     HEAL_POINTS_PROP => {
+                        let previous_healing = player.scoreboard.healing;
                         process_score_prop!(prop, healing, player, current_round);
+
+                        // The match total never decreases, so any increase
+                        // is healing done since the last update.
+                        if let Some(heal_target) = player.heal_target {
+                            healed =
+                                Some((heal_target, player.scoreboard.healing - previous_healing));
+                        }
                     }
                     INVULNS_PROP => {
                         process_score_prop!(prop, ubercharges, player, current_round);
@@ -1049,6 +1906,68 @@ impl GameDetailsAnalyser {
                     _ => {}
                 }
             }
+
+            if is_mini_boss.is_some() || use_boss_health_bar.is_some() {
+                player.robot_kind = player.robot_kind.update(is_mini_boss, use_boss_health_bar);
+            }
+
+            // Apply the health after the life state, which may be part of the same update
+            let mut low_health_survival: Option<LowHealthSurvival> = None;
+            if let Some(health) = health {
+                if player.life_state == PlayerLifeState::Alive {
+                    low_health_survival = player.health.update(
+                        health,
+                        player.max_health,
+                        self.config.low_health_threshold,
+                        tick,
+                        game_tick,
+                    );
+                }
+            }
+
+            if conditions_changed {
+                let active: Vec<TrackedCondition> = TrackedCondition::ALL
+                    .into_iter()
+                    .filter(|condition| player.has_any_cond(condition.conditions()))
+                    .collect();
+
+                player.condition_timeline.update(
+                    |condition| active.contains(&condition),
+                    tick,
+                    game_tick,
+                );
+            }
+
+            if !self.is_stv
+                && entity.entity_index == self.local_entity_id
+                && (pitch.is_some() || yaw.is_some())
+            {
+                self.local_view_angles
+                    .record(tick, pitch, yaw, self.config.flick_window_ticks);
+            }
+
+            let low_health_survival =
+                low_health_survival.map(|survival| (player.snapshot(), survival));
+
+            let user_id = player.user_id;
+            self.check_ubercharge_used(user_id);
+
+            if let Some((snapshot, survival)) = low_health_survival {
+                self.insert_highlight(
+                    survival.tick,
+                    Highlight::LowHealthSurvival {
+                        player: snapshot,
+                        health: survival.health,
+                    },
+                );
+            }
+
+            if let Some((target_entity_id, amount)) = healed {
+                if let Some(target) = self.players.get_by_entity_id(target_entity_id) {
+                    let target_id = target.user_id;
+                    self.pending_healing.add_medigun(user_id, target_id, amount);
+                }
+            }
         } else {
             trace!(
                 "player for entity ID {} not known in handle_player_entity",
@@ -1064,6 +1983,8 @@ impl GameDetailsAnalyser {
             SendPropIdentifier::new("DT_LocalTFWeaponMedigunData", "m_flChargeLevel");
         const OWNER_PROP: SendPropIdentifier =
             SendPropIdentifier::new("DT_BaseCombatWeapon", "m_hOwner");
+        const HEALING_TARGET_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_WeaponMedigun", "m_hHealingTarget");
 
         for prop in entity.props(parser_state) {
             #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
@@ -1073,7 +1994,19 @@ impl GameDetailsAnalyser {
                     if let Some(owner_id) = self.mediguns.get(&entity.entity_index.into()).copied()
                     {
                         if let Some(owner) = self.players.get_by_entity_id_mut(owner_id) {
+                            let old_charge = owner.charge;
                             owner.charge = (charge * 100.0).round() as u8;
+
+                            let (user_id, new_charge) = (owner.user_id, owner.charge);
+                            self.handle_charge_change(user_id, old_charge, new_charge);
+                        }
+                    }
+                }
+                HEALING_TARGET_PROP => {
+                    if let Some(owner_id) = self.mediguns.get(&entity.entity_index.into()).copied()
+                    {
+                        if let Some(owner) = self.players.get_by_entity_id_mut(owner_id) {
+                            owner.heal_target = entity_id_from_handle(&prop.value);
                         }
                     }
                 }
@@ -1088,27 +2021,367 @@ impl GameDetailsAnalyser {
         }
     }
 
-    fn handle_team(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
-        const TEAM_NUM_PROP: SendPropIdentifier = SendPropIdentifier::new("DT_Team", "m_iTeamNum");
-        const TEAM_SCORE_PROP: SendPropIdentifier = SendPropIdentifier::new("DT_Team", "m_iScore");
+    fn check_midair_and_flick(&mut self, event: &PlayerDeathEvent, kill_icon: &str) {
+        let killer_id = UserId::from(event.attacker);
+        let victim_id = UserId::from(event.user_id);
+        let game_tick = self.game_tick();
 
-        for prop in entity.props(parser_state) {
-            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-            match prop.identifier {
-                TEAM_NUM_PROP => {
-                    let team_num = i64::try_from(&prop.value).unwrap_or_default() as u8;
+        let local_user_id = self
+            .players
+            .get_by_entity_id(self.local_entity_id)
+            .map(|player| player.user_id);
 
-                    match Team::try_from(team_num).unwrap_or(Team::Other) {
-                        Team::Red => {
-                            self.red_team_entity_id = entity.entity_index;
-                        }
-                        Team::Blue => {
-                            self.blue_team_entity_id = entity.entity_index;
-                        }
-                        _ => {}
-                    }
-                }
-                TEAM_SCORE_PROP => {
+        let is_local_kill = local_user_id == Some(killer_id);
+        // A STV demo would have a midair for nearly every projectile kill
+        // of an airborne player, so those are opt-in
+        let record_midair = is_local_kill || (self.is_stv && self.config.stv_midairs);
+
+        if !record_midair {
+            return;
+        }
+
+        // Midairs are about hitscan and projectile weapons, not melee or afterburn
+        let is_melee = (event.damage_bits
+            & (DamageFlag::DMG_CLUB.bitmask() | DamageFlag::DMG_MELEE.bitmask()))
+            != 0;
+        let is_afterburn =
+            CustomDamage::from_u16(event.custom_kill) == Some(CustomDamage::TF_DMG_CUSTOM_BURNING);
+
+        if let (Some(killer), Some(victim)) =
+            (self.players.get(killer_id), self.players.get(victim_id))
+        {
+            let airborne_ticks = victim
+                .airborne_since
+                .map_or(0, |since| game_tick.saturating_sub(since));
+
+            if !is_melee
+                && !is_afterburn
+                && airborne_ticks >= self.config.midair_min_ticks
+                // Those are airshots
+                && !victim.has_cond(PlayerCondition::TF_COND_BLASTJUMPING)
+            {
+                let killer_airborne = killer.airborne_since.is_some();
+
+                self.add_highlight(Highlight::Midair {
+                    killer: self.players.snapshot_or_fallback(killer_id),
+                    victim: self.players.snapshot_or_fallback(victim_id),
+                    kill_icon: kill_icon.to_string(),
+                    airborne_ticks,
+                    killer_airborne,
+                });
+            }
+        }
+
+        if is_local_kill {
+            if let Some(flick) = self.local_view_angles.find_flick(
+                self.demo_tick,
+                self.config.flick_window_ticks,
+                self.config.flick_min_angle,
+            ) {
+                self.add_highlight(Highlight::Flick {
+                    killer: self.players.snapshot_or_fallback(killer_id),
+                    victim: self.players.snapshot_or_fallback(victim_id),
+                    kill_icon: kill_icon.to_string(),
+                    angle: flick.angle,
+                    ticks: flick.ticks,
+                });
+            }
+        }
+    }
+
+    /// In arena mode, add a highlight when a death leaves a single player alive on a team
+    fn check_last_man_standing(&mut self, victim_id: UserId) {
+        let Some(team) = self.players.get(victim_id).map(|victim| victim.team) else {
+            return;
+        };
+
+        // The victim's life state is only updated after the death event
+        let alive_on_team = |team: Team| {
+            self.players
+                .players
+                .values()
+                .filter(|player| {
+                    player.connected
+                        && player.team == team
+                        && player.user_id != victim_id
+                        && player.life_state == PlayerLifeState::Alive
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let survivors = alive_on_team(team);
+        let [survivor] = survivors.as_slice() else {
+            return;
+        };
+
+        let enemy_team = match team {
+            Team::Red => Team::Blue,
+            Team::Blue => Team::Red,
+            _ => return,
+        };
+        let enemies_alive = u32::try_from(alive_on_team(enemy_team).len()).unwrap_or(u32::MAX);
+
+        // A round where the enemies are all dead is already over
+        if enemies_alive == 0 {
+            return;
+        }
+
+        let player = survivor.snapshot();
+
+        if self.objectives.record_last_man_standing(player.user_id) {
+            self.add_highlight(Highlight::LastManStanding {
+                player,
+                enemies_alive,
+            });
+        }
+    }
+
+    fn check_overhealed_kill(&mut self, killer_id: UserId, victim_id: UserId) {
+        let Some(killer) = self.players.get(killer_id) else {
+            return;
+        };
+
+        if killer.life_state != PlayerLifeState::Alive
+            || !killer.health.is_overhealed(killer.max_health)
+        {
+            return;
+        }
+
+        let medic = self
+            .players
+            .players
+            .values()
+            .find(|player| {
+                player.life_state == PlayerLifeState::Alive
+                    && player.heal_target == Some(killer.entity_id)
+            })
+            .map(PlayerState::snapshot);
+
+        self.add_highlight(Highlight::OverhealedKill {
+            killer: killer.snapshot(),
+            victim: self.players.snapshot_or_fallback(victim_id),
+            health: killer.health.health(),
+            max_health: killer.max_health,
+            medic,
+        });
+    }
+
+    fn handle_item_entity(
+        &mut self,
+        entity: &PacketEntity,
+        parser_state: &ParserState,
+        is_weapon: bool,
+    ) {
+        const OWNER_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseEntity", "m_hOwnerEntity");
+        const ITEM_DEFINITION_INDEX_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_ScriptCreatedItem", "m_iItemDefinitionIndex");
+
+        if entity.update_type == UpdateType::Delete {
+            self.remove_item(entity.entity_index);
+            return;
+        }
+
+        let mut owner_id = None;
+        let mut definition_index = None;
+
+        for prop in entity.props(parser_state) {
+            match prop.identifier {
+                OWNER_PROP => {
+                    owner_id = entity_id_from_handle(&prop.value);
+                }
+                ITEM_DEFINITION_INDEX_PROP => {
+                    definition_index = i64::try_from(&prop.value)
+                        .ok()
+                        .and_then(|index| u32::try_from(index).ok());
+                }
+                _ => {}
+            }
+        }
+
+        // Most updates are about ammo and the like
+        if owner_id.is_none() && definition_index.is_none() {
+            return;
+        }
+
+        let previous_owner_id = self.item_owners.get(&entity.entity_index).copied();
+        let previous_item = previous_owner_id
+            .and_then(|previous_owner_id| self.players.get_by_entity_id_mut(previous_owner_id))
+            .and_then(|previous_owner| previous_owner.items.remove(&entity.entity_index));
+
+        let Some(owner_id) = owner_id.or(previous_owner_id) else {
+            return;
+        };
+        let Some(definition_index) =
+            definition_index.or(previous_item.map(|item| item.definition_index))
+        else {
+            return;
+        };
+
+        if let Some(owner) = self.players.get_by_entity_id_mut(owner_id) {
+            owner.items.insert(
+                entity.entity_index,
+                Item {
+                    definition_index,
+                    is_weapon,
+                },
+            );
+            self.item_owners.insert(entity.entity_index, owner_id);
+        } else {
+            self.item_owners.remove(&entity.entity_index);
+        }
+    }
+
+    fn remove_item(&mut self, entity_id: EntityId) {
+        if let Some(owner_id) = self.item_owners.remove(&entity_id) {
+            if let Some(owner) = self.players.get_by_entity_id_mut(owner_id) {
+                owner.items.remove(&entity_id);
+            }
+        }
+    }
+
+    fn handle_charge_change(&mut self, medic_id: UserId, old_charge: u8, new_charge: u8) {
+        if old_charge < 100 && new_charge == 100 {
+            if self.pending_ubercharges.contains_key(&medic_id) {
+                return;
+            }
+
+            self.pending_ubercharges.insert(
+                medic_id,
+                PendingUbercharge {
+                    index: self.ubercharges.len(),
+                    built_game_tick: self.game_tick(),
+                },
+            );
+            self.ubercharges.push(Ubercharge {
+                medic: medic_id,
+                built_tick: self.demo_tick,
+                used_tick: None,
+                end_tick: None,
+                held_ticks: 0,
+                kind: None,
+                target: None,
+                outcome: None,
+            });
+
+            self.add_highlight(Highlight::UberBuilt {
+                medic: self.players.snapshot_or_fallback(medic_id),
+            });
+            return;
+        }
+
+        if old_charge == 100 && new_charge < 100 {
+            // Usually, the ubercharge condition arrives before the charge starts draining.
+            // If it did not, it is picked up by `handle_player_entity` once it does.
+            self.check_ubercharge_used(medic_id);
+        }
+
+        if new_charge == 0 {
+            // Either a deployed charge has run out, or the charge was lost
+            // without being deployed, for example because the round was reset
+            self.end_ubercharge(medic_id, UberchargeOutcome::Faded);
+        }
+    }
+
+    /// Check whether a medic holding a full charge has just deployed it
+    fn check_ubercharge_used(&mut self, medic_id: UserId) {
+        let Some(pending) = self.pending_ubercharges.get(&medic_id) else {
+            return;
+        };
+        if self.ubercharges[pending.index].used_tick.is_some() {
+            return;
+        }
+        let Some(medic) = self.players.get(medic_id) else {
+            return;
+        };
+        let Some(kind) = UberchargeKind::of_player(medic) else {
+            return;
+        };
+
+        let target_id = medic
+            .heal_target
+            .and_then(|target| self.players.get_by_entity_id(target))
+            .map(|target| target.user_id);
+        let held_ticks = self.game_tick().saturating_sub(pending.built_game_tick);
+
+        let ubercharge = &mut self.ubercharges[pending.index];
+        ubercharge.used_tick = Some(self.demo_tick);
+        ubercharge.held_ticks = held_ticks;
+        ubercharge.kind = Some(kind);
+        ubercharge.target = target_id;
+        ubercharge.outcome = Some(UberchargeOutcome::Used);
+
+        self.add_highlight(Highlight::UberUsed {
+            medic: self.players.snapshot_or_fallback(medic_id),
+            target: target_id.and_then(|target_id| self.players.snapshot(target_id)),
+            kind,
+            held_ticks,
+        });
+    }
+
+    /// End the pending ubercharge of a medic, if there is one
+    fn end_ubercharge(&mut self, medic_id: UserId, outcome: UberchargeOutcome) {
+        let Some(pending) = self.pending_ubercharges.remove(&medic_id) else {
+            return;
+        };
+
+        let target_id = self
+            .players
+            .get(medic_id)
+            .and_then(|medic| medic.heal_target)
+            .and_then(|target| self.players.get_by_entity_id(target))
+            .map(|target| target.user_id);
+        let held_ticks = self.game_tick().saturating_sub(pending.built_game_tick);
+
+        let ubercharge = &mut self.ubercharges[pending.index];
+        ubercharge.end_tick = Some(self.demo_tick);
+
+        // A deployed charge ends when the medic dies, but its outcome stays the same
+        if ubercharge.used_tick.is_none() {
+            ubercharge.held_ticks = held_ticks;
+            ubercharge.target = target_id;
+            ubercharge.outcome = Some(outcome);
+        }
+    }
+
+    fn finish_ubercharges(&mut self) {
+        let game_tick = self.game_tick();
+
+        for (_medic_id, pending) in self.pending_ubercharges.drain() {
+            let ubercharge = &mut self.ubercharges[pending.index];
+
+            if ubercharge.used_tick.is_none() {
+                ubercharge.held_ticks = game_tick.saturating_sub(pending.built_game_tick);
+            }
+        }
+    }
+
+    fn handle_team(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        const TEAM_NUM_PROP: SendPropIdentifier = SendPropIdentifier::new("DT_Team", "m_iTeamNum");
+        const TEAM_SCORE_PROP: SendPropIdentifier = SendPropIdentifier::new("DT_Team", "m_iScore");
+        const TEAM_NAME_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_Team", "m_szTeamname");
+
+        // The name may be sent before the team number
+        let mut team_name: Option<String> = None;
+
+        for prop in entity.props(parser_state) {
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            match prop.identifier {
+                TEAM_NUM_PROP => {
+                    let team_num = i64::try_from(&prop.value).unwrap_or_default() as u8;
+
+                    match Team::try_from(team_num).unwrap_or(Team::Other) {
+                        Team::Red => {
+                            self.red_team_entity_id = entity.entity_index;
+                        }
+                        Team::Blue => {
+                            self.blue_team_entity_id = entity.entity_index;
+                        }
+                        _ => {}
+                    }
+                }
+                TEAM_SCORE_PROP => {
                     let score = i64::try_from(&prop.value).unwrap_or_default() as u32;
                     if entity.entity_index == self.red_team_entity_id {
                         self.red_team_score = score;
@@ -1116,20 +2389,189 @@ impl GameDetailsAnalyser {
                         self.blue_team_score = score;
                     }
                 }
+                TEAM_NAME_PROP => {
+                    team_name = <&str>::try_from(&prop.value).ok().map(String::from);
+                }
                 _ => {}
             }
         }
+
+        if let Some(team_name) = team_name {
+            if entity.entity_index == self.red_team_entity_id {
+                self.match_info.set_team_name(Team::Red, team_name);
+            } else if entity.entity_index == self.blue_team_entity_id {
+                self.match_info.set_team_name(Team::Blue, team_name);
+            }
+        }
     }
 
     fn handle_game_rules(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
         const TEAMS_SWITCHED_PROP: SendPropIdentifier =
             SendPropIdentifier::new("DT_TFGameRules", "m_bTeamsSwitched");
+        const GAME_TYPE_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_nGameType");
+        const PLAYING_KOTH_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_bPlayingKoth");
+        const PLAYING_MVM_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_bPlayingMannVsMachine");
+        const RED_KOTH_TIMER_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_hRedKothTimer");
+        const BLUE_KOTH_TIMER_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_hBlueKothTimer");
+        const MATCH_GROUP_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_nMatchGroupType");
+        const COMPETITIVE_MODE_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_bCompetitiveMode");
+        const WAITING_FOR_PLAYERS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamplayRoundBasedRules", "m_bInWaitingForPlayers");
+        const AWAITING_READY_RESTART_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamplayRoundBasedRules", "m_bAwaitingReadyRestart");
+
+        let was_pregame = self.match_info.is_pregame();
+
+        for prop in entity.props(parser_state) {
+            match prop.identifier {
+                TEAMS_SWITCHED_PROP => {
+                    self.teams_switched = i64::try_from(&prop.value).unwrap_or_default() != 0;
+                }
+                GAME_TYPE_PROP => {
+                    self.game_mode
+                        .set_game_type(i64::try_from(&prop.value).unwrap_or_default());
+                }
+                PLAYING_KOTH_PROP => {
+                    self.game_mode
+                        .set_playing_koth(i64::try_from(&prop.value).unwrap_or_default() != 0);
+                }
+                PLAYING_MVM_PROP => {
+                    self.game_mode
+                        .set_playing_mvm(i64::try_from(&prop.value).unwrap_or_default() != 0);
+                }
+                RED_KOTH_TIMER_PROP => {
+                    self.objectives
+                        .set_koth_timer(Team::Red, entity_id_from_handle(&prop.value));
+                }
+                BLUE_KOTH_TIMER_PROP => {
+                    self.objectives
+                        .set_koth_timer(Team::Blue, entity_id_from_handle(&prop.value));
+                }
+                MATCH_GROUP_PROP => {
+                    self.match_info
+                        .set_match_group(i64::try_from(&prop.value).unwrap_or(-1));
+                }
+                COMPETITIVE_MODE_PROP => {
+                    self.match_info
+                        .set_competitive_mode(i64::try_from(&prop.value).unwrap_or_default() != 0);
+                }
+                WAITING_FOR_PLAYERS_PROP => {
+                    self.match_info.set_waiting_for_players(
+                        i64::try_from(&prop.value).unwrap_or_default() != 0,
+                        self.demo_tick,
+                    );
+                }
+                AWAITING_READY_RESTART_PROP => {
+                    self.match_info.set_awaiting_ready_restart(
+                        i64::try_from(&prop.value).unwrap_or_default() != 0,
+                        self.demo_tick,
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        if was_pregame && !self.match_info.is_pregame() {
+            self.end_pregame();
+        }
+    }
+
+    /// Kills, damage and scores of the pre-game do not count towards the match.
+    /// The game resets the scoreboard when the match starts, so we do the same.
+    fn end_pregame(&mut self) {
+        self.pending_healing.take();
+        self.kill_matrix = KillMatrix::default();
+
+        for player in self.players.all_mut() {
+            player.reset_stats();
+        }
+    }
+
+    fn handle_objective_resource(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        const NUM_CONTROL_POINTS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseTeamObjectiveResource", "m_iNumControlPoints");
+
+        for prop in entity.props(parser_state) {
+            if prop.identifier == NUM_CONTROL_POINTS_PROP {
+                let num_control_points = i64::try_from(&prop.value).unwrap_or_default();
+                self.game_mode.set_num_control_points(
+                    usize::try_from(num_control_points).unwrap_or_default(),
+                );
+            } else if let Some((table_name, prop_name)) = prop.identifier.names() {
+                // Array props are named after their index, in a table named after the array
+                if table_name.as_str() == "m_iOwner" {
+                    if let Ok(index) = usize::from_str(prop_name.as_str()) {
+                        let owner = Team::new(i64::try_from(&prop.value).unwrap_or_default());
+                        self.game_mode.add_point_owner(index, owner);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_round_timer(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        const PAUSED_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamRoundTimer", "m_bTimerPaused");
+        const TIME_REMAINING_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamRoundTimer", "m_flTimeRemaining");
+        const END_TIME_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamRoundTimer", "m_flTimerEndTime");
+
+        let timer = self.objectives.timer_mut(entity.entity_index);
+
+        for prop in entity.props(parser_state) {
+            match prop.identifier {
+                PAUSED_PROP => {
+                    timer.paused = i64::try_from(&prop.value).unwrap_or_default() != 0;
+                }
+                TIME_REMAINING_PROP => {
+                    timer.time_remaining = f32::try_from(&prop.value).unwrap_or_default();
+                }
+                END_TIME_PROP => {
+                    timer.end_time = f32::try_from(&prop.value).unwrap_or_default();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_train_watcher(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        const TEAM_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseEntity", "m_iTeamNum");
+        const PROGRESS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamTrainWatcher", "m_flTotalProgress");
+        const CAPPERS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamTrainWatcher", "m_nNumCappers");
+
+        let cart = self.objectives.cart_mut(entity.entity_index);
 
         for prop in entity.props(parser_state) {
-            if prop.identifier == TEAMS_SWITCHED_PROP {
-                self.teams_switched = i64::try_from(&prop.value).unwrap_or_default() != 0;
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            match prop.identifier {
+                TEAM_PROP => {
+                    cart.team = Team::new(i64::try_from(&prop.value).unwrap_or_default());
+                }
+                PROGRESS_PROP => {
+                    cart.progress = f32::try_from(&prop.value).unwrap_or_default();
+                }
+                CAPPERS_PROP => {
+                    cart.pushers = i64::try_from(&prop.value).unwrap_or_default() as u8;
+                }
+                _ => {}
             }
         }
+
+        let team = cart.team;
+        self.game_mode.add_cart(team);
+        self.objectives
+            .record_cart_progress(entity.entity_index, self.demo_tick);
     }
 
     fn parse_user_info(&mut self, index: usize, text: Option<&str>, data: Option<Stream>) {
@@ -1157,6 +2599,12 @@ impl GameDetailsAnalyser {
             return;
         }
 
+        // The event arrives before the player entity is updated,
+        // and players don't respawn when moving to spectator.
+        if let Some(player) = self.players.get_mut(user_id) {
+            player.team = team;
+        }
+
         self.add_highlight(Highlight::PlayerTeamChange {
             player: HighlightPlayerSnapshot {
                 user_id,
@@ -1165,6 +2613,8 @@ impl GameDetailsAnalyser {
             },
             team,
         });
+
+        self.update_team_compositions();
     }
 
     fn handle_player_hurt_event(&mut self, event: &PlayerHurtEvent) {
@@ -1177,6 +2627,7 @@ impl GameDetailsAnalyser {
         };
 
         let weapon = WeaponClass::from_u16(event.weapon_id).unwrap_or_default();
+        let victim_burning = victim.has_cond(PlayerCondition::TF_COND_BURNING);
 
         // In POV demos, only record airshots performed by the local player.
         if (self.is_stv || self.players
@@ -1185,21 +2636,50 @@ impl GameDetailsAnalyser {
             victim.has_cond(PlayerCondition::TF_COND_BLASTJUMPING) &&
             victim_id != attacker_id &&
             // Only count hits with certain weapons
-            matches!(
-                weapon,
-                WeaponClass::TF_WEAPON_ROCKETLAUNCHER |
-                    WeaponClass::TF_WEAPON_ROCKETLAUNCHER_DIRECTHIT |
-                    WeaponClass::TF_WEAPON_PARTICLE_CANNON | // Cow mangler
-                    WeaponClass::TF_WEAPON_GRENADELAUNCHER |
-                    WeaponClass::TF_WEAPON_CANNON | // Loose cannon
-                    WeaponClass::TF_WEAPON_CROSSBOW
-            )
+            self.config.airshot_weapons.contains(&weapon)
         {
             self.add_highlight(Highlight::Airshot {
                 attacker: self.players.snapshot_or_fallback(attacker_id),
                 victim: self.players.snapshot_or_fallback(victim_id),
             });
         }
+
+        // Unlike the weapon stats, this includes self-damage and damage from the world
+        if let Some(victim) = self.players.get_mut(victim_id) {
+            victim.health.add_damage(u32::from(event.damage_amount));
+        }
+
+        // Self-damage (e.g. from rocket jumps) and damage from
+        // the world do not count towards the weapon stats.
+        if victim_id != attacker_id && attacker_id != 0 {
+            let damage = u32::from(event.damage_amount);
+            let crit = CritType::from_hurt_event(event);
+
+            if let Some(attacker) = self.players.get_mut(attacker_id) {
+                let stats = attacker.weapon_stats.entry(weapon).or_default();
+                stats.damage_dealt += damage;
+                stats.hits += 1;
+
+                // Only look up the loadout for the few hits that can be random crits
+                let random_crit = crit == CritType::Crit
+                    && crit.is_random(&CritContext {
+                        attacker_crit_boosted: attacker.is_crit_boosted(),
+                        victim_burning,
+                        weapon,
+                        weapon_items: &attacker.item_definition_indexes(true),
+                        custom_damage: CustomDamage::from_u16(event.custom),
+                    });
+                attacker.crit_stats.add_hit(crit, random_crit, damage);
+            }
+            if let Some(victim) = self.players.get_mut(victim_id) {
+                victim.weapon_stats.entry(weapon).or_default().damage_taken += damage;
+            }
+
+            self.kill_matrix
+                .update(self.current_round, attacker_id, victim_id, |matchup| {
+                    matchup.damage += damage;
+                });
+        }
     }
 
     // TODO: refactor to remove the following line
@@ -1212,19 +2692,78 @@ impl GameDetailsAnalyser {
             Some(UserId::from(event.assister))
         };
         let victim_id = UserId::from(event.user_id);
+        let game_tick = self.game_tick();
+        // A spy faking their death keeps playing the same life
+        let is_feign_death = (event.death_flags & TF_DEATH_FEIGN_DEATH) != 0;
 
         let victim = self.players.get_mut(victim_id);
 
         let drop: bool;
         let airshot: bool;
+        let victim_burning: bool;
 
         if let Some(victim) = victim {
             drop = victim.charge == 100;
             airshot = victim.has_cond(PlayerCondition::TF_COND_BLASTJUMPING);
-            victim.handle_life_end(self.teams_switched, self.demo_tick);
+            victim_burning = victim.has_cond(PlayerCondition::TF_COND_BURNING);
+            if !is_feign_death {
+                victim.handle_life_end(self.teams_switched, game_tick, self.demo_tick);
+            }
         } else {
             drop = false;
             airshot = false;
+            victim_burning = false;
+        }
+
+        if !is_feign_death {
+            self.end_ubercharge(victim_id, UberchargeOutcome::Dropped);
+        }
+
+        let weapon = WeaponClass::from_u16(event.weapon_id).unwrap_or_default();
+        let killer = self.players.get(killer_id);
+        // Older demos do not report the item the kill was made with
+        let weapon_items = match event.weapon_def_index {
+            0 => killer
+                .map(|killer| killer.item_definition_indexes(true))
+                .unwrap_or_default(),
+            definition_index => vec![definition_index],
+        };
+        let crit = CritType::from_death_event(event);
+        let random_crit = crit.is_random(&CritContext {
+            attacker_crit_boosted: killer.is_some_and(PlayerState::is_crit_boosted),
+            victim_burning,
+            weapon,
+            weapon_items: &weapon_items,
+            custom_damage: CustomDamage::from_u16(event.custom_kill),
+        });
+
+        if killer_id != victim_id && killer_id != 0 && !is_feign_death {
+            if let Some(killer) = self.players.get_mut(killer_id) {
+                killer.weapon_stats.entry(weapon).or_default().kills += 1;
+                killer.crit_stats.add_kill(crit, random_crit);
+            }
+            if let Some(victim) = self.players.get_mut(victim_id) {
+                victim.weapon_stats.entry(weapon).or_default().deaths += 1;
+            }
+
+            self.kill_matrix
+                .update(self.current_round, killer_id, victim_id, |matchup| {
+                    matchup.kills += 1;
+                });
+
+            self.rounds
+                .record_kill(self.demo_tick, killer_id, victim_id);
+
+            if is_sentry_kill(event.weapon.as_ref(), weapon) {
+                self.add_sentry_kill(killer_id, EntityId::from(event.inflictor_ent_index));
+            }
+
+            if let Some(assister_id) = maybe_assister_id {
+                self.kill_matrix
+                    .update(self.current_round, assister_id, victim_id, |matchup| {
+                        matchup.assists += 1;
+                    });
+            }
         }
 
         let mut kill_icon = event.weapon.as_ref();
@@ -1332,9 +2871,38 @@ impl GameDetailsAnalyser {
             streak: event.kill_streak_total as usize,
             drop,
             airshot,
+            crit,
+            random_crit,
         });
 
-        if event.kill_streak_total > 0 && event.kill_streak_total % 5 == 0 {
+        if killer_id != victim_id && killer_id != 0 && !is_feign_death {
+            self.check_midair_and_flick(event, kill_icon);
+            self.check_overhealed_kill(killer_id, victim_id);
+        }
+
+        if !is_feign_death && self.game_mode.is_arena() {
+            self.check_last_man_standing(victim_id);
+        }
+
+        if killer_id != victim_id && !is_feign_death && self.game_mode.is_mvm() {
+            self.record_robot_kill(killer_id, victim_id);
+        }
+
+        if killer_id != victim_id && !is_feign_death {
+            if let Some(killer) = self.players.snapshot(killer_id) {
+                if let Some(multikill) = self.multikills.record_kill(
+                    killer,
+                    self.players.snapshot_or_fallback(victim_id),
+                    kill_icon.to_string(),
+                    self.demo_tick,
+                    self.game_tick(),
+                ) {
+                    self.add_multikill_highlight(multikill);
+                }
+            }
+        }
+
+        if self.config.is_killstreak(event.kill_streak_total) {
             self.add_highlight(Highlight::KillStreak {
                 player: self.players.snapshot_or_fallback(killer_id),
                 streak: event.kill_streak_total,
@@ -1343,7 +2911,7 @@ impl GameDetailsAnalyser {
 
         // Note: kill_streak_assist is only incremented when a medic gets an assist while their
         // medigun is active (which isn't always when their heal target gets a kill!)
-        if event.kill_streak_assist > 0 && event.kill_streak_assist % 5 == 0 {
+        if self.config.is_killstreak(event.kill_streak_assist) {
             if let Some(assister_id) = maybe_assister_id {
                 if let Some(player) = self.players.snapshot(assister_id) {
                     self.add_highlight(Highlight::KillStreak {
@@ -1354,7 +2922,7 @@ impl GameDetailsAnalyser {
             }
         }
 
-        if event.kill_streak_victim >= 10 {
+        if event.kill_streak_victim >= self.config.killstreak_ended_threshold {
             self.add_highlight(Highlight::KillStreakEnded {
                 killer: self.players.snapshot_or_fallback(killer_id),
                 victim: self.players.snapshot_or_fallback(victim_id),
@@ -1363,15 +2931,286 @@ impl GameDetailsAnalyser {
         }
     }
 
-    fn handle_crossbow_heal_event(&mut self, event: &CrossbowHealEvent) {
-        // This event seems to only be present in STV demos.
-        // Also, the UserIds in the event use u8s instead of u16s,
-        // which will cause attribution errors.
+    fn handle_building_entity(
+        &mut self,
+        entity: &PacketEntity,
+        parser_state: &ParserState,
+        kind: BuildingKind,
+    ) {
+        const BUILDER_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseObject", "m_hBuilder");
+        const LEVEL_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseObject", "m_iUpgradeLevel");
+        const MINI_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseObject", "m_bMiniBuilding");
+        const MODE_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseObject", "m_iObjectMode");
+
+        // Buildings that already existed when the recording started
+        // (or that were outside of the PVS of a POV demo when they were built)
+        // don't have a player_builtobject event, so we also track them here.
+        if entity.update_type == UpdateType::Enter {
+            let builder = entity
+                .props(parser_state)
+                .find(|prop| prop.identifier == BUILDER_PROP)
+                .and_then(|prop| entity_id_from_handle(&prop.value))
+                .and_then(|entity_id| self.players.get_by_entity_id(entity_id))
+                .map(|player| player.user_id);
+
+            if let Some(builder) = builder {
+                self.track_building(entity.entity_index, builder, kind);
+            }
+        }
 
-        // TODO: reconsider if we need this at all.
+        let Some(&index) = self.active_buildings.get(&entity.entity_index) else {
+            return;
+        };
+        let building = &mut self.buildings[index];
+        let mut upgrades = 0;
 
-        let target_id = UserId::from(u16::from(event.target));
-        let healer_id = UserId::from(u16::from(event.healer));
+        for prop in entity.props(parser_state) {
+            match prop.identifier {
+                LEVEL_PROP => {
+                    let level = u8::try_from(i64::try_from(&prop.value).unwrap_or_default())
+                        .unwrap_or_default();
+
+                    // Redeployed buildings are rebuilt from level 1,
+                    // this should not count as an upgrade.
+                    if level > building.level() {
+                        building.upgrades.push(BuildingUpgrade {
+                            tick: self.demo_tick,
+                            level,
+                        });
+                        upgrades += 1;
+                    }
+                }
+                MINI_PROP => {
+                    building.mini = i64::try_from(&prop.value).unwrap_or_default() > 0;
+                }
+                MODE_PROP if building.kind == BuildingKind::Teleporter => {
+                    building.teleporter_mode =
+                        if i64::try_from(&prop.value).unwrap_or_default() == 1 {
+                            Some(TeleporterMode::Exit)
+                        } else {
+                            Some(TeleporterMode::Entrance)
+                        };
+                }
+                _ => {}
+            }
+        }
+
+        if upgrades > 0 {
+            if let Some(builder) = self.players.get_mut(building.builder) {
+                builder
+                    .building_stats
+                    .entry(building.kind)
+                    .or_default()
+                    .upgrades += upgrades;
+            }
+        }
+    }
+
+    fn add_sentry_kill(&mut self, builder_id: UserId, inflictor_id: EntityId) {
+        let is_sentry_of_builder = |building: &Building| {
+            building.builder == builder_id && building.kind == BuildingKind::Sentry
+        };
+
+        // The inflictor is the sentry itself, unless the kill was made
+        // with a rocket. Fall back to the active sentry of the builder.
+        let sentry = self
+            .active_buildings
+            .get(&inflictor_id)
+            .copied()
+            .filter(|&index| is_sentry_of_builder(&self.buildings[index]))
+            .or_else(|| {
+                self.active_buildings
+                    .values()
+                    .copied()
+                    .find(|&index| is_sentry_of_builder(&self.buildings[index]))
+            });
+
+        if let Some(index) = sentry {
+            self.buildings[index].kills += 1;
+        }
+
+        if let Some(builder) = self.players.get_mut(builder_id) {
+            builder
+                .building_stats
+                .entry(BuildingKind::Sentry)
+                .or_default()
+                .kills += 1;
+        }
+    }
+
+    /// Start tracking a building, unless it is already being tracked.
+    /// Returns the index of the building into `buildings`.
+    fn track_building(
+        &mut self,
+        entity_id: EntityId,
+        builder: UserId,
+        kind: BuildingKind,
+    ) -> usize {
+        if let Some(&index) = self.active_buildings.get(&entity_id) {
+            let building = &self.buildings[index];
+            if building.builder == builder && building.kind == kind {
+                return index;
+            }
+
+            // We missed the removal of the previous building using this entity
+            self.end_building(entity_id, BuildingEnd::Removed);
+        }
+
+        let index = self.buildings.len();
+        self.active_buildings.insert(entity_id, index);
+        self.buildings.push(Building {
+            builder,
+            kind,
+            teleporter_mode: None,
+            mini: false,
+            built_tick: self.demo_tick,
+            upgrades: Vec::new(),
+            kills: 0,
+            uses: 0,
+            end_tick: None,
+            end: None,
+        });
+
+        index
+    }
+
+    /// Stop tracking a building, returning its index into `buildings`
+    fn end_building(&mut self, entity_id: EntityId, end: BuildingEnd) -> Option<usize> {
+        let index = self.active_buildings.remove(&entity_id)?;
+
+        let building = &mut self.buildings[index];
+        building.end_tick = Some(self.demo_tick);
+        building.end = Some(end);
+
+        Some(index)
+    }
+
+    fn handle_built_object_event(&mut self, event: &PlayerBuiltObjectEvent) {
+        let Some(kind) = BuildingKind::from_u16(event.object) else {
+            return;
+        };
+        let builder_id = UserId::from(event.user_id);
+        let index = self.track_building(EntityId::from(u32::from(event.index)), builder_id, kind);
+
+        // Only count buildings that were placed during the recording, and
+        // count redeployed buildings once. Buildings also enter the PVS
+        // of a POV demo again after having been out of sight.
+        if self.built_buildings.insert(index) {
+            if let Some(player) = self.players.get_mut(builder_id) {
+                player.building_stats.entry(kind).or_default().built += 1;
+            }
+        }
+    }
+
+    fn handle_object_destroyed_event(&mut self, event: &ObjectDestroyedEvent) {
+        let attacker_id = UserId::from(event.attacker);
+        let maybe_assister_id = if event.assister == u16::MAX || event.assister == 0 {
+            None
+        } else {
+            Some(UserId::from(event.assister))
+        };
+
+        let Some(index) = self.end_building(
+            EntityId::from(u32::from(event.index)),
+            BuildingEnd::Destroyed {
+                attacker: attacker_id,
+                assister: maybe_assister_id,
+                weapon: event.weapon.to_string(),
+            },
+        ) else {
+            return;
+        };
+
+        let building = &self.buildings[index];
+        let (builder_id, kind, level) = (building.builder, building.kind, building.level());
+
+        if let Some(builder) = self.players.get_mut(builder_id) {
+            builder.building_stats.entry(kind).or_default().destroyed += 1;
+        }
+
+        self.add_highlight(Highlight::BuildingDestroyed {
+            builder: self.players.snapshot_or_fallback(builder_id),
+            attacker: self.players.snapshot_or_fallback(attacker_id),
+            assister: maybe_assister_id
+                .map(|assister_id| self.players.snapshot_or_fallback(assister_id)),
+            kind,
+            level,
+            weapon: event.weapon.to_string(),
+        });
+    }
+
+    fn handle_object_detonated_event(&mut self, event: &ObjectDetonatedEvent) {
+        self.end_building(
+            EntityId::from(u32::from(event.index)),
+            BuildingEnd::Detonated,
+        );
+    }
+
+    fn handle_object_removed_event(&mut self, event: &ObjectRemovedEvent) {
+        // This is also sent after a building was destroyed or detonated,
+        // in which case we are no longer tracking it.
+        self.end_building(EntityId::from(u32::from(event.index)), BuildingEnd::Removed);
+    }
+
+    fn handle_player_teleported_event(&mut self, event: &PlayerTeleportedEvent) {
+        let builder_id = UserId::from(event.builder_id);
+
+        // The event doesn't tell us which teleporter was used,
+        // so we attribute it to the entrance of the builder.
+        let entrance = self.active_buildings.values().copied().find(|index| {
+            let building = &self.buildings[*index];
+            building.builder == builder_id
+                && building.kind == BuildingKind::Teleporter
+                && building.teleporter_mode != Some(TeleporterMode::Exit)
+        });
+
+        if let Some(index) = entrance {
+            self.buildings[index].uses += 1;
+        }
+
+        if let Some(builder) = self.players.get_mut(builder_id) {
+            builder
+                .building_stats
+                .entry(BuildingKind::Teleporter)
+                .or_default()
+                .uses += 1;
+        }
+    }
+
+    fn flush_pending_healing(&mut self) {
+        for (healer_id, target_id, amount) in self.pending_healing.take() {
+            self.add_healing(healer_id, target_id, amount);
+        }
+    }
+
+    fn add_healing(&mut self, healer_id: UserId, target_id: UserId, amount: u32) {
+        if amount == 0 {
+            return;
+        }
+
+        let round = self.current_round;
+        if let Some(healer) = self.players.get_mut(healer_id) {
+            healer.heal_spread.add(round, target_id, amount);
+        }
+    }
+
+    fn handle_crossbow_heal_event(&mut self, event: &CrossbowHealEvent) {
+        // This event seems to only be present in STV demos.
+        // Also, the UserIds in the event use u8s instead of u16s,
+        // which will cause attribution errors.
+
+        let target_id = UserId::from(u16::from(event.target));
+        let healer_id = UserId::from(u16::from(event.healer));
+
+        // The healing scoreboard includes this amount, it must not
+        // be credited to the medigun target as well
+        let amount = u32::from(event.amount);
+        self.add_healing(healer_id, target_id, amount);
+        self.pending_healing.add_crossbow(healer_id, amount);
 
         if let Some(target_player) = self.players.get(target_id) {
             if target_player.has_cond(PlayerCondition::TF_COND_BLASTJUMPING) {
@@ -1384,22 +3223,79 @@ impl GameDetailsAnalyser {
     }
 
     fn handle_player_spawn_event(&mut self, event: &PlayerSpawnEvent) {
+        let game_tick = self.game_tick();
+
         if let Some(player) = self.players.get_mut(UserId::from(event.user_id)) {
+            let previous_class = player.class;
             player.class = Class::new(event.class);
             player.team = Team::new(event.team);
-            player.last_spawn_tick = Some(self.demo_tick);
+            player.last_spawn_tick = Some(game_tick);
+
+            // The first spawn of a player is not a class change
+            if previous_class != Class::Other && previous_class != player.class {
+                let (snapshot, class) = (player.snapshot(), player.class);
+                self.add_class_change(snapshot, previous_class, class);
+            }
         } else {
             trace!("Unknown player with user id {} spawned", event.user_id);
         }
+
+        self.update_team_compositions();
+    }
+
+    fn add_class_change(&mut self, player: HighlightPlayerSnapshot, from: Class, to: Class) {
+        self.class_changes.push(ClassChange {
+            tick: self.demo_tick,
+            user_id: player.user_id,
+            team: player.team,
+            from,
+            to,
+        });
+
+        self.add_highlight(Highlight::ClassChange { player, from, to });
+    }
+
+    fn update_team_compositions(&mut self) {
+        let mut red: Classes<u8> = Classes::default();
+        let mut blue: Classes<u8> = Classes::default();
+
+        for player in self.players.players.values() {
+            if !player.connected || player.class == Class::Other {
+                continue;
+            }
+
+            match player.team {
+                Team::Red => red[player.class] += 1,
+                Team::Blue => blue[player.class] += 1,
+                _ => {}
+            }
+        }
+
+        self.compositions
+            .update(self.demo_tick, Team::Red, red.into_real_classes());
+        self.compositions
+            .update(self.demo_tick, Team::Blue, blue.into_real_classes());
     }
 
     fn handle_round_stalemate_event(&mut self, event: &TeamPlayRoundStalemateEvent) {
         self.add_highlight(Highlight::RoundStalemate {
             reason: event.reason,
         });
+
+        // This event is sent when the round goes into sudden death.
+        // The round only ends with the following teamplay_round_win event.
+        self.rounds.record_sudden_death();
     }
 
     fn handle_round_start_event(&mut self, event: &TeamPlayRoundStartEvent) {
+        // Kills from the previous round (e.g. during humiliation)
+        // should not be combined with kills from this round.
+        self.flush_multikills();
+
+        self.rounds
+            .start_round(self.current_round, self.demo_tick, self.game_tick());
+        self.objectives.start_round();
+
         self.add_highlight(Highlight::RoundStart {
             full_reset: event.full_reset,
         });
@@ -1407,23 +3303,49 @@ impl GameDetailsAnalyser {
 
     fn handle_round_win_event(&mut self, event: &TeamPlayRoundWinEvent) {
         let team = Team::try_from(event.team).unwrap_or_default();
+        let win_reason = WinReason::from_u8(event.win_reason);
+        let winner = matches!(team, Team::Red | Team::Blue).then_some(team);
 
         // In at least one of my demos, a stalemate was represented by team "Other" winning a round,
         // and handle_round_stalemate_event was not called. I'm not sure if this is always the case.
-        if matches!(team, Team::Red | Team::Blue) {
-            self.add_highlight(Highlight::RoundWin { winner: team });
+        if let Some(winner) = winner {
+            self.add_highlight(Highlight::RoundWin { winner, win_reason });
         } else {
             self.add_highlight(Highlight::RoundStalemate {
                 reason: event.win_reason,
             });
         }
+
+        self.rounds.end_round(
+            &RoundEnd {
+                winner,
+                win_reason,
+                sudden_death: event.was_sudden_death != 0,
+            },
+            self.demo_tick,
+            self.game_tick(),
+        );
     }
 
     fn handle_round_end(&mut self) {
+        self.flush_multikills();
         self.current_round += 1;
+        let game_tick = self.game_tick();
         for player in self.players.alive_players_mut() {
-            player.handle_life_end(self.teams_switched, self.demo_tick);
+            player.handle_life_end(self.teams_switched, game_tick, self.demo_tick);
         }
+
+        let team_size = |team: Team| {
+            let size = self
+                .players
+                .players
+                .values()
+                .filter(|player| player.connected && player.team == team)
+                .count();
+            u32::try_from(size).unwrap_or_default()
+        };
+        let (red, blue) = (team_size(Team::Red), team_size(Team::Blue));
+        self.match_info.record_team_sizes(red, blue);
     }
 
     fn handle_point_captured_event(&mut self, event: &TeamPlayPointCapturedEvent) {
@@ -1438,6 +3360,20 @@ impl GameDetailsAnalyser {
             }
         }
 
+        self.rounds.record_capture(PointCapture {
+            tick: self.demo_tick,
+            point_name: event.cp_name.to_string(),
+            team: Team::new(event.team),
+            cappers: cappers.iter().map(|capper| capper.user_id).collect(),
+        });
+
+        if self.game_mode.is_koth() {
+            #[allow(clippy::cast_precision_loss)]
+            let server_time = u32::from(self.server_tick) as f32 * self.interval_per_tick;
+            self.objectives
+                .record_koth_timers(self.demo_tick, Team::new(event.team), server_time);
+        }
+
         self.add_highlight(Highlight::PointCaptured {
             point_name: event.cp_name.to_string(),
             capturing_team: event.team,
@@ -1445,6 +3381,101 @@ impl GameDetailsAnalyser {
         });
     }
 
+    fn handle_flag_event(&mut self, event: &TeamPlayFlagEventEvent) {
+        let Some(kind) = FlagEventKind::from_u16(event.event_type) else {
+            return;
+        };
+
+        // `player` is an entity index, 0 if the flag returned on its own
+        let player = self
+            .players
+            .get_by_entity_id(EntityId::from(u32::from(event.player)))
+            .map(PlayerState::snapshot);
+
+        self.add_highlight(Highlight::Flag {
+            player,
+            team: Team::new(event.team),
+            kind,
+        });
+    }
+
+    fn handle_begin_wave_event(&mut self, event: &MvmBeginWaveEvent) {
+        self.mvm.begin_wave(
+            event.wave_index,
+            event.max_waves,
+            self.demo_tick,
+            self.game_tick(),
+        );
+
+        self.add_highlight(Highlight::WaveStart {
+            wave: event.wave_index,
+            max_waves: event.max_waves,
+        });
+    }
+
+    fn handle_wave_end(&mut self, outcome: WaveOutcome) {
+        let Some(wave) = self
+            .mvm
+            .end_wave(Some(outcome), self.demo_tick, self.game_tick())
+        else {
+            return;
+        };
+
+        self.add_highlight(match outcome {
+            WaveOutcome::Completed => Highlight::WaveComplete { wave },
+            WaveOutcome::Failed => Highlight::WaveFailed { wave },
+        });
+    }
+
+    fn handle_pickup_currency_event(&mut self, event: &MvmPickupCurrencyEvent) {
+        let amount = u32::from(event.currency);
+        self.mvm.record_credits(amount);
+
+        // `player` is an entity index
+        if let Some(player) = self
+            .players
+            .get_by_entity_id_mut(EntityId::from(u32::from(event.player)))
+        {
+            player.mvm_stats.credits_collected += amount;
+        }
+    }
+
+    fn handle_npc_hurt_event(&mut self, event: &NpcHurtEvent) {
+        let entity_id = EntityId::from(u32::from(event.ent_index));
+        if !self.mvm.is_tank(entity_id) {
+            return;
+        }
+
+        // Removing the tank makes sure that each tank is only counted once
+        let destroyed = event.health == 0 && self.mvm.remove_tank(entity_id);
+
+        if let Some(attacker) = self.players.get_mut(UserId::from(event.attacker_player)) {
+            attacker.mvm_stats.tank_damage += u32::from(event.damage_amount);
+            if destroyed {
+                attacker.mvm_stats.robot_kills.add(RobotKind::Tank);
+            }
+        }
+
+        if destroyed {
+            self.mvm.record_robot_kill(RobotKind::Tank);
+        }
+    }
+
+    fn record_robot_kill(&mut self, killer_id: UserId, victim_id: UserId) {
+        let Some(victim) = self.players.get(victim_id) else {
+            return;
+        };
+        if !victim.is_bot {
+            return;
+        }
+        let kind = victim.robot_kind;
+
+        if let Some(killer) = self.players.get_mut(killer_id) {
+            killer.mvm_stats.robot_kills.add(kind);
+        }
+        self.mvm.record_robot_kill(kind);
+    }
+
     fn handle_player_connect_event(&mut self, event: &PlayerConnectClientEvent) {
         self.add_highlight(Highlight::PlayerConnected {
             player: HighlightPlayerSnapshot {
@@ -1463,8 +3494,20 @@ impl GameDetailsAnalyser {
             reason: event.reason.to_string(),
         });
 
-        self.players
-            .player_leave(self.teams_switched, user_id, self.demo_tick);
+        self.players.player_leave(
+            self.teams_switched,
+            user_id,
+            self.game_tick(),
+            self.demo_tick,
+        );
+
+        if self.match_info.is_pregame() {
+            if let Some(player) = self.players.get_mut(user_id) {
+                player.left_during_pregame = true;
+            }
+        }
+
+        self.update_team_compositions();
     }
 }
 
@@ -1511,3 +3554,1209 @@ fn test_bincode() {
 
     assert_eq!(decoded, state);
 }
+
+#[cfg(test)]
+mod tests {
+    use tf_demo_parser::demo::{
+        data::userinfo::PlayerInfo,
+        message::{
+            packetentities::{BaselineIndex, PacketEntitiesMessage},
+            setconvar::{ConVar, SetConVarMessage},
+            usermessage::ChatMessageKind,
+            NetTickMessage, SetPauseMessage, VoiceDataMessage,
+        },
+        sendprop::SendProp,
+    };
+
+    use super::*;
+    use match_info::{MatchFormat, MatchType};
+
+    fn parser_state() -> ParserState {
+        ParserState::new(24, GameDetailsAnalyser::does_handle, false)
+    }
+
+    fn add_player(analyser: &mut GameDetailsAnalyser, user_id: u16, team: Team, class: Class) {
+        analyser.players.insert_or_update_player(UserInfo {
+            entity_id: EntityId::from(u32::from(user_id)),
+            player_info: PlayerInfo {
+                name: format!("player{user_id}"),
+                user_id: UserId::from(user_id),
+                steam_id: format!("[U:1:{user_id}]"),
+                ..PlayerInfo::default()
+            },
+        });
+
+        let player = analyser.players.get_mut(UserId::from(user_id)).unwrap();
+        player.team = team;
+        player.class = class;
+    }
+
+    fn packet_entity(
+        entity_id: u32,
+        update_type: UpdateType,
+        props: Vec<(SendPropIdentifier, SendPropValue)>,
+    ) -> PacketEntity {
+        PacketEntity {
+            server_class: ClassId::from(0),
+            entity_index: EntityId::from(entity_id),
+            props: props
+                .into_iter()
+                .map(|(identifier, value)| SendProp {
+                    index: 0,
+                    identifier,
+                    value,
+                })
+                .collect(),
+            in_pvs: true,
+            update_type,
+            serial_number: 0,
+            delay: None,
+            delta: None,
+            baseline_index: BaselineIndex::First,
+        }
+    }
+
+    fn player_entity(
+        user_id: u16,
+        props: Vec<(SendPropIdentifier, SendPropValue)>,
+    ) -> PacketEntity {
+        packet_entity(u32::from(user_id), UpdateType::Preserve, props)
+    }
+
+    pub(super) fn death_event(
+        victim: u16,
+        attacker: u16,
+        weapon: WeaponClass,
+        kill_icon: &str,
+    ) -> PlayerDeathEvent {
+        PlayerDeathEvent {
+            user_id: victim,
+            victim_ent_index: u32::from(victim),
+            inflictor_ent_index: u32::from(attacker),
+            attacker,
+            weapon: kill_icon.into(),
+            weapon_id: weapon as u16,
+            damage_bits: 0,
+            custom_kill: 0,
+            assister: u16::MAX,
+            weapon_log_class_name: kill_icon.into(),
+            stun_flags: 0,
+            death_flags: 0,
+            silent_kill: false,
+            player_penetrate_count: 0,
+            assister_fallback: "".into(),
+            kill_streak_total: 0,
+            kill_streak_wep: 0,
+            kill_streak_assist: 0,
+            kill_streak_victim: 0,
+            ducks_streaked: 0,
+            duck_streak_total: 0,
+            duck_streak_assist: 0,
+            duck_streak_victim: 0,
+            rocket_jump: false,
+            weapon_def_index: 0,
+            crit_type: 0,
+        }
+    }
+
+    fn hurt_event(victim: u16, attacker: u16, weapon: WeaponClass, damage: u16) -> PlayerHurtEvent {
+        PlayerHurtEvent {
+            user_id: victim,
+            health: 0,
+            attacker,
+            damage_amount: damage,
+            custom: 0,
+            show_disguised_crit: false,
+            crit: false,
+            mini_crit: false,
+            all_see_crit: false,
+            weapon_id: weapon as u16,
+            bonus_effect: 0,
+        }
+    }
+
+    fn player_summary(summary: &GameSummary, user_id: u16) -> &PlayerSummary {
+        summary
+            .players
+            .iter()
+            .find(|player| player.user_id == UserId::from(user_id))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_crossbow_healing_is_not_credited_to_medigun_target() {
+        const HEAL_POINTS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFPlayerScoringDataExclusive", "m_iHealPoints");
+
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Medic);
+        add_player(&mut analyser, 3, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 4, Team::Red, Class::Scout);
+
+        analyser
+            .players
+            .get_mut(UserId::from(2u16))
+            .unwrap()
+            .heal_target = Some(EntityId::from(3u32));
+
+        // 50 healed with the medigun and 75 with the crossbow in the same tick
+        analyser.handle_crossbow_heal_event(&CrossbowHealEvent {
+            healer: 2,
+            target: 4,
+            amount: 75,
+        });
+        analyser.handle_player_entity(
+            &player_entity(2, vec![(HEAL_POINTS_PROP, SendPropValue::Integer(125))]),
+            &state,
+        );
+
+        let summary = analyser.into_output(&state);
+        let heal_spread = &player_summary(&summary, 2).heal_spread;
+        assert_eq!(heal_spread.targets[&UserId::from(3u16)], 50);
+        assert_eq!(heal_spread.targets[&UserId::from(4u16)], 75);
+    }
+
+    #[test]
+    fn test_earned_crit_kills_are_not_random() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+        add_player(&mut analyser, 4, Team::Blue, Class::Engineer);
+
+        let mut market_gardener =
+            death_event(3, 2, WeaponClass::TF_WEAPON_SHOVEL, "market_gardener");
+        market_gardener.crit_type = 2;
+        market_gardener.weapon_def_index = 416;
+        analyser.handle_player_death_event(&market_gardener);
+
+        let mut rocket = death_event(
+            4,
+            2,
+            WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+            "tf_projectile_rocket",
+        );
+        rocket.crit_type = 2;
+        rocket.weapon_def_index = 18;
+        analyser.handle_player_death_event(&rocket);
+
+        let summary = analyser.into_output(&state);
+        let crit_stats = &player_summary(&summary, 2).crit_stats;
+        assert_eq!(crit_stats.crit_kills, 2);
+        assert_eq!(crit_stats.random_crit_kills, 1);
+    }
+
+    #[test]
+    fn test_sentry_rocket_kills() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Engineer);
+        add_player(&mut analyser, 3, Team::Blue, Class::Soldier);
+        add_player(&mut analyser, 4, Team::Blue, Class::Medic);
+
+        analyser.handle_built_object_event(&PlayerBuiltObjectEvent {
+            user_id: 2,
+            object: BuildingKind::Sentry as u16,
+            index: 100,
+        });
+
+        let mut bullet = death_event(3, 2, WeaponClass::TF_WEAPON_SENTRY_BULLET, "obj_sentrygun3");
+        bullet.inflictor_ent_index = 100;
+        analyser.handle_player_death_event(&bullet);
+
+        // The inflictor of a rocket kill is the rocket
+        let mut rocket = death_event(4, 2, WeaponClass::TF_WEAPON_SENTRY_ROCKET, "obj_sentrygun3");
+        rocket.inflictor_ent_index = 200;
+        analyser.handle_player_death_event(&rocket);
+
+        // Kills by the engineer themselves
+        analyser.handle_player_death_event(&death_event(
+            3,
+            2,
+            WeaponClass::TF_WEAPON_SHOTGUN_PRIMARY,
+            "shotgun_primary",
+        ));
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(summary.buildings[0].kills, 2);
+        let sentry_stats = &player_summary(&summary, 2).building_stats[&BuildingKind::Sentry];
+        assert_eq!(sentry_stats.kills, 2);
+    }
+
+    #[test]
+    fn test_buildings_are_counted_once() {
+        const BUILDER_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BaseObject", "m_hBuilder");
+
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Engineer);
+
+        let sentry_enters_pvs = || {
+            packet_entity(
+                100,
+                UpdateType::Enter,
+                vec![(BUILDER_PROP, SendPropValue::Integer(2))],
+            )
+        };
+        let built_sentry = PlayerBuiltObjectEvent {
+            user_id: 2,
+            object: BuildingKind::Sentry as u16,
+            index: 100,
+        };
+
+        // A sentry that existed before the recording started
+        analyser.handle_building_entity(&sentry_enters_pvs(), &state, BuildingKind::Sentry);
+
+        analyser.handle_object_removed_event(&ObjectRemovedEvent {
+            user_id: 2,
+            object_type: BuildingKind::Sentry as u16,
+            index: 100,
+        });
+        analyser.handle_built_object_event(&built_sentry);
+        // Leaving and entering the PVS again
+        analyser.handle_building_entity(&sentry_enters_pvs(), &state, BuildingKind::Sentry);
+        // Redeployed
+        analyser.handle_built_object_event(&built_sentry);
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(summary.buildings.len(), 2);
+        let sentry_stats = &player_summary(&summary, 2).building_stats[&BuildingKind::Sentry];
+        assert_eq!(sentry_stats.built, 1);
+    }
+
+    #[test]
+    fn test_pregame_stats_are_reset() {
+        const WAITING_FOR_PLAYERS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamplayRoundBasedRules", "m_bInWaitingForPlayers");
+
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+
+        let waiting_for_players = |waiting: bool| {
+            packet_entity(
+                50,
+                UpdateType::Preserve,
+                vec![(
+                    WAITING_FOR_PLAYERS_PROP,
+                    SendPropValue::Integer(waiting.into()),
+                )],
+            )
+        };
+        let rocket = || {
+            death_event(
+                3,
+                2,
+                WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+                "tf_projectile_rocket",
+            )
+        };
+
+        analyser.handle_game_rules(&waiting_for_players(true), &state);
+        analyser.handle_player_death_event(&rocket());
+        analyser.handle_game_rules(&waiting_for_players(false), &state);
+        analyser.handle_player_death_event(&rocket());
+
+        let summary = analyser.into_output(&state);
+        let weapon_stats = &player_summary(&summary, 2).weapon_stats;
+        assert_eq!(
+            weapon_stats[&WeaponClass::TF_WEAPON_ROCKETLAUNCHER].kills,
+            1
+        );
+        let matchup = &summary.kill_matrix.matchups[&UserId::from(2u16)][&UserId::from(3u16)];
+        assert_eq!(matchup.kills, 1);
+    }
+
+    #[test]
+    fn test_team_change_updates_composition() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Red, Class::Scout);
+
+        analyser.demo_tick = DemoTick::from(100);
+        analyser.update_team_compositions();
+
+        analyser.demo_tick = DemoTick::from(200);
+        analyser.handle_player_team_event(&PlayerTeamEvent {
+            user_id: 3,
+            team: Team::Spectator as u8,
+            old_team: Team::Red as u8,
+            disconnect: false,
+            auto_team: false,
+            silent: false,
+            name: "player3".into(),
+        });
+
+        let summary = analyser.into_output(&state);
+        let red: Vec<_> = summary
+            .team_compositions
+            .iter()
+            .filter(|composition| composition.team == Team::Red)
+            .collect();
+        assert_eq!(red.len(), 2);
+        assert_eq!(red[1].tick, DemoTick::from(200));
+        assert_eq!(red[1].classes.iter().sum::<u8>(), 1);
+    }
+
+    #[test]
+    fn test_weapon_stats() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+
+        let rocket_launcher = WeaponClass::TF_WEAPON_ROCKETLAUNCHER;
+        analyser.handle_player_hurt_event(&hurt_event(3, 2, rocket_launcher, 90));
+        analyser.handle_player_hurt_event(&hurt_event(3, 2, rocket_launcher, 35));
+        // A rocket jump
+        analyser.handle_player_hurt_event(&hurt_event(2, 2, rocket_launcher, 40));
+        analyser.handle_player_death_event(&death_event(
+            3,
+            2,
+            rocket_launcher,
+            "tf_projectile_rocket",
+        ));
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(
+            player_summary(&summary, 2).weapon_stats[&rocket_launcher],
+            WeaponStats {
+                damage_dealt: 125,
+                damage_taken: 0,
+                kills: 1,
+                deaths: 0,
+                hits: 2,
+            }
+        );
+        assert_eq!(
+            player_summary(&summary, 3).weapon_stats[&rocket_launcher],
+            WeaponStats {
+                damage_dealt: 0,
+                damage_taken: 125,
+                kills: 0,
+                deaths: 1,
+                hits: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_feign_deaths_are_not_weapon_kills() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Sniper);
+        add_player(&mut analyser, 3, Team::Blue, Class::Spy);
+        analyser
+            .players
+            .get_mut(UserId::from(3u16))
+            .unwrap()
+            .last_spawn_tick = Some(0);
+        analyser.server_tick = ServerTick::from(100);
+
+        let mut feign_death = death_event(3, 2, WeaponClass::TF_WEAPON_SNIPERRIFLE, "sniperrifle");
+        feign_death.death_flags = TF_DEATH_FEIGN_DEATH;
+        analyser.handle_player_death_event(&feign_death);
+
+        // The spy is still in the same life
+        let spy = analyser.players.get(UserId::from(3u16)).unwrap();
+        assert!(spy.lives.is_empty());
+        assert_eq!(spy.time_on_class[Class::Spy], 0);
+        assert_eq!(spy.last_spawn_tick, Some(0));
+
+        let summary = analyser.into_output(&state);
+        assert!(player_summary(&summary, 2).weapon_stats.is_empty());
+        assert!(player_summary(&summary, 3).weapon_stats.is_empty());
+    }
+
+    const PLAYER_COND_PROP: SendPropIdentifier =
+        SendPropIdentifier::new("DT_TFPlayerShared", "m_nPlayerCond");
+
+    fn set_tick(analyser: &mut GameDetailsAnalyser, tick: u32) {
+        analyser.demo_tick = DemoTick::from(tick);
+        analyser.server_tick = ServerTick::from(tick);
+    }
+
+    fn set_conditions(
+        analyser: &mut GameDetailsAnalyser,
+        state: &ParserState,
+        user_id: u16,
+        conditions: &[PlayerCondition],
+    ) {
+        let player_cond = conditions.iter().fold(0, |player_cond, condition| {
+            player_cond | 1 << *condition as u32
+        });
+        analyser.handle_player_entity(
+            &player_entity(
+                user_id,
+                vec![(PLAYER_COND_PROP, SendPropValue::Integer(player_cond))],
+            ),
+            state,
+        );
+    }
+
+    fn set_charge(analyser: &mut GameDetailsAnalyser, user_id: u16, charge: u8) {
+        let medic = analyser.players.get_mut(UserId::from(user_id)).unwrap();
+        let old_charge = medic.charge;
+        medic.charge = charge;
+        analyser.handle_charge_change(UserId::from(user_id), old_charge, charge);
+    }
+
+    fn medic_analyser() -> GameDetailsAnalyser {
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Medic);
+        add_player(&mut analyser, 3, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 4, Team::Blue, Class::Scout);
+
+        let medic = analyser.players.get_mut(UserId::from(2u16)).unwrap();
+        medic.heal_target = Some(EntityId::from(3u32));
+
+        set_tick(&mut analyser, 100);
+        set_charge(&mut analyser, 2, 100);
+        analyser
+    }
+
+    #[test]
+    fn test_ubercharge_used_until_drained() {
+        let state = parser_state();
+        let mut analyser = medic_analyser();
+
+        set_tick(&mut analyser, 150);
+        set_conditions(
+            &mut analyser,
+            &state,
+            2,
+            &[PlayerCondition::TF_COND_INVULNERABLE],
+        );
+        set_charge(&mut analyser, 2, 99);
+        set_tick(&mut analyser, 600);
+        set_charge(&mut analyser, 2, 0);
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(
+            summary.ubercharges,
+            vec![Ubercharge {
+                medic: UserId::from(2u16),
+                built_tick: DemoTick::from(100),
+                used_tick: Some(DemoTick::from(150)),
+                end_tick: Some(DemoTick::from(600)),
+                held_ticks: 50,
+                kind: Some(UberchargeKind::Ubercharge),
+                target: Some(UserId::from(3u16)),
+                outcome: Some(UberchargeOutcome::Used),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ubercharge_condition_after_charge_update() {
+        let state = parser_state();
+        let mut analyser = medic_analyser();
+
+        // The charge starts draining one update before the condition arrives
+        set_tick(&mut analyser, 150);
+        set_charge(&mut analyser, 2, 99);
+        assert!(analyser.ubercharges[0].outcome.is_none());
+
+        set_tick(&mut analyser, 151);
+        set_conditions(
+            &mut analyser,
+            &state,
+            2,
+            &[PlayerCondition::TF_COND_INVULNERABLE],
+        );
+
+        let summary = analyser.into_output(&state);
+        let ubercharge = &summary.ubercharges[0];
+        assert_eq!(ubercharge.used_tick, Some(DemoTick::from(151)));
+        assert_eq!(ubercharge.kind, Some(UberchargeKind::Ubercharge));
+        assert_eq!(ubercharge.outcome, Some(UberchargeOutcome::Used));
+        // Still being used when the demo ended
+        assert_eq!(ubercharge.end_tick, None);
+    }
+
+    #[test]
+    fn test_ubercharge_dropped() {
+        let state = parser_state();
+        let mut analyser = medic_analyser();
+
+        set_tick(&mut analyser, 300);
+        analyser.handle_player_death_event(&death_event(
+            2,
+            4,
+            WeaponClass::TF_WEAPON_SCATTERGUN,
+            "scattergun",
+        ));
+
+        let summary = analyser.into_output(&state);
+        let ubercharge = &summary.ubercharges[0];
+        assert_eq!(ubercharge.used_tick, None);
+        assert_eq!(ubercharge.end_tick, Some(DemoTick::from(300)));
+        assert_eq!(ubercharge.held_ticks, 200);
+        assert_eq!(ubercharge.target, Some(UserId::from(3u16)));
+        assert_eq!(ubercharge.outcome, Some(UberchargeOutcome::Dropped));
+    }
+
+    #[test]
+    fn test_ubercharge_faded_on_round_reset() {
+        let state = parser_state();
+        let mut analyser = medic_analyser();
+
+        set_tick(&mut analyser, 300);
+        set_charge(&mut analyser, 2, 0);
+
+        let summary = analyser.into_output(&state);
+        let ubercharge = &summary.ubercharges[0];
+        assert_eq!(ubercharge.used_tick, None);
+        assert_eq!(ubercharge.end_tick, Some(DemoTick::from(300)));
+        assert_eq!(ubercharge.held_ticks, 200);
+        assert_eq!(ubercharge.outcome, Some(UberchargeOutcome::Faded));
+    }
+
+    #[test]
+    fn test_used_ubercharge_stays_used_when_the_medic_dies() {
+        let state = parser_state();
+        let mut analyser = medic_analyser();
+
+        set_tick(&mut analyser, 150);
+        set_conditions(
+            &mut analyser,
+            &state,
+            2,
+            &[PlayerCondition::TF_COND_CRITBOOSTED],
+        );
+        set_charge(&mut analyser, 2, 90);
+        set_tick(&mut analyser, 200);
+        analyser.handle_player_death_event(&death_event(
+            2,
+            4,
+            WeaponClass::TF_WEAPON_SCATTERGUN,
+            "scattergun",
+        ));
+
+        let summary = analyser.into_output(&state);
+        let ubercharge = &summary.ubercharges[0];
+        assert_eq!(ubercharge.used_tick, Some(DemoTick::from(150)));
+        assert_eq!(ubercharge.end_tick, Some(DemoTick::from(200)));
+        assert_eq!(ubercharge.held_ticks, 50);
+        assert_eq!(ubercharge.kind, Some(UberchargeKind::Kritzkrieg));
+        assert_eq!(ubercharge.outcome, Some(UberchargeOutcome::Used));
+    }
+
+    #[test]
+    fn test_crit_boosted_medic_holding_a_charge() {
+        let state = parser_state();
+        let mut analyser = medic_analyser();
+
+        // Crit boosted by another medic's Kritzkrieg while the own charge is still full
+        set_tick(&mut analyser, 150);
+        set_conditions(
+            &mut analyser,
+            &state,
+            2,
+            &[PlayerCondition::TF_COND_CRITBOOSTED],
+        );
+
+        let summary = analyser.into_output(&state);
+        let ubercharge = &summary.ubercharges[0];
+        assert_eq!(ubercharge.used_tick, None);
+        assert_eq!(ubercharge.kind, None);
+        assert_eq!(ubercharge.outcome, None);
+        assert_eq!(ubercharge.held_ticks, 50);
+    }
+
+    #[test]
+    fn test_pauses_are_excluded_from_durations() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+
+        let advance = |analyser: &mut GameDetailsAnalyser, tick: u32, pause: Option<bool>| {
+            let demo_tick = DemoTick::from(tick);
+            let net_tick = Message::NetTick(NetTickMessage {
+                tick: ServerTick::from(1000 + tick),
+                frame_time: 0,
+                std_dev: 0,
+            });
+            analyser.handle_message(&net_tick, demo_tick, &state);
+
+            if let Some(pause) = pause {
+                let set_pause = Message::SetPause(SetPauseMessage { pause });
+                analyser.handle_message(&set_pause, demo_tick, &state);
+            }
+        };
+
+        advance(&mut analyser, 0, None);
+        let packet_entities = Message::PacketEntities(PacketEntitiesMessage {
+            entities: vec![],
+            removed_entities: vec![],
+            max_entries: 0,
+            delta: None,
+            base_line: BaselineIndex::First,
+            updated_base_line: false,
+        });
+        analyser.handle_message(&packet_entities, DemoTick::from(0), &state);
+        let spawn_tick = analyser.game_tick();
+        for user_id in [2u16, 3] {
+            let player = analyser.players.get_mut(UserId::from(user_id)).unwrap();
+            player.life_state = PlayerLifeState::Alive;
+            player.last_spawn_tick = Some(spawn_tick);
+        }
+
+        advance(&mut analyser, 100, Some(true));
+        // A repeated pause message does not restart the pause
+        advance(&mut analyser, 150, Some(true));
+        advance(&mut analyser, 300, Some(false));
+
+        advance(&mut analyser, 400, None);
+        analyser.handle_player_death_event(&death_event(
+            2,
+            3,
+            WeaponClass::TF_WEAPON_SCATTERGUN,
+            "scattergun",
+        ));
+
+        // Still paused when the demo ends
+        advance(&mut analyser, 500, Some(true));
+        advance(&mut analyser, 600, None);
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(
+            summary.pauses,
+            vec![
+                PauseInterval {
+                    start_tick: DemoTick::from(100),
+                    end_tick: Some(DemoTick::from(300)),
+                },
+                PauseInterval {
+                    start_tick: DemoTick::from(500),
+                    end_tick: None,
+                },
+            ]
+        );
+        assert_eq!(summary.rounds[0].duration, 300);
+
+        let real_class_index = |class: Class| class as usize - 1;
+        let soldier = player_summary(&summary, 2);
+        assert_eq!(soldier.time_on_class[real_class_index(Class::Soldier)], 200);
+        let scout = player_summary(&summary, 3);
+        assert_eq!(scout.time_on_class[real_class_index(Class::Scout)], 300);
+    }
+
+    #[test]
+    fn test_midairs_of_the_local_player() {
+        let midairs = |local_user_id: u32| {
+            let mut analyser = GameDetailsAnalyser {
+                is_stv: true,
+                local_entity_id: EntityId::from(local_user_id),
+                ..GameDetailsAnalyser::default()
+            };
+            add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+            add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+            analyser
+                .players
+                .get_mut(UserId::from(3u16))
+                .unwrap()
+                .airborne_since = Some(0);
+            analyser.server_tick = ServerTick::from(100);
+
+            analyser.handle_player_death_event(&death_event(
+                3,
+                2,
+                WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+                "tf_projectile_rocket",
+            ));
+
+            analyser
+                .highlights
+                .iter()
+                .filter(|highlight| matches!(highlight.event, Highlight::Midair { .. }))
+                .count()
+        };
+
+        // Only the STV bot is the local player
+        assert_eq!(midairs(1), 0);
+        assert_eq!(midairs(2), 1);
+    }
+
+    #[test]
+    fn test_stv_midairs_are_opt_in() {
+        let stv_midairs = |enabled: bool| {
+            let mut analyser = GameDetailsAnalyser::new(HighlightConfig {
+                stv_midairs: enabled,
+                ..HighlightConfig::default()
+            });
+            analyser.is_stv = true;
+            add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+            add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+            analyser
+                .players
+                .get_mut(UserId::from(3u16))
+                .unwrap()
+                .airborne_since = Some(0);
+            analyser.server_tick = ServerTick::from(100);
+
+            analyser.handle_player_death_event(&death_event(
+                3,
+                2,
+                WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+                "tf_projectile_rocket",
+            ));
+
+            analyser
+                .highlights
+                .iter()
+                .filter(|highlight| matches!(highlight.event, Highlight::Midair { .. }))
+                .count()
+        };
+
+        assert_eq!(stv_midairs(false), 0);
+        assert_eq!(stv_midairs(true), 1);
+    }
+
+    #[test]
+    fn test_last_man_standing_in_arena() {
+        let mut analyser = GameDetailsAnalyser::default();
+        analyser.game_mode.set_map_name("arena_lumberyard");
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Red, Class::Scout);
+        add_player(&mut analyser, 4, Team::Blue, Class::Demoman);
+        add_player(&mut analyser, 5, Team::Blue, Class::Medic);
+        for player in analyser.players.players.values_mut() {
+            player.life_state = PlayerLifeState::Alive;
+        }
+
+        let kill_and_count = |analyser: &mut GameDetailsAnalyser, victim: u16, attacker: u16| {
+            analyser.handle_player_death_event(&death_event(
+                victim,
+                attacker,
+                WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+                "tf_projectile_rocket",
+            ));
+            analyser
+                .players
+                .get_mut(UserId::from(victim))
+                .unwrap()
+                .life_state = PlayerLifeState::Death;
+
+            analyser
+                .highlights
+                .iter()
+                .filter(|highlight| matches!(highlight.event, Highlight::LastManStanding { .. }))
+                .count()
+        };
+
+        assert_eq!(kill_and_count(&mut analyser, 3, 4), 1);
+        assert!(matches!(
+            &analyser.highlights.last().unwrap().event,
+            Highlight::LastManStanding { player, enemies_alive: 2 }
+                if player.user_id == UserId::from(2u16)
+        ));
+
+        // The last enemy standing is recorded once
+        assert_eq!(kill_and_count(&mut analyser, 5, 2), 2);
+    }
+
+    #[test]
+    fn test_flag_events() {
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Scout);
+
+        let flag_event = |player: u16, event_type: FlagEventKind| TeamPlayFlagEventEvent {
+            player,
+            carrier: 0,
+            event_type: event_type as u16,
+            home: 0,
+            team: Team::Blue as u8,
+        };
+        analyser.handle_flag_event(&flag_event(2, FlagEventKind::PickedUp));
+        analyser.handle_flag_event(&flag_event(0, FlagEventKind::Returned));
+
+        assert!(matches!(
+            &analyser.highlights[0].event,
+            Highlight::Flag { player: Some(player), team: Team::Blue, kind: FlagEventKind::PickedUp }
+                if player.user_id == UserId::from(2u16)
+        ));
+        assert!(matches!(
+            &analyser.highlights[1].event,
+            Highlight::Flag {
+                player: None,
+                kind: FlagEventKind::Returned,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_robot_kills() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        analyser.game_mode.set_playing_mvm(true);
+        add_player(&mut analyser, 2, Team::Red, Class::Heavy);
+        add_player(&mut analyser, 3, Team::Blue, Class::Heavy);
+        let robot = analyser.players.get_mut(UserId::from(3u16)).unwrap();
+        robot.is_bot = true;
+        robot.robot_kind = RobotKind::Giant;
+
+        analyser.handle_begin_wave_event(&MvmBeginWaveEvent {
+            wave_index: 0,
+            max_waves: 7,
+            advanced: 0,
+        });
+        analyser.handle_player_death_event(&death_event(
+            3,
+            2,
+            WeaponClass::TF_WEAPON_MINIGUN,
+            "minigun",
+        ));
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(player_summary(&summary, 2).mvm_stats.robot_kills.giants, 1);
+        assert_eq!(summary.waves[0].robot_kills.giants, 1);
+    }
+
+    #[test]
+    fn test_tank_damage() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Heavy);
+
+        let tank = EntityId::from(100u32);
+        analyser.mvm.add_tank(tank);
+
+        let tank_hurt = |health: u16, damage_amount: u16| NpcHurtEvent {
+            ent_index: 100,
+            health,
+            attacker_player: 2,
+            weapon_id: WeaponClass::TF_WEAPON_MINIGUN as u16,
+            damage_amount,
+            crit: false,
+            boss: 0,
+        };
+        analyser.handle_npc_hurt_event(&tank_hurt(100, 500));
+        analyser.handle_npc_hurt_event(&tank_hurt(0, 100));
+        // The tank is destroyed, later events are ignored
+        analyser.handle_npc_hurt_event(&tank_hurt(0, 100));
+
+        let summary = analyser.into_output(&state);
+        let mvm_stats = &player_summary(&summary, 2).mvm_stats;
+        assert_eq!(mvm_stats.tank_damage, 600);
+        assert_eq!(mvm_stats.robot_kills.tanks, 1);
+    }
+
+    #[test]
+    fn test_players_leaving_during_pregame_are_excluded() {
+        const WAITING_FOR_PLAYERS_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TeamplayRoundBasedRules", "m_bInWaitingForPlayers");
+
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+        add_player(&mut analyser, 4, Team::Blue, Class::Scout);
+
+        let waiting_for_players = |waiting: bool| {
+            packet_entity(
+                50,
+                UpdateType::Preserve,
+                vec![(
+                    WAITING_FOR_PLAYERS_PROP,
+                    SendPropValue::Integer(waiting.into()),
+                )],
+            )
+        };
+        let disconnect = |user_id: u16| PlayerDisconnectEvent {
+            user_id,
+            reason: "Disconnect by user.".into(),
+            name: format!("player{user_id}").as_str().into(),
+            network_id: format!("[U:1:{user_id}]").as_str().into(),
+            bot: 0,
+        };
+
+        analyser.handle_game_rules(&waiting_for_players(true), &state);
+        analyser.handle_player_disconnect_event(&disconnect(3));
+        analyser.handle_game_rules(&waiting_for_players(false), &state);
+        analyser.handle_player_disconnect_event(&disconnect(4));
+
+        let summary = analyser.into_output(&state);
+        let user_ids: Vec<_> = summary
+            .players
+            .iter()
+            .map(|player| player.user_id)
+            .collect();
+        assert!(!user_ids.contains(&UserId::from(3u16)));
+        assert!(user_ids.contains(&UserId::from(4u16)));
+    }
+
+    #[test]
+    fn test_match_group_from_game_rules() {
+        const MATCH_GROUP_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFGameRules", "m_nMatchGroupType");
+
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        analyser.handle_game_rules(
+            &packet_entity(
+                50,
+                UpdateType::Preserve,
+                vec![(MATCH_GROUP_PROP, SendPropValue::Integer(6))],
+            ),
+            &state,
+        );
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(summary.match_info.match_type, MatchType::ValveCasual);
+        assert_eq!(summary.match_info.format, MatchFormat::Highlander);
+    }
+
+    #[test]
+    fn test_convar_changes() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+
+        let set_convar = |name: &str, value: &str| {
+            Message::SetConVar(SetConVarMessage {
+                length: 1,
+                vars: vec![ConVar {
+                    key: name.into(),
+                    value: value.into(),
+                }],
+            })
+        };
+
+        analyser.handle_message(&set_convar("mp_tournament", "0"), DemoTick::from(1), &state);
+        analyser.initial_packet_entities_parsed = true;
+        analyser.handle_message(
+            &set_convar("mp_tournament", "1"),
+            DemoTick::from(100),
+            &state,
+        );
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(summary.server.convar("mp_tournament"), Some("1"));
+        // The initial value is not a change
+        assert_eq!(summary.server.convar_changes.len(), 1);
+        assert_eq!(summary.server.convar_changes[0].tick, DemoTick::from(100));
+        assert!(summary.match_info.tournament_mode);
+    }
+
+    #[test]
+    fn test_voice_intervals() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+
+        let bytes = [0u8; 4];
+        // Player slot 1 is entity 2
+        let voice_data = || {
+            Message::VoiceData(VoiceDataMessage {
+                client: 1,
+                proximity: 0,
+                length: 32,
+                data: tf_demo_parser::Demo::new(&bytes).get_stream(),
+            })
+        };
+        analyser.handle_message(&voice_data(), DemoTick::from(100), &state);
+        analyser.handle_message(&voice_data(), DemoTick::from(110), &state);
+        // More than half a second later
+        analyser.handle_message(&voice_data(), DemoTick::from(200), &state);
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(summary.voice.len(), 2);
+        assert_eq!(summary.voice[0].user_id, UserId::from(2u16));
+        assert_eq!(summary.voice[0].end_tick, DemoTick::from(110));
+    }
+
+    #[test]
+    fn test_chat_log() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        analyser
+            .players
+            .get_mut(UserId::from(2u16))
+            .unwrap()
+            .life_state = PlayerLifeState::Alive;
+
+        analyser.handle_chat_message(&SayText2Message {
+            client: EntityId::from(2u32),
+            raw: 1,
+            kind: ChatMessageKind::ChatTeam,
+            from: Some("player2".into()),
+            text: "push".into(),
+        });
+        analyser.handle_chat_message(&SayText2Message {
+            client: EntityId::from(2u32),
+            raw: 1,
+            kind: ChatMessageKind::NameChange,
+            from: Some("player2".into()),
+            text: "renamed".into(),
+        });
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(summary.chat_log.len(), 2);
+
+        let message = &summary.chat_log[0];
+        assert_eq!(message.kind, ChatKind::Team);
+        assert_eq!(message.team, Team::Red);
+        assert!(message.sender_alive);
+        assert_eq!(message.text, "push");
+
+        // The old name, with the new name as the text
+        let name_change = &summary.chat_log[1];
+        assert_eq!(name_change.kind, ChatKind::NameChange);
+        assert_eq!(name_change.name, "player2");
+        assert_eq!(name_change.text, "renamed");
+    }
+
+    #[test]
+    fn test_player_resource_ping_and_score() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+
+        let player_resource = |props: Vec<(&str, i64)>| {
+            packet_entity(
+                40,
+                UpdateType::Preserve,
+                props
+                    .into_iter()
+                    .map(|(table, value)| {
+                        (
+                            SendPropIdentifier::new(table, "002"),
+                            SendPropValue::Integer(value),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+
+        // The first ping arrives before the connection state
+        analyser.handle_player_resource(
+            &player_resource(vec![("m_iPing", 50), ("m_bConnected", 1), ("m_iScore", 7)]),
+            &state,
+        );
+        set_tick(&mut analyser, 100);
+        analyser.handle_player_resource(&player_resource(vec![("m_iPing", 300)]), &state);
+        set_tick(&mut analyser, 200);
+        analyser.handle_player_resource(&player_resource(vec![("m_iPing", 50)]), &state);
+
+        let spikes = analyser
+            .highlights
+            .iter()
+            .filter(|highlight| matches!(highlight.event, Highlight::PingSpike { peak: 300, .. }))
+            .count();
+        assert_eq!(spikes, 1);
+
+        let summary = analyser.into_output(&state);
+        let player = player_summary(&summary, 2);
+        assert_eq!(player.score, 7);
+
+        let ping = player.ping.as_ref().unwrap();
+        assert_eq!(ping.min, 50);
+        assert_eq!(ping.max, 300);
+        // (50 * 100 + 300 * 100) / 200
+        assert_eq!(ping.average, 175);
+    }
+
+    #[test]
+    fn test_low_health_survival() {
+        const HEALTH_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BasePlayer", "m_iHealth");
+
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Scout);
+        analyser
+            .players
+            .get_mut(UserId::from(2u16))
+            .unwrap()
+            .life_state = PlayerLifeState::Alive;
+
+        for (tick, health) in [(100, 125), (200, 8), (300, 4), (400, 125)] {
+            analyser.demo_tick = DemoTick::from(tick);
+            analyser.handle_player_entity(
+                &player_entity(2, vec![(HEALTH_PROP, SendPropValue::Integer(health))]),
+                &state,
+            );
+        }
+
+        let [survival] = analyser.highlights.as_slice() else {
+            panic!("expected a single highlight");
+        };
+        assert_eq!(survival.tick, DemoTick::from(300));
+        assert!(matches!(
+            survival.event,
+            Highlight::LowHealthSurvival { health: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn test_overhealed_kill() {
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Red, Class::Soldier);
+        add_player(&mut analyser, 3, Team::Blue, Class::Scout);
+        add_player(&mut analyser, 4, Team::Red, Class::Medic);
+        for player in analyser.players.players.values_mut() {
+            player.life_state = PlayerLifeState::Alive;
+        }
+
+        let soldier = analyser.players.get_mut(UserId::from(2u16)).unwrap();
+        soldier.max_health = 200;
+        soldier.health.update(300, 200, 0, DemoTick::from(100), 100);
+        analyser
+            .players
+            .get_mut(UserId::from(4u16))
+            .unwrap()
+            .heal_target = Some(EntityId::from(2u32));
+
+        analyser.handle_player_death_event(&death_event(
+            3,
+            2,
+            WeaponClass::TF_WEAPON_ROCKETLAUNCHER,
+            "tf_projectile_rocket",
+        ));
+
+        assert!(analyser.highlights.iter().any(|highlight| matches!(
+            &highlight.event,
+            Highlight::OverhealedKill { health: 300, max_health: 200, medic: Some(medic), .. }
+                if medic.user_id == UserId::from(4u16)
+        )));
+    }
+
+    #[test]
+    fn test_class_changes() {
+        let state = parser_state();
+        let mut analyser = GameDetailsAnalyser::default();
+        add_player(&mut analyser, 2, Team::Other, Class::Other);
+
+        let spawn = |class: Class| PlayerSpawnEvent {
+            user_id: 2,
+            team: Team::Red as u16,
+            class: class as u16,
+        };
+        // The first spawn is not a class change
+        analyser.handle_player_spawn_event(&spawn(Class::Scout));
+        analyser.demo_tick = DemoTick::from(100);
+        analyser.handle_player_spawn_event(&spawn(Class::Scout));
+        analyser.demo_tick = DemoTick::from(200);
+        analyser.handle_player_spawn_event(&spawn(Class::Sniper));
+
+        let summary = analyser.into_output(&state);
+        assert_eq!(
+            summary.class_changes,
+            vec![ClassChange {
+                tick: DemoTick::from(200),
+                user_id: UserId::from(2u16),
+                team: Team::Red,
+                from: Class::Scout,
+                to: Class::Sniper,
+            }]
+        );
+        assert_eq!(summary.team_compositions.len(), 2);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, parser::analyser::UserId};
+
+use super::HighlightPlayerSnapshot;
+
+/// Settings for detecting multikills.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultikillConfig {
+    /// The minimum number of kills that count as a multikill
+    pub min_kills: usize,
+
+    /// The maximum number of game ticks between
+    /// the first and the last kill of a multikill
+    pub window: u32,
+}
+
+impl Default for MultikillConfig {
+    fn default() -> Self {
+        Self {
+            min_kills: 2,
+            // About three seconds at 66 ticks per second
+            window: 200,
+        }
+    }
+}
+
+/// A finished multikill, ready to be turned into a highlight.
+#[derive(Debug, PartialEq)]
+pub struct Multikill {
+    pub killer: HighlightPlayerSnapshot,
+    pub victims: Vec<HighlightPlayerSnapshot>,
+    pub weapons: Vec<String>,
+    pub start_tick: DemoTick,
+    pub end_tick: DemoTick,
+}
+
+#[derive(Debug)]
+struct PendingMultikill {
+    /// The game tick of the first kill, used to check the window
+    first_game_tick: u32,
+    multikill: Multikill,
+}
+
+/// Keeps track of recent kills of every player to find multikills.
+///
+/// Ticks are passed twice: The demo tick is used for the resulting highlight,
+/// while the "game tick" excludes paused time and is used to check the window.
+#[derive(Debug, Default)]
+pub struct MultikillTracker {
+    config: MultikillConfig,
+    pending: HashMap<UserId, PendingMultikill>,
+}
+
+impl MultikillTracker {
+    pub fn new(config: MultikillConfig) -> Self {
+        Self {
+            config,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Record a kill. If this kill falls outside the window of the killer's
+    /// previous kills, those are returned if they make up a multikill.
+    pub fn record_kill(
+        &mut self,
+        killer: HighlightPlayerSnapshot,
+        victim: HighlightPlayerSnapshot,
+        weapon: String,
+        tick: DemoTick,
+        game_tick: u32,
+    ) -> Option<Multikill> {
+        let mut finished = None;
+
+        if let Some(pending) = self.pending.get_mut(&killer.user_id) {
+            if game_tick.saturating_sub(pending.first_game_tick) <= self.config.window {
+                pending.multikill.victims.push(victim);
+                pending.multikill.weapons.push(weapon);
+                pending.multikill.end_tick = tick;
+
+                return None;
+            }
+
+            finished = self.flush_player(killer.user_id);
+        }
+
+        self.pending.insert(
+            killer.user_id,
+            PendingMultikill {
+                first_game_tick: game_tick,
+                multikill: Multikill {
+                    killer,
+                    victims: vec![victim],
+                    weapons: vec![weapon],
+                    start_tick: tick,
+                    end_tick: tick,
+                },
+            },
+        );
+
+        finished
+    }
+
+    /// Stop tracking the kills of a single player.
+    pub fn flush_player(&mut self, user_id: UserId) -> Option<Multikill> {
+        self.pending
+            .remove(&user_id)
+            .map(|pending| pending.multikill)
+            .filter(|multikill| multikill.victims.len() >= self.config.min_kills)
+    }
+
+    /// Stop tracking the kills of all players, for example at the end of a round.
+    pub fn flush(&mut self) -> Vec<Multikill> {
+        let min_kills = self.config.min_kills;
+
+        let mut multikills: Vec<Multikill> = self
+            .pending
+            .drain()
+            .map(|(_user_id, pending)| pending.multikill)
+            .filter(|multikill| multikill.victims.len() >= min_kills)
+            .collect();
+
+        multikills.sort_by_key(|multikill| multikill.end_tick);
+
+        multikills
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(user_id: u16) -> HighlightPlayerSnapshot {
+        HighlightPlayerSnapshot {
+            user_id: user_id.into(),
+            ..Default::default()
+        }
+    }
+
+    fn kill(tracker: &mut MultikillTracker, killer: u16, tick: u32) -> Option<Multikill> {
+        tracker.record_kill(
+            player(killer),
+            player(99),
+            "scattergun".into(),
+            tick.into(),
+            tick,
+        )
+    }
+
+    #[test]
+    fn test_multikill_window() {
+        let mut tracker = MultikillTracker {
+            config: MultikillConfig {
+                min_kills: 2,
+                window: 100,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(kill(&mut tracker, 1, 1000), None);
+        assert_eq!(kill(&mut tracker, 1, 1050), None);
+        assert_eq!(kill(&mut tracker, 1, 1100), None);
+
+        // Outside of the window, finishes the triple kill
+        let multikill = kill(&mut tracker, 1, 1101).expect("expected a multikill");
+        assert_eq!(multikill.victims.len(), 3);
+        assert_eq!(multikill.start_tick, 1000);
+        assert_eq!(multikill.end_tick, 1100);
+
+        // A single kill is not a multikill
+        assert!(tracker.flush().is_empty());
+    }
+
+    #[test]
+    fn test_multikill_separate_players() {
+        let mut tracker = MultikillTracker::default();
+
+        kill(&mut tracker, 1, 1000);
+        kill(&mut tracker, 2, 1010);
+        kill(&mut tracker, 2, 1020);
+
+        let multikills = tracker.flush();
+        assert_eq!(multikills.len(), 1);
+        assert_eq!(multikills[0].killer.user_id, UserId::from(2u16));
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, message::packetentities::EntityId};
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RobotKind {
+    #[default]
+    Regular,
+    /// A giant robot (`m_bIsMiniBoss`)
+    Giant,
+    /// A robot with a boss health bar, like Sergeant Crits
+    Boss,
+    Tank,
+}
+
+impl RobotKind {
+    /// Update the kind of a robot player after `m_bIsMiniBoss` or `m_bUseBossHealthBar` changed.
+    /// `None` means the prop was not part of the update.
+    #[must_use]
+    pub fn update(self, is_mini_boss: Option<bool>, use_boss_health_bar: Option<bool>) -> Self {
+        let is_mini_boss = is_mini_boss.unwrap_or(self != Self::Regular);
+        let use_boss_health_bar = use_boss_health_bar.unwrap_or(self == Self::Boss);
+
+        if use_boss_health_bar {
+            Self::Boss
+        } else if is_mini_boss {
+            Self::Giant
+        } else {
+            Self::Regular
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RobotKills {
+    pub regular: u32,
+    pub giants: u32,
+    pub bosses: u32,
+    pub tanks: u32,
+}
+
+impl RobotKills {
+    pub fn add(&mut self, kind: RobotKind) {
+        match kind {
+            RobotKind::Regular => self.regular += 1,
+            RobotKind::Giant => self.giants += 1,
+            RobotKind::Boss => self.bosses += 1,
+            RobotKind::Tank => self.tanks += 1,
+        }
+    }
+}
+
+/// Mann vs. Machine statistics of a single defender
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MvmPlayerStats {
+    pub robot_kills: RobotKills,
+    pub tank_damage: u32,
+    pub credits_collected: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WaveOutcome {
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct WaveSummary {
+    /// Zero-based
+    pub wave: u16,
+    pub max_waves: u16,
+    pub start_tick: DemoTick,
+    /// `None` if the demo ended during the wave
+    pub end_tick: Option<DemoTick>,
+    /// In ticks, excluding pauses
+    pub duration: u32,
+    pub outcome: Option<WaveOutcome>,
+    pub credits_collected: u32,
+    pub robot_kills: RobotKills,
+    /// Upgrades bought before and during this wave.
+    /// The `player_upgraded` event does not say who bought what, so this is only a count.
+    pub upgrades_purchased: u32,
+}
+
+/// Tracks the waves of a Mann vs. Machine demo, similar to the `RoundTracker`
+#[derive(Debug, Default)]
+pub struct MvmTracker {
+    waves: Vec<WaveSummary>,
+    // The game tick at which the current wave started, `None` between waves
+    wave_start_game_tick: Option<u32>,
+    // Upgrades bought since the last wave started
+    pending_upgrades: u32,
+    // Entity IDs of the tanks that are currently alive
+    tanks: HashSet<EntityId>,
+}
+
+impl MvmTracker {
+    pub fn begin_wave(&mut self, wave: u16, max_waves: u16, tick: DemoTick, game_tick: u32) {
+        // A wave can be restarted after it was failed, without an end event in between
+        self.end_wave(None, tick, game_tick);
+
+        self.waves.push(WaveSummary {
+            wave,
+            max_waves,
+            start_tick: tick,
+            end_tick: None,
+            duration: 0,
+            outcome: None,
+            credits_collected: 0,
+            robot_kills: RobotKills::default(),
+            upgrades_purchased: std::mem::take(&mut self.pending_upgrades),
+        });
+        self.wave_start_game_tick = Some(game_tick);
+    }
+
+    /// End the current wave, if there is one.
+    /// Returns the index of the wave that ended.
+    pub fn end_wave(
+        &mut self,
+        outcome: Option<WaveOutcome>,
+        tick: DemoTick,
+        game_tick: u32,
+    ) -> Option<u16> {
+        let start_game_tick = self.wave_start_game_tick.take()?;
+        let wave = self.waves.last_mut()?;
+
+        wave.end_tick = Some(tick);
+        wave.duration = game_tick.saturating_sub(start_game_tick);
+        wave.outcome = outcome;
+
+        Some(wave.wave)
+    }
+
+    /// The wave that is currently being played
+    fn current_wave_mut(&mut self) -> Option<&mut WaveSummary> {
+        self.wave_start_game_tick?;
+        self.waves.last_mut()
+    }
+
+    pub fn current_wave(&self) -> Option<u16> {
+        self.wave_start_game_tick?;
+        self.waves.last().map(|wave| wave.wave)
+    }
+
+    pub fn record_credits(&mut self, amount: u32) {
+        // Credits that are picked up right after a wave ended still belong to it
+        if let Some(wave) = self.waves.last_mut() {
+            wave.credits_collected += amount;
+        }
+    }
+
+    pub fn record_robot_kill(&mut self, kind: RobotKind) {
+        if let Some(wave) = self.current_wave_mut() {
+            wave.robot_kills.add(kind);
+        }
+    }
+
+    pub fn record_upgrade(&mut self) {
+        match self.current_wave_mut() {
+            Some(wave) => wave.upgrades_purchased += 1,
+            None => self.pending_upgrades += 1,
+        }
+    }
+
+    pub fn add_tank(&mut self, entity_id: EntityId) {
+        self.tanks.insert(entity_id);
+    }
+
+    pub fn is_tank(&self, entity_id: EntityId) -> bool {
+        self.tanks.contains(&entity_id)
+    }
+
+    /// Returns `true` if the entity was a tank that was still alive
+    pub fn remove_tank(&mut self, entity_id: EntityId) -> bool {
+        self.tanks.remove(&entity_id)
+    }
+
+    pub fn finish(mut self, game_tick: u32) -> Vec<WaveSummary> {
+        if let Some(start_game_tick) = self.wave_start_game_tick {
+            if let Some(wave) = self.waves.last_mut() {
+                wave.duration = game_tick.saturating_sub(start_game_tick);
+            }
+        }
+
+        self.waves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waves() {
+        let mut tracker = MvmTracker::default();
+
+        tracker.begin_wave(0, 7, DemoTick::from(1000), 1000);
+        assert_eq!(tracker.current_wave(), Some(0));
+
+        assert_eq!(
+            tracker.end_wave(Some(WaveOutcome::Failed), DemoTick::from(2000), 2000),
+            Some(0)
+        );
+        assert_eq!(tracker.current_wave(), None);
+        // Only the current wave can end
+        assert_eq!(tracker.end_wave(None, DemoTick::from(2100), 2100), None);
+
+        // Retrying the wave, until the demo ends
+        tracker.begin_wave(0, 7, DemoTick::from(3000), 3000);
+
+        let waves = tracker.finish(3500);
+        assert_eq!(waves.len(), 2);
+
+        assert_eq!(waves[0].outcome, Some(WaveOutcome::Failed));
+        assert_eq!(waves[0].end_tick, Some(DemoTick::from(2000)));
+        assert_eq!(waves[0].duration, 1000);
+
+        assert_eq!(waves[1].outcome, None);
+        assert_eq!(waves[1].end_tick, None);
+        assert_eq!(waves[1].duration, 500);
+    }
+
+    #[test]
+    fn test_wave_credits_and_kills() {
+        let mut tracker = MvmTracker::default();
+
+        tracker.begin_wave(0, 7, DemoTick::from(1000), 1000);
+        tracker.record_robot_kill(RobotKind::Regular);
+        tracker.record_robot_kill(RobotKind::Giant);
+        tracker.record_credits(50);
+        tracker.end_wave(Some(WaveOutcome::Completed), DemoTick::from(2000), 2000);
+
+        // Collected after the wave ended
+        tracker.record_credits(25);
+        // Robots that die between waves don't count
+        tracker.record_robot_kill(RobotKind::Regular);
+
+        let waves = tracker.finish(3000);
+        assert_eq!(waves[0].credits_collected, 75);
+        assert_eq!(waves[0].robot_kills.regular, 1);
+        assert_eq!(waves[0].robot_kills.giants, 1);
+    }
+
+    #[test]
+    fn test_wave_upgrades() {
+        let mut tracker = MvmTracker::default();
+
+        // Bought before the first wave
+        tracker.record_upgrade();
+
+        tracker.begin_wave(0, 7, DemoTick::from(1000), 1000);
+        tracker.record_upgrade();
+        tracker.end_wave(Some(WaveOutcome::Completed), DemoTick::from(2000), 2000);
+
+        tracker.record_upgrade();
+        tracker.begin_wave(1, 7, DemoTick::from(3000), 3000);
+
+        let waves = tracker.finish(3500);
+        assert_eq!(waves[0].upgrades_purchased, 2);
+        assert_eq!(waves[1].upgrades_purchased, 1);
+    }
+
+    #[test]
+    fn test_robot_kind_update() {
+        assert_eq!(
+            RobotKind::Regular.update(Some(true), None),
+            RobotKind::Giant
+        );
+        assert_eq!(RobotKind::Giant.update(None, Some(true)), RobotKind::Boss);
+        assert_eq!(RobotKind::Boss.update(None, Some(false)), RobotKind::Giant);
+        assert_eq!(
+            RobotKind::Boss.update(Some(false), Some(false)),
+            RobotKind::Regular
+        );
+    }
+}
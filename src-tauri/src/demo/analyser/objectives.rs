@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use num_derive::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{
+    data::DemoTick,
+    message::packetentities::EntityId,
+    parser::analyser::{Team, UserId},
+};
+
+/// Values of `eventtype` in the `teamplay_flag_event` game event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, FromPrimitive)]
+pub enum FlagEventKind {
+    PickedUp = 1,
+    Captured = 2,
+    Defended = 3,
+    Dropped = 4,
+    Returned = 5,
+}
+
+/// The remaining time of both KOTH timers, recorded whenever the point changes hands
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct KothTimers {
+    pub tick: DemoTick,
+    /// The team that just captured the point
+    pub owner: Team,
+    /// In seconds
+    pub red_time_remaining: f32,
+    pub blue_time_remaining: f32,
+}
+
+/// Progress of a payload cart, recorded when it moves or the number of pushers changes
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CartProgress {
+    pub tick: DemoTick,
+    /// The team pushing the cart
+    pub team: Team,
+    /// From 0 to 1
+    pub progress: f32,
+    pub pushers: u8,
+}
+
+/// Mode-specific objective timelines that don't fit into highlights
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Objectives {
+    pub koth_timers: Vec<KothTimers>,
+    pub cart_progress: Vec<CartProgress>,
+}
+
+/// State of a `CTeamRoundTimer` entity
+#[derive(Debug, Default)]
+pub struct RoundTimer {
+    pub paused: bool,
+    /// Only valid while the timer is paused
+    pub time_remaining: f32,
+    /// Server time at which the timer runs out, only valid while the timer is running
+    pub end_time: f32,
+}
+
+impl RoundTimer {
+    fn time_remaining(&self, server_time: f32) -> f32 {
+        if self.paused {
+            self.time_remaining
+        } else {
+            (self.end_time - server_time).max(0.0)
+        }
+    }
+}
+
+/// State of a `CTeamTrainWatcher` entity
+#[derive(Debug)]
+pub struct Cart {
+    pub team: Team,
+    pub progress: f32,
+    pub pushers: u8,
+    last_recorded: Option<(f32, u8)>,
+}
+
+impl Default for Cart {
+    fn default() -> Self {
+        Self {
+            team: Team::Other,
+            progress: 0.0,
+            pushers: 0,
+            last_recorded: None,
+        }
+    }
+}
+
+/// Samples closer than this are not recorded, unless the number of pushers changed
+const MIN_CART_PROGRESS: f32 = 0.01;
+
+#[derive(Debug, Default)]
+pub struct ObjectiveTracker {
+    timers: HashMap<EntityId, RoundTimer>,
+    red_koth_timer: Option<EntityId>,
+    blue_koth_timer: Option<EntityId>,
+
+    carts: HashMap<EntityId, Cart>,
+
+    // Players that were the last one alive on their team in the current arena round
+    last_men_standing: Vec<UserId>,
+
+    output: Objectives,
+}
+
+impl ObjectiveTracker {
+    pub fn timer_mut(&mut self, entity_id: EntityId) -> &mut RoundTimer {
+        self.timers.entry(entity_id).or_default()
+    }
+
+    pub fn set_koth_timer(&mut self, team: Team, entity_id: Option<EntityId>) {
+        match team {
+            Team::Red => self.red_koth_timer = entity_id,
+            Team::Blue => self.blue_koth_timer = entity_id,
+            _ => {}
+        }
+    }
+
+    fn koth_time_remaining(&self, timer: Option<EntityId>, server_time: f32) -> f32 {
+        timer
+            .and_then(|entity_id| self.timers.get(&entity_id))
+            .map_or(0.0, |timer| timer.time_remaining(server_time))
+    }
+
+    pub fn record_koth_timers(&mut self, tick: DemoTick, owner: Team, server_time: f32) {
+        let red_time_remaining = self.koth_time_remaining(self.red_koth_timer, server_time);
+        let blue_time_remaining = self.koth_time_remaining(self.blue_koth_timer, server_time);
+
+        self.output.koth_timers.push(KothTimers {
+            tick,
+            owner,
+            red_time_remaining,
+            blue_time_remaining,
+        });
+    }
+
+    pub fn cart_mut(&mut self, entity_id: EntityId) -> &mut Cart {
+        self.carts.entry(entity_id).or_default()
+    }
+
+    /// Record the progress of a cart after its entity was updated
+    pub fn record_cart_progress(&mut self, entity_id: EntityId, tick: DemoTick) {
+        let Some(cart) = self.carts.get_mut(&entity_id) else {
+            return;
+        };
+
+        let is_due = cart.last_recorded.map_or(true, |(progress, pushers)| {
+            pushers != cart.pushers || (cart.progress - progress).abs() >= MIN_CART_PROGRESS
+        });
+
+        if is_due {
+            cart.last_recorded = Some((cart.progress, cart.pushers));
+            self.output.cart_progress.push(CartProgress {
+                tick,
+                team: cart.team,
+                progress: cart.progress,
+                pushers: cart.pushers,
+            });
+        }
+    }
+
+    pub fn remove_entity(&mut self, entity_id: EntityId) {
+        self.timers.remove(&entity_id);
+        self.carts.remove(&entity_id);
+    }
+
+    pub fn start_round(&mut self) {
+        self.last_men_standing.clear();
+    }
+
+    /// Returns `false` if this player was already the last man standing in the current round
+    pub fn record_last_man_standing(&mut self, user_id: UserId) -> bool {
+        if self.last_men_standing.contains(&user_id) {
+            false
+        } else {
+            self.last_men_standing.push(user_id);
+            true
+        }
+    }
+
+    pub fn finish(self) -> Objectives {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_koth_timers() {
+        let mut tracker = ObjectiveTracker::default();
+
+        let red_timer = EntityId::from(100u32);
+        let blue_timer = EntityId::from(101u32);
+        tracker.set_koth_timer(Team::Red, Some(red_timer));
+        tracker.set_koth_timer(Team::Blue, Some(blue_timer));
+
+        *tracker.timer_mut(red_timer) = RoundTimer {
+            paused: false,
+            time_remaining: 0.0,
+            end_time: 250.0,
+        };
+        *tracker.timer_mut(blue_timer) = RoundTimer {
+            paused: true,
+            time_remaining: 90.0,
+            end_time: 0.0,
+        };
+        tracker.record_koth_timers(DemoTick::from(1000), Team::Red, 100.0);
+
+        let objectives = tracker.finish();
+
+        assert_eq!(objectives.koth_timers.len(), 1);
+        assert_eq!(objectives.koth_timers[0].owner, Team::Red);
+        assert!((objectives.koth_timers[0].red_time_remaining - 150.0).abs() < f32::EPSILON);
+        assert!((objectives.koth_timers[0].blue_time_remaining - 90.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_cart_progress() {
+        let mut tracker = ObjectiveTracker::default();
+
+        let cart = EntityId::from(200u32);
+        tracker.cart_mut(cart).team = Team::Blue;
+        tracker.record_cart_progress(cart, DemoTick::from(1000));
+        tracker.cart_mut(cart).progress = 0.005;
+        // Too small of a change
+        tracker.record_cart_progress(cart, DemoTick::from(1001));
+        tracker.cart_mut(cart).pushers = 2;
+        tracker.record_cart_progress(cart, DemoTick::from(1002));
+
+        let objectives = tracker.finish();
+
+        assert_eq!(objectives.cart_progress.len(), 2);
+        assert_eq!(objectives.cart_progress[1].pushers, 2);
+        assert_eq!(objectives.cart_progress[1].tick, DemoTick::from(1002));
+    }
+
+    #[test]
+    fn test_last_man_standing_once_per_round() {
+        let mut tracker = ObjectiveTracker::default();
+        let user_id = UserId::from(2u16);
+
+        assert!(tracker.record_last_man_standing(user_id));
+        assert!(!tracker.record_last_man_standing(user_id));
+
+        tracker.start_round();
+        assert!(tracker.record_last_man_standing(user_id));
+    }
+}
@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::data::DemoTick;
+
+/// A ping this many milliseconds above a player's average ping is considered a spike
+pub const PING_SPIKE_THRESHOLD: u32 = 100;
+
+/// A period of time during which a player's ping was unusually high
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PingSpike {
+    pub start_tick: DemoTick,
+    pub end_tick: DemoTick,
+    /// The highest ping during the spike, in milliseconds
+    pub peak: u32,
+    /// The average ping before the spike started, in milliseconds
+    pub baseline: u32,
+}
+
+/// Ping statistics of a single player, in milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PingStats {
+    /// Weighted by how long each value was reported
+    pub average: u32,
+    pub min: u32,
+    pub max: u32,
+    /// Ordered by their start
+    pub spikes: Vec<PingSpike>,
+}
+
+/// Collects the ping values reported by the player resource.
+/// The server only sends a new value when it changes,
+/// so each value is weighted by the time until the next one, excluding pauses.
+#[derive(Debug, Default)]
+pub struct PingTracker {
+    // The last reported ping, and the game tick at which it was reported
+    current: Option<(u32, u32)>,
+    // Sum of ping * ticks, for the weighted average
+    weighted_sum: u64,
+    total_ticks: u64,
+    min: Option<u32>,
+    max: u32,
+    open_spike: Option<PingSpike>,
+    spikes: Vec<PingSpike>,
+}
+
+impl PingTracker {
+    fn average(&self) -> Option<u32> {
+        if self.total_ticks == 0 {
+            return None;
+        }
+
+        u32::try_from(self.weighted_sum / self.total_ticks).ok()
+    }
+
+    // Add the time since the last value to the weighted average
+    fn close_current(&mut self, game_tick: u32) {
+        if let Some((ping, since)) = self.current.take() {
+            let ticks = u64::from(game_tick.saturating_sub(since));
+            self.weighted_sum += u64::from(ping) * ticks;
+            self.total_ticks += ticks;
+        }
+    }
+
+    /// Record a new ping value.
+    /// Returns the spike that ended with this value, if any.
+    pub fn record(&mut self, ping: u32, tick: DemoTick, game_tick: u32) -> Option<PingSpike> {
+        self.close_current(game_tick);
+        self.current = Some((ping, game_tick));
+
+        self.min = Some(self.min.map_or(ping, |min| min.min(ping)));
+        self.max = self.max.max(ping);
+
+        if let Some(spike) = &mut self.open_spike {
+            if ping < spike.baseline + PING_SPIKE_THRESHOLD {
+                return self.end_spike(tick);
+            }
+
+            spike.peak = spike.peak.max(ping);
+        } else if let Some(baseline) = self.average() {
+            if ping >= baseline + PING_SPIKE_THRESHOLD {
+                self.open_spike = Some(PingSpike {
+                    start_tick: tick,
+                    end_tick: tick,
+                    peak: ping,
+                    baseline,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Stop sampling, for example because the player disconnected.
+    /// Returns the spike that was still ongoing, if any.
+    pub fn pause(&mut self, tick: DemoTick, game_tick: u32) -> Option<PingSpike> {
+        self.close_current(game_tick);
+        self.end_spike(tick)
+    }
+
+    fn end_spike(&mut self, tick: DemoTick) -> Option<PingSpike> {
+        let mut spike = self.open_spike.take()?;
+        spike.end_tick = tick;
+        self.spikes.push(spike);
+
+        Some(spike)
+    }
+
+    /// `None` if no ping was ever reported, like for bots.
+    /// Call `pause` first to include the last value.
+    pub fn finish(self) -> Option<PingStats> {
+        let min = self.min?;
+
+        Some(PingStats {
+            average: self.average().unwrap_or(min),
+            min,
+            max: self.max,
+            spikes: self.spikes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_tracker() {
+        let mut tracker = PingTracker::default();
+
+        assert_eq!(tracker.record(50, DemoTick::from(0), 0), None);
+        assert_eq!(tracker.record(60, DemoTick::from(100), 100), None);
+        // Average so far: 55
+        assert_eq!(tracker.record(200, DemoTick::from(200), 200), None);
+        assert_eq!(tracker.record(300, DemoTick::from(250), 250), None);
+
+        let spike = PingSpike {
+            start_tick: DemoTick::from(200),
+            end_tick: DemoTick::from(300),
+            peak: 300,
+            baseline: 55,
+        };
+        assert_eq!(tracker.record(60, DemoTick::from(300), 300), Some(spike));
+
+        assert_eq!(tracker.pause(DemoTick::from(400), 400), None);
+
+        let stats = tracker.finish().unwrap();
+        assert_eq!(stats.min, 50);
+        assert_eq!(stats.max, 300);
+        // (50 * 100 + 60 * 100 + 200 * 50 + 300 * 50 + 60 * 100) / 400
+        assert_eq!(stats.average, 105);
+        assert_eq!(stats.spikes, vec![spike]);
+    }
+
+    #[test]
+    fn test_ping_spike_ends_on_pause() {
+        let mut tracker = PingTracker::default();
+
+        tracker.record(50, DemoTick::from(0), 0);
+        tracker.record(250, DemoTick::from(100), 100);
+
+        // The player disconnected during the spike
+        let spike = tracker.pause(DemoTick::from(150), 150).unwrap();
+        assert_eq!(spike.start_tick, DemoTick::from(100));
+        assert_eq!(spike.end_tick, DemoTick::from(150));
+        assert_eq!(spike.baseline, 50);
+    }
+
+    #[test]
+    fn test_ping_average_excludes_pauses() {
+        let mut tracker = PingTracker::default();
+
+        tracker.record(50, DemoTick::from(0), 0);
+        // The game was paused for 200 ticks in between
+        tracker.record(80, DemoTick::from(300), 100);
+        tracker.pause(DemoTick::from(400), 200);
+
+        let stats = tracker.finish().unwrap();
+        // (50 * 100 + 80 * 100) / 200
+        assert_eq!(stats.average, 65);
+    }
+
+    #[test]
+    fn test_no_ping() {
+        assert_eq!(PingTracker::default().finish(), None);
+    }
+}
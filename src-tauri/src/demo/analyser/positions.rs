@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::{
+    demo::{
+        data::{DemoTick, UserInfo},
+        gameevent_gen::PlayerDeathEvent,
+        gamevent::GameEvent,
+        message::{
+            gameevent::GameEventMessage,
+            packetentities::{EntityId, PacketEntity, UpdateType},
+            Message,
+        },
+        packet::{
+            datatable::{ParseSendTable, ServerClass, ServerClassName},
+            stringtable::StringTableEntry,
+        },
+        parser::{analyser::UserId, MessageHandler},
+        sendprop::SendPropIdentifier,
+        vector::VectorXY,
+    },
+    MessageType, ParserState,
+};
+
+use super::PlayerLifeState;
+
+/// Position and view angles of a player at a single tick
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PositionSample {
+    pub tick: DemoTick,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+/// Where a player died, and where their killer was at that moment
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DeathLocation {
+    pub tick: DemoTick,
+    pub victim: UserId,
+    pub victim_position: PositionSample,
+    pub killer: Option<UserId>,
+    pub killer_position: Option<PositionSample>,
+    pub kill_icon: String,
+}
+
+/// Sampled player positions of an entire demo.
+///
+/// This is kept separate from the `GameSummary`,
+/// since it is much larger and only needed for positional review.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct DemoPositions {
+    /// The number of ticks between two samples
+    pub sample_interval: u32,
+    pub players: HashMap<UserId, Vec<PositionSample>>,
+    pub deaths: Vec<DeathLocation>,
+}
+
+#[derive(Debug, Default)]
+struct PlayerPosition {
+    position: PositionSample,
+    life_state: PlayerLifeState,
+}
+
+#[derive(Debug, Default)]
+pub struct PositionAnalyser {
+    class_names: Vec<ServerClassName>,
+
+    // Key: entity ID, value: the user ID of the player currently using that entity
+    entity_users: HashMap<EntityId, UserId>,
+    players: HashMap<EntityId, PlayerPosition>,
+
+    last_sample_tick: Option<DemoTick>,
+    demo_tick: DemoTick,
+    initial_packet_entities_parsed: bool,
+
+    output: DemoPositions,
+}
+
+impl PositionAnalyser {
+    pub fn new(sample_interval: u32) -> Self {
+        Self {
+            output: DemoPositions {
+                // A sample interval of 0 would sample on every message
+                sample_interval: sample_interval.max(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn handle_entity(&mut self, entity: &PacketEntity, parser_state: &ParserState) {
+        const LIFE_STATE_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_BasePlayer", "m_lifeState");
+        const LOCAL_ORIGIN_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_vecOrigin");
+        const NON_LOCAL_ORIGIN_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_vecOrigin");
+        const LOCAL_ORIGIN_Z_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_vecOrigin[2]");
+        const NON_LOCAL_ORIGIN_Z_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_vecOrigin[2]");
+        const LOCAL_PITCH_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_angEyeAngles[0]");
+        const NON_LOCAL_PITCH_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_angEyeAngles[0]");
+        const LOCAL_YAW_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFLocalPlayerExclusive", "m_angEyeAngles[1]");
+        const NON_LOCAL_YAW_PROP: SendPropIdentifier =
+            SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_angEyeAngles[1]");
+
+        // Players that left the PVS no longer receive updates,
+        // so their last known position would be stale
+        if matches!(entity.update_type, UpdateType::Leave | UpdateType::Delete) {
+            self.players.remove(&entity.entity_index);
+            return;
+        }
+
+        let class_name: &str = self
+            .class_names
+            .get(usize::from(entity.server_class))
+            .map_or("", ServerClassName::as_str);
+
+        if class_name != "CTFPlayer" {
+            return;
+        }
+
+        let player = self.players.entry(entity.entity_index).or_default();
+
+        for prop in entity.props(parser_state) {
+            match prop.identifier {
+                LIFE_STATE_PROP => {
+                    player.life_state = PlayerLifeState::try_from(&prop.value).unwrap_or_default();
+                }
+                LOCAL_ORIGIN_PROP | NON_LOCAL_ORIGIN_PROP => {
+                    let position = VectorXY::try_from(&prop.value).unwrap_or_default();
+                    player.position.x = position.x;
+                    player.position.y = position.y;
+                }
+                LOCAL_ORIGIN_Z_PROP | NON_LOCAL_ORIGIN_Z_PROP => {
+                    player.position.z = f32::try_from(&prop.value).unwrap_or_default();
+                }
+                LOCAL_PITCH_PROP | NON_LOCAL_PITCH_PROP => {
+                    player.position.pitch = f32::try_from(&prop.value).unwrap_or_default();
+                }
+                LOCAL_YAW_PROP | NON_LOCAL_YAW_PROP => {
+                    player.position.yaw = f32::try_from(&prop.value).unwrap_or_default();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn sample_positions(&mut self) {
+        let is_due = self.last_sample_tick.map_or(true, |last_sample_tick| {
+            u32::from(self.demo_tick - last_sample_tick) >= self.output.sample_interval
+        });
+
+        if !is_due {
+            return;
+        }
+
+        self.last_sample_tick = Some(self.demo_tick);
+
+        for (entity_id, player) in &self.players {
+            if player.life_state != PlayerLifeState::Alive {
+                continue;
+            }
+            if let Some(user_id) = self.entity_users.get(entity_id) {
+                self.output
+                    .players
+                    .entry(*user_id)
+                    .or_default()
+                    .push(PositionSample {
+                        tick: self.demo_tick,
+                        ..player.position
+                    });
+            }
+        }
+    }
+
+    fn position_of(&self, user_id: UserId) -> Option<PositionSample> {
+        let (entity_id, _user_id) = self
+            .entity_users
+            .iter()
+            .find(|(_entity_id, entity_user_id)| **entity_user_id == user_id)?;
+
+        self.players.get(entity_id).map(|player| PositionSample {
+            tick: self.demo_tick,
+            ..player.position
+        })
+    }
+
+    fn handle_player_death_event(&mut self, event: &PlayerDeathEvent) {
+        let victim_id = UserId::from(event.user_id);
+        let killer_id = UserId::from(event.attacker);
+
+        let Some(victim_position) = self.position_of(victim_id) else {
+            return;
+        };
+
+        // The killer is either the world (0) or the victim themself in case of a suicide
+        let killer = (killer_id != 0 && killer_id != victim_id).then_some(killer_id);
+
+        self.output.deaths.push(DeathLocation {
+            tick: self.demo_tick,
+            victim: victim_id,
+            victim_position,
+            killer,
+            killer_position: killer.and_then(|killer_id| self.position_of(killer_id)),
+            kill_icon: event.weapon.to_string(),
+        });
+    }
+}
+
+impl MessageHandler for PositionAnalyser {
+    type Output = DemoPositions;
+
+    fn does_handle(message_type: MessageType) -> bool {
+        matches!(
+            message_type,
+            MessageType::PacketEntities | MessageType::GameEvent
+        )
+    }
+
+    fn handle_message(&mut self, message: &Message, tick: DemoTick, parser_state: &ParserState) {
+        self.demo_tick = tick;
+        match message {
+            Message::PacketEntities(message) => {
+                self.initial_packet_entities_parsed = true;
+                for entity in &message.entities {
+                    self.handle_entity(entity, parser_state);
+                }
+                for entity_id in &message.removed_entities {
+                    self.players.remove(entity_id);
+                }
+                self.sample_positions();
+            }
+            Message::GameEvent(GameEventMessage {
+                event: GameEvent::PlayerDeath(event),
+                ..
+            }) if self.initial_packet_entities_parsed => {
+                // Like `GameDetailsAnalyser`, ignore the bogus events before the first entities
+                self.handle_player_death_event(event);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_string_entry(
+        &mut self,
+        table: &str,
+        index: usize,
+        entry: &StringTableEntry,
+        _parser_state: &ParserState,
+    ) {
+        if table != "userinfo" {
+            return;
+        }
+
+        let Ok(index) = index.try_into() else {
+            warn!("Index out of bounds in PositionAnalyser::handle_string_entry");
+            return;
+        };
+
+        if let Ok(Some(user_info)) = UserInfo::parse_from_string_table(
+            index,
+            entry.text.as_ref().map(AsRef::as_ref),
+            entry.extra_data.as_ref().map(|data| data.data.clone()),
+        ) {
+            self.entity_users
+                .insert(user_info.entity_id, user_info.player_info.user_id);
+        }
+    }
+
+    fn handle_data_tables(
+        &mut self,
+        _parse_tables: &[ParseSendTable],
+        server_classes: &[ServerClass],
+        _parser_state: &ParserState,
+    ) {
+        self.class_names = server_classes
+            .iter()
+            .map(|class| &class.name)
+            .cloned()
+            .collect();
+    }
+
+    fn into_output(self, _state: &ParserState) -> Self::Output {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tf_demo_parser::demo::{
+        message::packetentities::{BaselineIndex, PacketEntitiesMessage},
+        packet::datatable::ClassId,
+        sendprop::{SendProp, SendPropValue},
+    };
+
+    use super::*;
+    use crate::demo::analyser::{tests::death_event, WeaponClass};
+
+    const ORIGIN_PROP: SendPropIdentifier =
+        SendPropIdentifier::new("DT_TFNonLocalPlayerExclusive", "m_vecOrigin");
+
+    fn analyser(sample_interval: u32) -> PositionAnalyser {
+        let mut analyser = PositionAnalyser::new(sample_interval);
+        analyser.class_names = vec![ServerClassName::from("CTFPlayer")];
+        for user_id in 1..=2u16 {
+            analyser
+                .entity_users
+                .insert(EntityId::from(u32::from(user_id)), UserId::from(user_id));
+        }
+        analyser
+    }
+
+    fn player_entity(entity_id: u32, update_type: UpdateType, x: f32) -> PacketEntity {
+        PacketEntity {
+            server_class: ClassId::from(0),
+            entity_index: EntityId::from(entity_id),
+            props: vec![SendProp {
+                index: 0,
+                identifier: ORIGIN_PROP,
+                value: SendPropValue::VectorXY(VectorXY { x, y: 0.0 }),
+            }],
+            in_pvs: true,
+            update_type,
+            serial_number: 0,
+            delay: None,
+            delta: None,
+            baseline_index: BaselineIndex::First,
+        }
+    }
+
+    fn packet_entities(
+        entities: Vec<PacketEntity>,
+        removed_entities: Vec<EntityId>,
+    ) -> Message<'static> {
+        Message::PacketEntities(PacketEntitiesMessage {
+            entities,
+            removed_entities,
+            max_entries: 0,
+            delta: None,
+            base_line: BaselineIndex::First,
+            updated_base_line: false,
+        })
+    }
+
+    fn sample_ticks(positions: &DemoPositions, user_id: u16) -> Vec<u32> {
+        positions
+            .players
+            .get(&UserId::from(user_id))
+            .map(|samples| {
+                samples
+                    .iter()
+                    .map(|sample| u32::from(sample.tick))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_sample_interval() {
+        let parser_state = ParserState::new(24, PositionAnalyser::does_handle, false);
+        let mut analyser = analyser(3);
+
+        for tick in 10..20u32 {
+            let message =
+                packet_entities(vec![player_entity(1, UpdateType::Preserve, 1.0)], vec![]);
+            analyser.handle_message(&message, DemoTick::from(tick), &parser_state);
+        }
+
+        let positions = analyser.into_output(&parser_state);
+        assert_eq!(sample_ticks(&positions, 1), vec![10, 13, 16, 19]);
+
+        // Sampling on every message is the closest thing to an interval of 0
+        assert_eq!(PositionAnalyser::new(0).output.sample_interval, 1);
+    }
+
+    #[test]
+    fn test_players_leaving_the_pvs() {
+        let parser_state = ParserState::new(24, PositionAnalyser::does_handle, false);
+        let mut analyser = analyser(1);
+
+        let messages = [
+            packet_entities(
+                vec![
+                    player_entity(1, UpdateType::Enter, 1.0),
+                    player_entity(2, UpdateType::Enter, 2.0),
+                ],
+                vec![],
+            ),
+            packet_entities(vec![player_entity(1, UpdateType::Leave, 1.0)], vec![]),
+            packet_entities(vec![], vec![EntityId::from(2u32)]),
+            packet_entities(vec![player_entity(1, UpdateType::Enter, 3.0)], vec![]),
+        ];
+        for (tick, message) in (1u32..).zip(&messages) {
+            analyser.handle_message(message, DemoTick::from(tick), &parser_state);
+        }
+
+        assert!(analyser.position_of(UserId::from(2u16)).is_none());
+
+        let positions = analyser.into_output(&parser_state);
+        assert_eq!(sample_ticks(&positions, 1), vec![1, 4]);
+        assert_eq!(sample_ticks(&positions, 2), vec![1, 2]);
+        assert_eq!(
+            positions.players[&UserId::from(1u16)][1],
+            PositionSample {
+                tick: DemoTick::from(4u32),
+                x: 3.0,
+                ..PositionSample::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_death_locations() {
+        let parser_state = ParserState::new(24, PositionAnalyser::does_handle, false);
+        let mut analyser = analyser(1);
+
+        let message = packet_entities(
+            vec![
+                player_entity(1, UpdateType::Enter, 1.0),
+                player_entity(2, UpdateType::Enter, 2.0),
+            ],
+            vec![],
+        );
+        analyser.handle_message(&message, DemoTick::from(5u32), &parser_state);
+
+        analyser.handle_player_death_event(&death_event(
+            1,
+            2,
+            WeaponClass::TF_WEAPON_SCATTERGUN,
+            "scattergun",
+        ));
+        // Suicides have no killer
+        analyser.handle_player_death_event(&death_event(
+            2,
+            2,
+            WeaponClass::TF_WEAPON_NONE,
+            "world",
+        ));
+
+        // Deaths of players outside the PVS have no location
+        let message = packet_entities(vec![], vec![EntityId::from(1u32)]);
+        analyser.handle_message(&message, DemoTick::from(6u32), &parser_state);
+        analyser.handle_player_death_event(&death_event(
+            1,
+            2,
+            WeaponClass::TF_WEAPON_SCATTERGUN,
+            "scattergun",
+        ));
+
+        let positions = analyser.into_output(&parser_state);
+        assert_eq!(positions.deaths.len(), 2);
+
+        let death = &positions.deaths[0];
+        assert_eq!(death.tick, DemoTick::from(5u32));
+        assert_eq!(death.victim, UserId::from(1u16));
+        assert_eq!(
+            death.victim_position,
+            PositionSample {
+                tick: DemoTick::from(5u32),
+                x: 1.0,
+                ..PositionSample::default()
+            }
+        );
+        assert_eq!(death.killer, Some(UserId::from(2u16)));
+        assert_eq!(
+            death.killer_position,
+            Some(PositionSample {
+                tick: DemoTick::from(5u32),
+                x: 2.0,
+                ..PositionSample::default()
+            })
+        );
+        assert_eq!(death.kill_icon, "scattergun");
+
+        let suicide = &positions.deaths[1];
+        assert_eq!(suicide.killer, None);
+        assert_eq!(suicide.killer_position, None);
+    }
+}
@@ -0,0 +1,234 @@
+use num_derive::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{
+    data::DemoTick,
+    parser::analyser::{Team, UserId},
+};
+
+/// Indexed by the "winreason" field of the `teamplay_round_win` event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, FromPrimitive)]
+pub enum WinReason {
+    None = 0,
+    AllPointsCaptured = 1,
+    OpponentsDead = 2,
+    FlagCaptureLimit = 3,
+    DefendUntilTimeLimit = 4,
+    Stalemate = 5,
+    TimeLimit = 6,
+    WinLimit = 7,
+    WinDiffLimit = 8,
+    ReactorCaptured = 9,
+    CoresCollected = 10,
+    ReactorReturned = 11,
+    PlayerDestructionPoints = 12,
+    Scored = 13,
+    StopwatchWatchingRounds = 14,
+    StopwatchWatchingFinalRound = 15,
+    StopwatchPlayingRounds = 16,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct PointCapture {
+    pub tick: DemoTick,
+    pub point_name: String,
+    pub team: Team,
+    pub cappers: Vec<UserId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FirstBlood {
+    pub tick: DemoTick,
+    pub killer: UserId,
+    pub victim: UserId,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RoundSummary {
+    /// The round number, matching the keys of the per-round scoreboards
+    pub round: u32,
+
+    pub start_tick: DemoTick,
+
+    /// `None` if the demo ended before the round did
+    pub end_tick: Option<DemoTick>,
+
+    /// The length of the round in ticks, excluding pauses
+    pub duration: u32,
+
+    /// `None` if the round ended in a stalemate or did not end at all
+    pub winner: Option<Team>,
+    pub win_reason: Option<WinReason>,
+
+    /// All point captures of this round, in order
+    pub captures: Vec<PointCapture>,
+    pub first_blood: Option<FirstBlood>,
+
+    pub stalemate: bool,
+    pub sudden_death: bool,
+}
+
+/// How a round ended
+#[derive(Debug)]
+pub struct RoundEnd {
+    pub winner: Option<Team>,
+    pub win_reason: Option<WinReason>,
+    pub sudden_death: bool,
+}
+
+#[derive(Debug)]
+struct PendingRound {
+    /// The game tick at which the round started, used to measure its duration
+    start_game_tick: u32,
+    summary: RoundSummary,
+}
+
+/// Collects the `RoundSummary` of every round.
+///
+/// Like the `MultikillTracker`, ticks are passed twice: The demo tick is stored in
+/// the summary, while the game tick is used to measure the round duration.
+#[derive(Debug, Default)]
+pub struct RoundTracker {
+    rounds: Vec<RoundSummary>,
+    current: Option<PendingRound>,
+}
+
+impl RoundTracker {
+    /// Start a new round. If the previous round has not ended yet
+    /// (for example because the game was restarted), it is discarded.
+    pub fn start_round(&mut self, round: u32, tick: DemoTick, game_tick: u32) {
+        self.current = Some(PendingRound {
+            start_game_tick: game_tick,
+            summary: RoundSummary {
+                round,
+                start_tick: tick,
+                end_tick: None,
+                duration: 0,
+                winner: None,
+                win_reason: None,
+                captures: Vec::new(),
+                first_blood: None,
+                stalemate: false,
+                sudden_death: false,
+            },
+        });
+    }
+
+    pub fn record_capture(&mut self, capture: PointCapture) {
+        if let Some(current) = &mut self.current {
+            current.summary.captures.push(capture);
+        }
+    }
+
+    /// Record a kill. Only the first kill of each round is kept.
+    pub fn record_kill(&mut self, tick: DemoTick, killer: UserId, victim: UserId) {
+        if let Some(current) = &mut self.current {
+            current.summary.first_blood.get_or_insert(FirstBlood {
+                tick,
+                killer,
+                victim,
+            });
+        }
+    }
+
+    /// The round went into sudden death
+    pub fn record_sudden_death(&mut self) {
+        if let Some(current) = &mut self.current {
+            current.summary.sudden_death = true;
+        }
+    }
+
+    pub fn end_round(&mut self, end: &RoundEnd, tick: DemoTick, game_tick: u32) {
+        let Some(PendingRound {
+            start_game_tick,
+            mut summary,
+        }) = self.current.take()
+        else {
+            return;
+        };
+
+        summary.end_tick = Some(tick);
+        summary.duration = game_tick.saturating_sub(start_game_tick);
+        summary.winner = end.winner;
+        summary.win_reason = end.win_reason;
+        summary.stalemate = end.winner.is_none();
+        summary.sudden_death |= end.sudden_death;
+
+        self.rounds.push(summary);
+    }
+
+    /// Get the summaries of all rounds, including the round
+    /// that was still in progress when the demo ended.
+    pub fn finish(mut self, game_tick: u32) -> Vec<RoundSummary> {
+        if let Some(PendingRound {
+            start_game_tick,
+            mut summary,
+        }) = self.current.take()
+        {
+            summary.duration = game_tick.saturating_sub(start_game_tick);
+            self.rounds.push(summary);
+        }
+
+        self.rounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_tracker() {
+        let mut tracker = RoundTracker::default();
+        let (scout, soldier) = (UserId::from(2u16), UserId::from(3u16));
+
+        // Kills before the first round are not first bloods
+        tracker.record_kill(DemoTick::from(50), soldier, scout);
+
+        tracker.start_round(0, DemoTick::from(100), 100);
+        tracker.record_kill(DemoTick::from(200), scout, soldier);
+        tracker.record_kill(DemoTick::from(300), soldier, scout);
+        tracker.end_round(
+            &RoundEnd {
+                winner: Some(Team::Blue),
+                win_reason: Some(WinReason::AllPointsCaptured),
+                sudden_death: false,
+            },
+            DemoTick::from(1100),
+            // 100 ticks of pause
+            1000,
+        );
+
+        tracker.start_round(1, DemoTick::from(1500), 1400);
+        tracker.end_round(
+            &RoundEnd {
+                winner: None,
+                win_reason: Some(WinReason::Stalemate),
+                sudden_death: false,
+            },
+            DemoTick::from(2000),
+            1900,
+        );
+
+        tracker.start_round(2, DemoTick::from(2100), 2000);
+
+        let rounds = tracker.finish(2500);
+        assert_eq!(rounds.len(), 3);
+
+        assert_eq!(rounds[0].duration, 900);
+        assert_eq!(rounds[0].winner, Some(Team::Blue));
+        assert_eq!(
+            rounds[0].first_blood,
+            Some(FirstBlood {
+                tick: DemoTick::from(200),
+                killer: scout,
+                victim: soldier,
+            })
+        );
+
+        assert!(rounds[1].stalemate);
+        assert_eq!(rounds[1].first_blood, None);
+
+        assert_eq!(rounds[2].end_tick, None);
+        assert_eq!(rounds[2].duration, 500);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, message::ServerInfoMessage};
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ServerOs {
+    #[default]
+    Unknown,
+    Linux,
+    Windows,
+    MacOs,
+}
+
+impl ServerOs {
+    /// Parse the `platform` field of the `ServerInfo` message
+    pub fn from_platform(platform: &str) -> Self {
+        match platform.chars().next().map(|c| c.to_ascii_lowercase()) {
+            Some('l') => Self::Linux,
+            Some('w') => Self::Windows,
+            Some('m' | 'o') => Self::MacOs,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A convar that was changed after the demo started
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConVarChange {
+    pub tick: DemoTick,
+    pub name: String,
+    pub value: String,
+}
+
+/// Information about the server the demo was recorded on
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerInfo {
+    pub name: String,
+    /// The network protocol version of the server
+    pub version: u16,
+    pub max_players: u8,
+    pub os: ServerOs,
+    pub dedicated: bool,
+    /// The values of every convar the server sent, at the end of the demo.
+    /// Only replicated convars like `mp_tournament` or `sv_cheats` are sent to clients.
+    pub convars: BTreeMap<String, String>,
+    /// Convars that changed during the demo, ordered by tick
+    pub convar_changes: Vec<ConVarChange>,
+}
+
+impl ServerInfo {
+    pub fn set_server_info(&mut self, message: &ServerInfoMessage) {
+        self.name.clone_from(&message.server_name);
+        self.version = message.version;
+        self.max_players = message.max_player_count;
+        self.os = ServerOs::from_platform(&message.platform);
+        self.dedicated = message.dedicated;
+    }
+
+    /// Set the value of a convar.
+    /// `tick` is `None` for the initial values, which are not recorded as changes.
+    pub fn set_convar(&mut self, name: &str, value: &str, tick: Option<DemoTick>) {
+        let previous = self.convars.insert(name.to_owned(), value.to_owned());
+
+        if let Some(tick) = tick {
+            if previous.as_deref() != Some(value) {
+                self.convar_changes.push(ConVarChange {
+                    tick,
+                    name: name.to_owned(),
+                    value: value.to_owned(),
+                });
+            }
+        }
+    }
+
+    pub fn convar(&self, name: &str) -> Option<&str> {
+        self.convars.get(name).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_convar() {
+        let mut server = ServerInfo::default();
+
+        server.set_convar("mp_tournament", "1", None);
+        server.set_convar("sv_cheats", "0", None);
+        // Sent again with the same value
+        server.set_convar("sv_cheats", "0", Some(DemoTick::from(100)));
+        server.set_convar("sv_cheats", "1", Some(DemoTick::from(200)));
+
+        assert_eq!(server.convar("mp_tournament"), Some("1"));
+        assert_eq!(server.convar("sv_cheats"), Some("1"));
+        assert_eq!(server.convar("mp_timelimit"), None);
+        assert_eq!(
+            server.convar_changes,
+            vec![ConVarChange {
+                tick: DemoTick::from(200),
+                name: "sv_cheats".into(),
+                value: "1".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_server_os_from_platform() {
+        assert_eq!(ServerOs::from_platform("l"), ServerOs::Linux);
+        assert_eq!(ServerOs::from_platform("W"), ServerOs::Windows);
+        assert_eq!(ServerOs::from_platform(""), ServerOs::Unknown);
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::{data::DemoTick, parser::analyser::UserId};
+
+use super::{PlayerCondition, PlayerState};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum UberchargeKind {
+    Ubercharge,
+    Kritzkrieg,
+    QuickFix,
+    Vaccinator,
+}
+
+impl UberchargeKind {
+    /// Determine which kind of ubercharge a medic is currently using, if any
+    pub fn of_player(player: &PlayerState) -> Option<Self> {
+        if player.has_cond(PlayerCondition::TF_COND_INVULNERABLE) {
+            Some(Self::Ubercharge)
+        } else if player.has_cond(PlayerCondition::TF_COND_MEGAHEAL) {
+            Some(Self::QuickFix)
+        } else if player.has_cond(PlayerCondition::TF_COND_MEDIGUN_UBER_BULLET_RESIST)
+            || player.has_cond(PlayerCondition::TF_COND_MEDIGUN_UBER_BLAST_RESIST)
+            || player.has_cond(PlayerCondition::TF_COND_MEDIGUN_UBER_FIRE_RESIST)
+        {
+            Some(Self::Vaccinator)
+        } else if player.charge < 100 && player.has_cond(PlayerCondition::TF_COND_CRITBOOSTED) {
+            // Medics are also crit boosted by another medic's Kritzkrieg,
+            // or after winning the round, so only count it once the charge drains
+            Some(Self::Kritzkrieg)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum UberchargeOutcome {
+    /// The medic deployed the charge
+    Used,
+    /// The medic died while holding the charge
+    Dropped,
+    /// The charge was lost without being used,
+    /// for example because the round was reset
+    Faded,
+}
+
+/// A single ubercharge, from the moment it was fully built until it was used or lost.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Ubercharge {
+    pub medic: UserId,
+
+    /// The tick at which the charge reached 100%
+    pub built_tick: DemoTick,
+
+    /// The tick at which the charge was deployed
+    pub used_tick: Option<DemoTick>,
+
+    /// The tick at which the charge ran out, was dropped or faded
+    pub end_tick: Option<DemoTick>,
+
+    /// How long the full charge was held before it was used or lost,
+    /// in ticks, excluding pauses
+    pub held_ticks: u32,
+
+    pub kind: Option<UberchargeKind>,
+
+    /// The heal target of the medic when the charge was used or dropped
+    pub target: Option<UserId>,
+
+    /// `None` if the charge was still being held when the demo ended
+    pub outcome: Option<UberchargeOutcome>,
+}
+
+/// An ubercharge that has not ended yet
+#[derive(Debug)]
+pub struct PendingUbercharge {
+    /// Index into the list of ubercharges
+    pub index: usize,
+    /// The game tick at which the charge reached 100%
+    pub built_game_tick: u32,
+}
@@ -0,0 +1,355 @@
+use std::{collections::HashMap, io::Write};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tf_demo_parser::{
+    demo::{
+        data::{DemoTick, UserInfo},
+        message::{packetentities::EntityId, Message, VoiceDataMessage, VoiceInitMessage},
+        packet::stringtable::StringTableEntry,
+        parser::{analyser::UserId, MessageHandler},
+    },
+    MessageType, ParserState,
+};
+
+/// Voice packets are sent about every 20ms while a player is talking.
+/// Pauses shorter than this are considered part of the same interval.
+pub const VOICE_GAP_SECONDS: f32 = 0.5;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VoiceCodec {
+    /// Usually `steam`
+    pub codec: String,
+    pub quality: u8,
+    pub sampling_rate: u16,
+}
+
+impl From<&VoiceInitMessage> for VoiceCodec {
+    fn from(message: &VoiceInitMessage) -> Self {
+        Self {
+            codec: message.codec.clone(),
+            quality: message.quality,
+            sampling_rate: message.sampling_rate,
+        }
+    }
+}
+
+/// A period of time during which a player was talking
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VoiceInterval {
+    pub user_id: UserId,
+    pub start_tick: DemoTick,
+    /// The tick of the last voice packet
+    pub end_tick: DemoTick,
+}
+
+/// Merges voice packets into intervals of continuous talking
+#[derive(Debug, Default)]
+pub struct VoiceTracker {
+    // Key: user ID of the speaker
+    open_intervals: HashMap<UserId, VoiceInterval>,
+    intervals: Vec<VoiceInterval>,
+}
+
+impl VoiceTracker {
+    /// Record a voice packet of `user_id`.
+    /// `max_gap` is the number of ticks after which a new interval is started.
+    pub fn record_packet(&mut self, user_id: UserId, tick: DemoTick, max_gap: u32) {
+        if let Some(interval) = self.open_intervals.get_mut(&user_id) {
+            if u32::from(tick - interval.end_tick) <= max_gap {
+                interval.end_tick = tick;
+                return;
+            }
+
+            let interval = self.open_intervals.remove(&user_id).unwrap();
+            self.intervals.push(interval);
+        }
+
+        self.open_intervals.insert(
+            user_id,
+            VoiceInterval {
+                user_id,
+                start_tick: tick,
+                end_tick: tick,
+            },
+        );
+    }
+
+    /// Returns every interval, ordered by their start
+    pub fn finish(mut self) -> Vec<VoiceInterval> {
+        self.intervals.extend(self.open_intervals.into_values());
+        self.intervals
+            .sort_by_key(|interval| (interval.start_tick, interval.user_id));
+
+        self.intervals
+    }
+}
+
+/// A single voice packet, as it was sent by the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoicePacket {
+    pub tick: DemoTick,
+    /// The payload length in bits
+    pub bits: u16,
+    /// The last byte is padded with zeros if `bits` is not a multiple of 8
+    pub data: Vec<u8>,
+}
+
+impl VoicePacket {
+    fn from_message(message: &VoiceDataMessage, tick: DemoTick) -> Option<Self> {
+        let mut stream = message.data.clone();
+        let bits = stream.bit_len();
+
+        let mut data = stream.read_bytes(bits / 8).ok()?.into_owned();
+        if bits % 8 != 0 {
+            data.push(stream.read_int::<u8>(bits % 8).ok()?);
+        }
+
+        Some(Self {
+            tick,
+            bits: u16::try_from(bits).ok()?,
+            data,
+        })
+    }
+}
+
+/// The raw voice packets of a single player
+#[derive(Debug, Default, PartialEq)]
+pub struct VoiceExport {
+    pub codec: VoiceCodec,
+    pub packets: Vec<VoicePacket>,
+}
+
+impl VoiceExport {
+    const MAGIC: &[u8; 8] = b"DMVOICE1";
+
+    /// Write the packets into a simple container for offline decoding.
+    /// All integers are little-endian.
+    ///
+    /// - The magic bytes `DMVOICE1`
+    /// - The codec name, prefixed with its length as `u8`
+    /// - The quality (`u8`) and sampling rate (`u16`)
+    /// - For each packet: the demo tick (`u32`), the payload length in bits (`u16`)
+    ///   and the payload, padded to full bytes
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        let codec = self.codec.codec.as_bytes();
+        let codec = &codec[..codec.len().min(usize::from(u8::MAX))];
+
+        writer.write_all(Self::MAGIC)?;
+        #[allow(clippy::cast_possible_truncation)]
+        writer.write_all(&[codec.len() as u8])?;
+        writer.write_all(codec)?;
+        writer.write_all(&[self.codec.quality])?;
+        writer.write_all(&self.codec.sampling_rate.to_le_bytes())?;
+
+        for packet in &self.packets {
+            writer.write_all(&u32::from(packet.tick).to_le_bytes())?;
+            writer.write_all(&packet.bits.to_le_bytes())?;
+            writer.write_all(&packet.data)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects the voice packets of a single player, see `VoiceExport`
+#[derive(Debug)]
+pub struct VoiceExtractor {
+    user_id: UserId,
+    // Key: entity ID, value: the user ID of the player currently using that entity
+    entity_users: HashMap<EntityId, UserId>,
+    output: VoiceExport,
+}
+
+impl VoiceExtractor {
+    pub fn new(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            entity_users: HashMap::new(),
+            output: VoiceExport::default(),
+        }
+    }
+}
+
+impl MessageHandler for VoiceExtractor {
+    type Output = VoiceExport;
+
+    fn does_handle(message_type: MessageType) -> bool {
+        matches!(
+            message_type,
+            MessageType::VoiceInit | MessageType::VoiceData
+        )
+    }
+
+    fn handle_message(&mut self, message: &Message, tick: DemoTick, _parser_state: &ParserState) {
+        match message {
+            Message::VoiceInit(message) => {
+                self.output.codec = VoiceCodec::from(message);
+            }
+            Message::VoiceData(message) => {
+                // `client` is the player slot, which is one less than the entity ID
+                let entity_id = EntityId::from(u32::from(message.client) + 1);
+
+                if self.entity_users.get(&entity_id) == Some(&self.user_id) {
+                    if let Some(packet) = VoicePacket::from_message(message, tick) {
+                        self.output.packets.push(packet);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_string_entry(
+        &mut self,
+        table: &str,
+        index: usize,
+        entry: &StringTableEntry,
+        _parser_state: &ParserState,
+    ) {
+        if table != "userinfo" {
+            return;
+        }
+
+        let Ok(index) = index.try_into() else {
+            warn!("Index out of bounds in VoiceExtractor::handle_string_entry");
+            return;
+        };
+
+        if let Ok(Some(user_info)) = UserInfo::parse_from_string_table(
+            index,
+            entry.text.as_ref().map(AsRef::as_ref),
+            entry.extra_data.as_ref().map(|data| data.data.clone()),
+        ) {
+            self.entity_users
+                .insert(user_info.entity_id, user_info.player_info.user_id);
+        }
+    }
+
+    fn into_output(self, _state: &ParserState) -> Self::Output {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tf_demo_parser::Demo;
+
+    use super::*;
+
+    fn voice_data(client: u8, bytes: &[u8], bits: usize) -> VoiceDataMessage<'_> {
+        VoiceDataMessage {
+            client,
+            proximity: 0,
+            length: u16::try_from(bits).unwrap(),
+            data: Demo::new(bytes).get_stream().read_bits(bits).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_voice_tracker() {
+        let mut tracker = VoiceTracker::default();
+        let alice = UserId::from(2u16);
+        let bob = UserId::from(3u16);
+
+        tracker.record_packet(alice, DemoTick::from(100), 33);
+        tracker.record_packet(alice, DemoTick::from(101), 33);
+        tracker.record_packet(bob, DemoTick::from(110), 33);
+        tracker.record_packet(alice, DemoTick::from(130), 33);
+        // Too long of a pause
+        tracker.record_packet(alice, DemoTick::from(200), 33);
+
+        let intervals = tracker.finish();
+
+        assert_eq!(
+            intervals,
+            vec![
+                VoiceInterval {
+                    user_id: alice,
+                    start_tick: DemoTick::from(100),
+                    end_tick: DemoTick::from(130),
+                },
+                VoiceInterval {
+                    user_id: bob,
+                    start_tick: DemoTick::from(110),
+                    end_tick: DemoTick::from(110),
+                },
+                VoiceInterval {
+                    user_id: alice,
+                    start_tick: DemoTick::from(200),
+                    end_tick: DemoTick::from(200),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_voice_packet_padding() {
+        let message = voice_data(0, &[0xAB, 0xFC], 12);
+
+        assert_eq!(
+            VoicePacket::from_message(&message, DemoTick::from(100)),
+            Some(VoicePacket {
+                tick: DemoTick::from(100),
+                bits: 12,
+                data: vec![0xAB, 0x0C],
+            })
+        );
+    }
+
+    #[test]
+    fn test_voice_extractor() {
+        let state = ParserState::new(24, VoiceExtractor::does_handle, false);
+        let mut extractor = VoiceExtractor::new(UserId::from(3u16));
+        extractor
+            .entity_users
+            .insert(EntityId::from(2u32), UserId::from(3u16));
+
+        extractor.handle_message(
+            &Message::VoiceInit(VoiceInitMessage {
+                codec: "steam".into(),
+                quality: 5,
+                sampling_rate: 24000,
+            }),
+            DemoTick::from(1),
+            &state,
+        );
+        // Player slot 1 is entity 2
+        extractor.handle_message(
+            &Message::VoiceData(voice_data(1, &[0x01, 0x02], 16)),
+            DemoTick::from(100),
+            &state,
+        );
+        extractor.handle_message(
+            &Message::VoiceData(voice_data(2, &[0x03, 0x04], 16)),
+            DemoTick::from(101),
+            &state,
+        );
+
+        let export = extractor.into_output(&state);
+        assert_eq!(export.codec.codec, "steam");
+        assert_eq!(export.packets.len(), 1);
+        assert_eq!(export.packets[0].data, vec![0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_voice_export_container() {
+        let export = VoiceExport {
+            codec: VoiceCodec {
+                codec: "steam".into(),
+                quality: 5,
+                sampling_rate: 24000,
+            },
+            packets: vec![VoicePacket {
+                tick: DemoTick::from(100),
+                bits: 12,
+                data: vec![0xAB, 0x0C],
+            }],
+        };
+        let mut container = Vec::new();
+        export.write_to(&mut container).unwrap();
+
+        assert_eq!(&container[..8], b"DMVOICE1");
+        assert_eq!(container.len(), 8 + 1 + 5 + 1 + 2 + 4 + 2 + 2);
+    }
+}
@@ -1,6 +1,19 @@
 use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, FromPrimitive, ToPrimitive)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    FromPrimitive,
+    ToPrimitive,
+    Serialize,
+    Deserialize,
+)]
 #[allow(non_camel_case_types)]
 pub enum WeaponClass {
     #[default]
@@ -11,11 +11,15 @@ use std::{
 use bitbuffer::BitRead;
 use log::warn;
 use serde::{Deserialize, Serialize};
+use tf_demo_parser::demo::parser::analyser::UserId;
 
 use crate::demo_cache::DemoMetadataCache;
 
 use self::{
-    analyser::{GameDetailsAnalyser, GameSummary},
+    analyser::{
+        format_chat_log, DemoPositions, GameMode, GameStateAnalyser, GameStates, GameSummary,
+        HighlightConfig, MatchInfo, PositionAnalyser, VoiceExtractor, CHECKPOINT_INTERVAL,
+    },
     error::Result,
 };
 
@@ -56,6 +60,11 @@ pub struct Demo {
     pub playback_time: f32,
     pub num_ticks: u32,
     pub is_stv: bool,
+    /// Guessed from the map name until the demo was parsed,
+    /// see `DemoMetadataCache::set_parsed_info`
+    pub game_mode: GameMode,
+    /// Only known after the demo was parsed, see `DemoMetadataCache::set_parsed_info`
+    pub match_info: Option<MatchInfo>,
 }
 
 impl Demo {
@@ -68,6 +77,7 @@ impl Demo {
         metadata: &fs::Metadata,
     ) -> Self {
         let is_stv = header.server.is_empty();
+        let game_mode = GameMode::from_map_name(&header.map);
 
         Self {
             name,
@@ -87,6 +97,8 @@ impl Demo {
             playback_time: header.duration,
             num_ticks: header.ticks,
             is_stv,
+            game_mode,
+            match_info: None,
         }
     }
 
@@ -95,6 +107,24 @@ impl Demo {
     }
 }
 
+/// The results of parsing a demo that the demo list can be filtered by.
+/// These are cached on disk, so the filters also work for demos
+/// that were parsed in an earlier session.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ParsedDemoInfo {
+    pub game_mode: GameMode,
+    pub match_info: MatchInfo,
+}
+
+impl From<&GameSummary> for ParsedDemoInfo {
+    fn from(game_summary: &GameSummary) -> Self {
+        Self {
+            game_mode: game_summary.game_mode,
+            match_info: game_summary.match_info.clone(),
+        }
+    }
+}
+
 // This uses `&'a Vec` instead of just `Vec` so we don't have to copy
 // the entire Vector to serialize it
 #[derive(Serialize)]
@@ -273,7 +303,10 @@ pub enum Filter {
     Event(String),
     FileName(String),
     FreeText(String),
+    GameMode(String),
     MapName(String),
+    MatchFormat(String),
+    MatchType(String),
     PlayerName(String),
     TagName(String),
 }
@@ -284,7 +317,10 @@ struct Filters {
     event: Vec<String>,
     file_name: Vec<String>,
     free_text: Vec<String>,
+    game_mode: Vec<String>,
     map_name: Vec<String>,
+    match_format: Vec<String>,
+    match_type: Vec<String>,
     player_name: Vec<String>,
     tag_name: Vec<String>,
 }
@@ -299,7 +335,10 @@ impl Filters {
                 Filter::Event(value) => result.event.push(value.to_lowercase()),
                 Filter::FileName(value) => result.file_name.push(value.to_lowercase()),
                 Filter::FreeText(value) => result.free_text.push(value.to_lowercase()),
+                Filter::GameMode(value) => result.game_mode.push(value.to_lowercase()),
                 Filter::MapName(value) => result.map_name.push(value.to_lowercase()),
+                Filter::MatchFormat(value) => result.match_format.push(value.to_lowercase()),
+                Filter::MatchType(value) => result.match_type.push(value.to_lowercase()),
                 Filter::PlayerName(value) => result.player_name.push(value.to_lowercase()),
                 Filter::TagName(value) => result.tag_name.push(value.to_lowercase()),
             }
@@ -313,7 +352,10 @@ impl Filters {
             && self.matches_event(demo)
             && self.matches_name(demo)
             && self.matches_free_text(demo)
+            && self.matches_game_mode(demo)
             && self.matches_map(demo)
+            && self.matches_match_format(demo)
+            && self.matches_match_type(demo)
             && self.matches_client(demo)
             && self.matches_tag(demo)
     }
@@ -380,6 +422,17 @@ impl Filters {
             .any(|query| fields.iter().any(|field| field.contains(query)))
     }
 
+    fn matches_game_mode(&self, demo: &Demo) -> bool {
+        if self.game_mode.is_empty() {
+            return true;
+        }
+
+        // Exact matches only, otherwise "payload" would also match "payload_race"
+        let game_mode = demo.game_mode.name();
+
+        self.game_mode.iter().any(|query| query == game_mode)
+    }
+
     fn matches_map(&self, demo: &Demo) -> bool {
         if self.map_name.is_empty() {
             return true;
@@ -390,6 +443,33 @@ impl Filters {
         self.map_name.iter().any(|query| map_name.contains(query))
     }
 
+    fn matches_match_format(&self, demo: &Demo) -> bool {
+        if self.match_format.is_empty() {
+            return true;
+        }
+
+        // Demos that were never parsed don't match any format
+        let Some(match_info) = &demo.match_info else {
+            return false;
+        };
+        let match_format = match_info.format.name();
+
+        self.match_format.iter().any(|query| query == match_format)
+    }
+
+    fn matches_match_type(&self, demo: &Demo) -> bool {
+        if self.match_type.is_empty() {
+            return true;
+        }
+
+        let Some(match_info) = &demo.match_info else {
+            return false;
+        };
+        let match_type = match_info.match_type.name();
+
+        self.match_type.iter().any(|query| query == match_type)
+    }
+
     fn matches_client(&self, demo: &Demo) -> bool {
         if self.player_name.is_empty() {
             return true;
@@ -455,14 +535,73 @@ pub fn write_events_and_tags(
     Ok(())
 }
 
-pub fn read_demo_details(path: &Path) -> Result<GameSummary> {
+/// Parse a demo, recording the game state every `CHECKPOINT_INTERVAL` ticks along the way
+pub fn read_demo_details(
+    path: &Path,
+    config: &HighlightConfig,
+) -> Result<(GameSummary, GameStates)> {
+    let file = fs::read(path)?;
+    let demo = tf_demo_parser::Demo::new(&file);
+
+    let analyser = GameStateAnalyser::new(config.clone(), CHECKPOINT_INTERVAL);
+
+    let parser = tf_demo_parser::DemoParser::new_all_with_analyser(demo.get_stream(), analyser);
+    let (_header, (state, states)) = parser.parse()?;
+
+    Ok((state, states))
+}
+
+pub fn read_demo_positions(path: &Path, sample_interval: u32) -> Result<DemoPositions> {
+    let file = fs::read(path)?;
+    let demo = tf_demo_parser::Demo::new(&file);
+
+    let analyser = PositionAnalyser::new(sample_interval);
+
+    let parser = tf_demo_parser::DemoParser::new_all_with_analyser(demo.get_stream(), analyser);
+    let (_header, positions) = parser.parse()?;
+
+    Ok(positions)
+}
+
+/// Write the voice packets of a single player into a container file at `output_path`,
+/// see `VoiceExport::write_to` for the format.
+pub fn export_demo_voice(path: &Path, user_id: UserId, output_path: &Path) -> Result<()> {
     let file = fs::read(path)?;
     let demo = tf_demo_parser::Demo::new(&file);
 
-    let analyser = GameDetailsAnalyser::default();
+    let analyser = VoiceExtractor::new(user_id);
 
     let parser = tf_demo_parser::DemoParser::new_all_with_analyser(demo.get_stream(), analyser);
-    let (_header, state) = parser.parse()?;
+    let (_header, voice) = parser.parse()?;
 
-    Ok(state)
+    let mut file = File::create(output_path)?;
+    voice.write_to(&mut file)?;
+
+    Ok(())
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatLogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+pub fn write_chat_log(
+    game_summary: &GameSummary,
+    format: ChatLogFormat,
+    output_path: &Path,
+) -> Result<()> {
+    let mut file = File::create(output_path)?;
+
+    match format {
+        ChatLogFormat::Text => {
+            let text = format_chat_log(&game_summary.chat_log, game_summary.interval_per_tick);
+            file.write_all(text.as_bytes())?;
+        }
+        ChatLogFormat::Json => serde_json::to_writer_pretty(&mut file, &game_summary.chat_log)?,
+    }
+
+    Ok(())
 }
@@ -8,7 +8,9 @@ use std::{
 };
 
 use crate::{
-    demo::{error::Result, read_demo, write_events_and_tags, Demo, DemoEvent, Error},
+    demo::{
+        error::Result, read_demo, write_events_and_tags, Demo, DemoEvent, Error, ParsedDemoInfo,
+    },
     std_ext::OrTryInsertWith,
 };
 
@@ -57,6 +59,17 @@ impl DemoMetadataCache {
         Ok(())
     }
 
+    /// Remember the results of parsing a demo, so the demo list can be filtered by them.
+    /// This replaces the game mode guessed from the map name.
+    pub fn set_parsed_info(&mut self, path: &str, info: ParsedDemoInfo) -> Result<Arc<Demo>> {
+        let demo = self.get_demo_mut(path)?;
+
+        demo.game_mode = info.game_mode;
+        demo.match_info = Some(info.match_info);
+
+        self.get_demo(path)
+    }
+
     pub fn get_known_events(&self) -> Vec<String> {
         self.collect_demo_properties_multiple(|demo| {
             demo.events.iter().map(|event| event.value.as_str())
@@ -14,7 +14,13 @@ use tauri_plugin_log::{
 };
 
 use cli::Args;
+use commands::demos::LoadedGameStates;
+use demo::{
+    analyser::{DemoPositions, GameStates, GameSummary, HighlightConfig},
+    ParsedDemoInfo,
+};
 use demo_cache::DemoMetadataCache;
+use parsed_demo_cache::ParsedDemoCache;
 
 mod cli;
 mod commands;
@@ -86,19 +92,33 @@ fn main() {
                 .app_cache_dir()
                 .map_err(|error| format!("Failed to resolve cache directory: {error}"))?;
 
-            app.manage(parsed_demo_cache::ParsedDemoCache::new(
+            app.manage(ParsedDemoCache::<GameSummary, HighlightConfig>::new(
                 cache_path.join("parsed"),
             ));
+            app.manage(ParsedDemoCache::<ParsedDemoInfo>::new(
+                cache_path.join("info"),
+            ));
+            app.manage(ParsedDemoCache::<DemoPositions>::new(
+                cache_path.join("positions"),
+            ));
+            app.manage(ParsedDemoCache::<GameStates>::new(
+                cache_path.join("states"),
+            ));
             app.manage(Mutex::new(DemoMetadataCache::new()));
+            app.manage(Mutex::new(LoadedGameStates::None));
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::cli::get_file_argument,
             commands::demos::delete_demo,
+            commands::demos::export_chat_log,
+            commands::demos::export_voice,
             commands::demos::get_demo,
             commands::demos::get_demo_details,
+            commands::demos::get_demo_positions,
             commands::demos::get_demos_in_directory,
+            commands::demos::get_game_state,
             commands::demos::get_known_demo_names,
             commands::demos::get_known_events,
             commands::demos::get_known_maps,
@@ -1,15 +1,14 @@
 use std::{
     hash::{Hash, Hasher},
-    io,
+    marker::PhantomData,
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use wyhash::WyHash;
 
 use crate::{
-    demo::analyser::GameSummary,
     disk_cache::{DiskCache, Error},
     traits::Cache,
 };
@@ -20,6 +19,8 @@ struct Header {
     version: String,
     modified_time: SystemTime,
     file_size: u64,
+    /// The serialized options the demo was parsed with
+    options: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -28,14 +29,20 @@ struct RawEntry<'a> {
     content: &'a [u8],
 }
 
-pub struct ParsedDemoCache {
+/// Caches the results of parsing demos, such as the `GameSummary`.
+/// Entries are invalidated when the demo file, the app version
+/// or the options used for parsing (of type `O`) change.
+pub struct ParsedDemoCache<T, O = ()> {
     disk_cache: DiskCache,
+    // `fn() -> T` keeps the cache `Send + Sync` regardless of `T`
+    phantom: PhantomData<fn() -> (T, O)>,
 }
 
-impl ParsedDemoCache {
+impl<T, O> ParsedDemoCache<T, O> {
     pub fn new(path: PathBuf) -> Self {
         Self {
             disk_cache: DiskCache::new(path),
+            phantom: PhantomData,
         }
     }
 
@@ -59,7 +66,10 @@ impl ParsedDemoCache {
         Ok(key)
     }
 
-    async fn header(demo_path: impl AsRef<Path>) -> io::Result<Header> {
+    async fn header(demo_path: impl AsRef<Path>, options: &O) -> Result<Header, Error>
+    where
+        O: Serialize,
+    {
         let demo_path = demo_path.as_ref();
 
         let metadata = tokio::fs::metadata(demo_path).await?;
@@ -69,23 +79,20 @@ impl ParsedDemoCache {
             version: String::from(env!("CARGO_PKG_VERSION")),
             modified_time: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
             file_size: metadata.len(),
+            options: bincode::serialize(options)?,
         };
 
         Ok(header)
     }
 }
 
-impl Cache for ParsedDemoCache {
-    type Key = str;
-    type Value = GameSummary;
-    type Error = Error;
-
-    async fn get(
-        &self,
-        demo_path: impl AsRef<Self::Key>,
-    ) -> Result<Option<Self::Value>, Self::Error> {
-        let demo_path = demo_path.as_ref();
-
+impl<T, O> ParsedDemoCache<T, O>
+where
+    T: Serialize + DeserializeOwned,
+    O: Serialize,
+{
+    /// Get the entry of a demo, if it was parsed with the same options.
+    pub async fn get_with_options(&self, demo_path: &str, options: &O) -> Result<Option<T>, Error> {
         log::trace!(target: "CACHE", "get {demo_path}");
 
         let key = Self::cache_key(demo_path)?;
@@ -93,10 +100,10 @@ impl Cache for ParsedDemoCache {
         if let Some(raw_entry_bytes) = self.disk_cache.get(&key).await? {
             let raw_entry: RawEntry = bincode::deserialize(&raw_entry_bytes)?;
 
-            let expected_header = Self::header(demo_path).await?;
+            let expected_header = Self::header(demo_path, options).await?;
 
             if raw_entry.header == expected_header {
-                let value: GameSummary = bincode::deserialize(raw_entry.content)?;
+                let value: T = bincode::deserialize(raw_entry.content)?;
 
                 Ok(Some(value))
             } else {
@@ -108,32 +115,30 @@ impl Cache for ParsedDemoCache {
         }
     }
 
-    async fn insert(
+    pub async fn insert_with_options(
         &self,
-        demo_path: impl AsRef<Self::Key>,
-        game_summary: &Self::Value,
-    ) -> Result<(), Self::Error> {
-        let demo_path = demo_path.as_ref();
-
+        demo_path: &str,
+        options: &O,
+        value: &T,
+    ) -> Result<(), Error> {
         log::trace!(target: "CACHE", "insert {demo_path}");
 
         let key = Self::cache_key(demo_path)?;
-        let header = Self::header(demo_path).await?;
+        let header = Self::header(demo_path, options).await?;
 
-        let demo_bytes = bincode::serialize(game_summary)?;
+        let value_bytes = bincode::serialize(value)?;
 
         let entry = RawEntry {
             header,
-            content: &demo_bytes,
+            content: &value_bytes,
         };
         let entry_bytes = bincode::serialize(&entry)?;
 
         self.disk_cache.insert(&key, &entry_bytes).await
     }
 
-    async fn remove(&self, demo_path: impl AsRef<Self::Key>) -> Result<(), Self::Error> {
-        let demo_path = demo_path.as_ref();
-
+    /// Remove the entry of a demo, regardless of the options it was parsed with.
+    pub async fn remove(&self, demo_path: &str) -> Result<(), Error> {
         log::trace!(target: "CACHE", "remove {demo_path}");
 
         let key = Self::cache_key(demo_path)?;
@@ -142,27 +147,74 @@ impl Cache for ParsedDemoCache {
     }
 }
 
+impl<T> Cache for ParsedDemoCache<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    type Key = str;
+    type Value = T;
+    type Error = Error;
+
+    async fn get(
+        &self,
+        demo_path: impl AsRef<Self::Key>,
+    ) -> Result<Option<Self::Value>, Self::Error> {
+        self.get_with_options(demo_path.as_ref(), &()).await
+    }
+
+    async fn insert(
+        &self,
+        demo_path: impl AsRef<Self::Key>,
+        value: &Self::Value,
+    ) -> Result<(), Self::Error> {
+        self.insert_with_options(demo_path.as_ref(), &(), value)
+            .await
+    }
+
+    async fn remove(&self, demo_path: impl AsRef<Self::Key>) -> Result<(), Self::Error> {
+        ParsedDemoCache::remove(self, demo_path.as_ref()).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use crate::{demo::read_demo_details, parsed_demo_cache::ParsedDemoCache, traits::Cache};
+    use crate::{
+        demo::{
+            analyser::{GameSummary, HighlightConfig},
+            read_demo_details,
+        },
+        parsed_demo_cache::ParsedDemoCache,
+    };
 
     #[tokio::test]
     async fn test_cache_roundtrip() {
         let cache_path = std::env::temp_dir().join("demoman_cache_test");
-        let cache = ParsedDemoCache::new(cache_path.clone());
+        let cache = ParsedDemoCache::<GameSummary, HighlightConfig>::new(cache_path.clone());
         let demo_path = "src/tests/data/demos/test_demo.dem";
+        let config = HighlightConfig::default();
+        let other_config = HighlightConfig {
+            killstreak_interval: 3,
+            ..HighlightConfig::default()
+        };
 
-        let original = read_demo_details(Path::new(demo_path)).expect("failed to parse test demo");
+        let (original, _states) =
+            read_demo_details(Path::new(demo_path), &config).expect("failed to parse test demo");
 
-        cache.insert(demo_path, &original).await.unwrap();
-        let read_back = cache.get(demo_path).await;
+        cache
+            .insert_with_options(demo_path, &config, &original)
+            .await
+            .unwrap();
+        let read_back = cache.get_with_options(demo_path, &config).await;
+        let read_back_other = cache.get_with_options(demo_path, &other_config).await;
 
         // Clean up even if the test fails
         tokio::fs::remove_dir_all(cache_path).await.unwrap();
         let read_back = read_back.expect("get failed");
 
         assert_eq!(Some(original), read_back);
+        // Changing the config invalidates the entry
+        assert_eq!(None, read_back_other.expect("get failed"));
     }
 }
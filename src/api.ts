@@ -1,6 +1,15 @@
 import { invoke } from "@tauri-apps/api/core";
 
-import type { Demo, DemoEvent, DemoFilter, GameSummary, SortKey } from "./demo";
+import type {
+  ChatLogFormat,
+  Demo,
+  DemoEvent,
+  DemoFilter,
+  GameSummary,
+  HighlightConfig,
+  SortKey,
+  UserId,
+} from "./demo";
 
 export async function getDemosInDirectory(
   dirPath: string,
@@ -50,9 +59,35 @@ export async function getDemo(demoPath: string) {
   });
 }
 
-export async function getDemoDetails(demoPath: string) {
+export async function getDemoDetails(
+  demoPath: string,
+  highlightConfig: HighlightConfig
+) {
   return invoke<GameSummary>("get_demo_details", {
     demoPath,
+    highlightConfig,
+  });
+}
+
+export async function exportVoice(
+  demoPath: string,
+  userId: UserId,
+  outputPath: string
+) {
+  return invoke<void>("export_voice", { demoPath, userId, outputPath });
+}
+
+export async function exportChatLog(
+  demoPath: string,
+  highlightConfig: HighlightConfig,
+  format: ChatLogFormat,
+  outputPath: string
+) {
+  return invoke<void>("export_chat_log", {
+    demoPath,
+    highlightConfig,
+    format,
+    outputPath,
   });
 }
 
@@ -17,6 +17,56 @@ export type Demo = {
   playbackTime: number;
   numTicks: number;
   isStv: boolean;
+  gameMode: GameMode;
+  // Only known after the demo was opened
+  matchInfo: MatchInfo | null;
+};
+
+export const gameModeNames = {
+  unknown: "Unknown",
+  control_points: "Control Points",
+  attack_defend: "Attack/Defend",
+  koth: "King of the Hill",
+  payload: "Payload",
+  payload_race: "Payload Race",
+  ctf: "Capture the Flag",
+  arena: "Arena",
+  mvm: "Mann vs. Machine",
+  passtime: "PASS Time",
+} as const;
+
+export type GameMode = keyof typeof gameModeNames;
+
+export const matchFormatNames = {
+  unknown: "Unknown",
+  ultiduo: "Ultiduo",
+  fours: "4v4",
+  sixes: "6v6",
+  highlander: "Highlander",
+  casual: "Casual",
+} as const;
+
+export type MatchFormat = keyof typeof matchFormatNames;
+
+export const matchTypeNames = {
+  public: "Public",
+  tournament: "Tournament",
+  valve_competitive: "Valve Competitive",
+  valve_casual: "Valve Casual",
+  valve_mvm: "Valve Mann vs. Machine",
+} as const;
+
+export type MatchType = keyof typeof matchTypeNames;
+
+export type MatchInfo = {
+  format: MatchFormat;
+  match_type: MatchType;
+  players_per_team: number;
+  tournament_mode: boolean;
+  red_team_name: string;
+  blue_team_name: string;
+  ready_up: boolean;
+  pregame_end_tick: number | null;
 };
 
 export type Team = "red" | "blue" | "spectator" | "other";
@@ -60,8 +110,12 @@ export type KillHighlight = {
   streak: number;
   drop: boolean;
   airshot: boolean;
+  crit: CritType;
+  random_crit: boolean;
 };
 
+export type CritType = "Normal" | "MiniCrit" | "Crit";
+
 export type KillStreakHighlight = {
   player: HighlightPlayerSnapshot;
   streak: number;
@@ -73,11 +127,44 @@ export type KillStreakEndedHighlight = {
   streak: number;
 };
 
+export type ChatKind =
+  | "All"
+  | "Team"
+  | "AllDead"
+  | "TeamDead"
+  | "Spectator"
+  // The text is the new name of the sender
+  | "NameChange";
+
+// The prefix the game shows in front of the sender's name
+export const chatKindPrefixes: Record<ChatKind, string> = {
+  All: "",
+  Team: "(TEAM) ",
+  AllDead: "*DEAD* ",
+  TeamDead: "*DEAD*(TEAM) ",
+  Spectator: "*SPEC* ",
+  NameChange: "",
+};
+
 export type ChatMessageHighlight = {
   sender: HighlightPlayerSnapshot;
   text: string;
+  kind: ChatKind;
+  sender_alive: boolean;
+};
+
+export type ChatLogEntry = {
+  tick: number;
+  user_id: UserId;
+  name: string;
+  team: Team;
+  kind: ChatKind;
+  sender_alive: boolean;
+  text: string;
 };
 
+export type ChatLogFormat = "text" | "json";
+
 export type MessageHighlight = {
   text: string;
 };
@@ -92,12 +179,82 @@ export type CrossbowAirshotHighlight = {
   target: HighlightPlayerSnapshot;
 };
 
+export type MidairHighlight = {
+  killer: HighlightPlayerSnapshot;
+  victim: HighlightPlayerSnapshot;
+  kill_icon: string;
+  airborne_ticks: number;
+  killer_airborne: boolean;
+};
+
+export type FlickHighlight = {
+  killer: HighlightPlayerSnapshot;
+  victim: HighlightPlayerSnapshot;
+  kill_icon: string;
+  angle: number;
+  ticks: number;
+};
+
+// See HighlightConfig.low_health_threshold
+export type LowHealthSurvivalHighlight = {
+  player: HighlightPlayerSnapshot;
+  // The lowest health the player survived with
+  health: number;
+};
+
+export type OverhealedKillHighlight = {
+  killer: HighlightPlayerSnapshot;
+  victim: HighlightPlayerSnapshot;
+  health: number;
+  max_health: number;
+  // The medic who was healing the killer
+  medic: HighlightPlayerSnapshot | null;
+};
+
+export type ClassChangeHighlight = {
+  player: HighlightPlayerSnapshot;
+  from: ClassName;
+  to: ClassName;
+};
+
 export type PointCapturedHighlight = {
   point_name: string;
   capturing_team: number;
   cappers: HighlightPlayerSnapshot[];
 };
 
+export type FlagEventKind =
+  | "PickedUp"
+  | "Captured"
+  | "Defended"
+  | "Dropped"
+  | "Returned";
+
+export type FlagHighlight = {
+  player: HighlightPlayerSnapshot | null;
+  team: Team;
+  kind: FlagEventKind;
+};
+
+export type LastManStandingHighlight = {
+  player: HighlightPlayerSnapshot;
+  enemies_alive: number;
+};
+
+// Waves are zero-based
+export type WaveStartHighlight = {
+  wave: number;
+  max_waves: number;
+};
+
+export type WaveCompleteHighlight = {
+  wave: number;
+};
+
+export type WaveFailedHighlight = {
+  wave: number;
+};
+
 export type RoundStalemateHighlight = {
   reason: number;
 };
@@ -108,7 +265,7 @@ export type RoundStartHighlight = {
 
 export type RoundWinHighlight = {
   winner: Team;
-  // TODO: Win reason?
+  win_reason: WinReason | null;
 };
 
 export type PlayerConnectedHighlight = {
@@ -129,6 +286,52 @@ export type PauseHighlight = {
   pause: boolean;
 };
 
+export type UberchargeKind =
+  | "Ubercharge"
+  | "Kritzkrieg"
+  | "QuickFix"
+  | "Vaccinator";
+
+export type UberchargeOutcome = "Used" | "Dropped" | "Faded";
+
+export type UberBuiltHighlight = {
+  medic: HighlightPlayerSnapshot;
+};
+
+export type UberUsedHighlight = {
+  medic: HighlightPlayerSnapshot;
+  target: HighlightPlayerSnapshot | null;
+  kind: UberchargeKind;
+  held_ticks: number;
+};
+
+export type BuildingDestroyedHighlight = {
+  builder: HighlightPlayerSnapshot;
+  attacker: HighlightPlayerSnapshot;
+  assister: HighlightPlayerSnapshot | null;
+  kind: BuildingKind;
+  level: number;
+  weapon: string;
+};
+
+export type PingSpikeHighlight = {
+  player: HighlightPlayerSnapshot;
+  // In milliseconds
+  peak: number;
+  // The average ping before the spike, in milliseconds
+  baseline: number;
+  start_tick: number;
+  end_tick: number;
+};
+
+export type MultikillHighlight = {
+  killer: HighlightPlayerSnapshot;
+  victims: HighlightPlayerSnapshot[];
+  weapons: string[];
+  start_tick: number;
+  end_tick: number;
+};
+
 export type Highlight =
   | { Kill: KillHighlight }
   | { KillStreak: KillStreakHighlight }
@@ -144,7 +347,22 @@ export type Highlight =
   | { PlayerConnected: PlayerConnectedHighlight }
   | { PlayerDisconnected: PlayerDisconnectedHighlight }
   | { PlayerTeamChange: PlayerTeamChangeHighlight }
-  | { Pause: PauseHighlight };
+  | { Pause: PauseHighlight }
+  | { PingSpike: PingSpikeHighlight }
+  | { Multikill: MultikillHighlight }
+  | { UberBuilt: UberBuiltHighlight }
+  | { UberUsed: UberUsedHighlight }
+  | { BuildingDestroyed: BuildingDestroyedHighlight }
+  | { Midair: MidairHighlight }
+  | { Flick: FlickHighlight }
+  | { LowHealthSurvival: LowHealthSurvivalHighlight }
+  | { OverhealedKill: OverhealedKillHighlight }
+  | { ClassChange: ClassChangeHighlight }
+  | { Flag: FlagHighlight }
+  | { LastManStanding: LastManStandingHighlight }
+  | { WaveStart: WaveStartHighlight }
+  | { WaveComplete: WaveCompleteHighlight }
+  | { WaveFailed: WaveFailedHighlight };
 
 // Awful hack to get around using #[serde(tag = "...")],
 // which is unsupported by bincode.
@@ -163,7 +381,22 @@ export type TaggedHighlight =
   | { type: "PlayerConnected"; highlight: PlayerConnectedHighlight }
   | { type: "PlayerDisconnected"; highlight: PlayerDisconnectedHighlight }
   | { type: "PlayerTeamChange"; highlight: PlayerTeamChangeHighlight }
-  | { type: "Pause"; highlight: PauseHighlight };
+  | { type: "Pause"; highlight: PauseHighlight }
+  | { type: "PingSpike"; highlight: PingSpikeHighlight }
+  | { type: "Multikill"; highlight: MultikillHighlight }
+  | { type: "UberBuilt"; highlight: UberBuiltHighlight }
+  | { type: "UberUsed"; highlight: UberUsedHighlight }
+  | { type: "BuildingDestroyed"; highlight: BuildingDestroyedHighlight }
+  | { type: "Midair"; highlight: MidairHighlight }
+  | { type: "Flick"; highlight: FlickHighlight }
+  | { type: "LowHealthSurvival"; highlight: LowHealthSurvivalHighlight }
+  | { type: "OverhealedKill"; highlight: OverhealedKillHighlight }
+  | { type: "ClassChange"; highlight: ClassChangeHighlight }
+  | { type: "Flag"; highlight: FlagHighlight }
+  | { type: "LastManStanding"; highlight: LastManStandingHighlight }
+  | { type: "WaveStart"; highlight: WaveStartHighlight }
+  | { type: "WaveComplete"; highlight: WaveCompleteHighlight }
+  | { type: "WaveFailed"; highlight: WaveFailedHighlight };
 
 export type HighlightType = TaggedHighlight["type"];
 
@@ -203,6 +436,53 @@ export type PlayerSummary = {
   scoreboard: Scoreboard;
 
   round_scoreboards: ScoreboardMap;
+
+  // Keyed by weapon class, e.g. "TF_WEAPON_SHOTGUN_SOLDIER"
+  weapon_stats: Record<string, WeaponStats>;
+  building_stats: Partial<Record<BuildingKind, BuildingStats>>;
+  heal_spread: HealSpread;
+  crit_stats: CritStats;
+  conditions: ConditionInterval[];
+  condition_time: Partial<Record<TrackedCondition, number>>;
+  lives: Life[];
+  most_used_loadout: Loadout | null;
+  // Only filled in Mann vs. Machine demos
+  mvm_stats: MvmPlayerStats;
+  // null for bots
+  ping: PingStats | null;
+  // The score on the in-game scoreboard at the end of the demo,
+  // known for every player unlike scoreboard.points
+  score: number;
+};
+
+export type PingSpike = {
+  start_tick: number;
+  end_tick: number;
+  // The highest ping during the spike, in milliseconds
+  peak: number;
+  // The average ping before the spike started, in milliseconds
+  baseline: number;
+};
+
+// In milliseconds
+export type PingStats = {
+  average: number;
+  min: number;
+  max: number;
+  spikes: PingSpike[];
+};
+
+export type RobotKills = {
+  regular: number;
+  giants: number;
+  bosses: number;
+  tanks: number;
+};
+
+export type MvmPlayerStats = {
+  robot_kills: RobotKills;
+  tank_damage: number;
+  credits_collected: number;
 };
 
 export function primaryTeam(player: PlayerSummary): Team {
@@ -219,8 +499,36 @@ export function primaryTeam(player: PlayerSummary): Team {
 
 export type UserIdAliases = Record<number, number>;
 
+export type Matchup = {
+  kills: number;
+  assists: number;
+  damage: number;
+};
+
+// Attacker -> victim -> matchup
+export type Matchups = Record<UserId, Record<UserId, Matchup>>;
+
+export type KillMatrix = {
+  matchups: Matchups;
+  round_matchups: Record<number, Matchups>;
+};
+
+export type Ubercharge = {
+  medic: UserId;
+  built_tick: number;
+  used_tick: number | null;
+  end_tick: number | null;
+  held_ticks: number;
+  kind: UberchargeKind | null;
+  target: UserId | null;
+  outcome: UberchargeOutcome | null;
+};
+
 export type GameSummary = {
   local_user_id: UserId;
+  game_mode: GameMode;
+  match_info: MatchInfo;
+  server: ServerInfo;
   highlights: HighlightEvent[];
   red_team_score: number;
   blue_team_score: number;
@@ -228,6 +536,362 @@ export type GameSummary = {
   players: PlayerSummary[];
   num_rounds: number;
   aliases: UserIdAliases;
+  ubercharges: Ubercharge[];
+  kill_matrix: KillMatrix;
+  rounds: RoundSummary[];
+  pauses: PauseInterval[];
+  buildings: Building[];
+  best_moments: BestMoment[];
+  objectives: Objectives;
+  waves: WaveSummary[];
+  // Ordered by start_tick
+  voice: VoiceInterval[];
+  // Ordered by tick
+  chat_log: ChatLogEntry[];
+  // Ordered by tick
+  class_changes: ClassChange[];
+  // Ordered by tick
+  team_compositions: TeamComposition[];
+};
+
+export type ClassChange = {
+  tick: number;
+  user_id: UserId;
+  team: Team;
+  from: ClassName;
+  to: ClassName;
+};
+
+export type TeamComposition = {
+  tick: number;
+  team: Team;
+  // The number of players on each class, indexed by Class
+  classes: number[];
+};
+
+const compositionClassNames = [
+  "scout",
+  "sniper",
+  "soldier",
+  "demo",
+  "med",
+  "heavy",
+  "pyro",
+  "spy",
+  "engi",
+];
+
+// The composition of `team` at `tick`, if it is known
+export function compositionAt(
+  compositions: TeamComposition[],
+  team: Team,
+  tick: number
+): TeamComposition | undefined {
+  for (let index = compositions.length - 1; index >= 0; index--) {
+    const composition = compositions[index];
+    if (composition.team === team && composition.tick <= tick) {
+      return composition;
+    }
+  }
+  return undefined;
+}
+
+// e.g. "2 scout 2 soldier 1 demo 1 med"
+export function formatComposition(composition: TeamComposition): string {
+  return composition.classes
+    .map((count, cls) =>
+      count > 0 ? `${count} ${compositionClassNames[cls]}` : null
+    )
+    .filter((part) => part !== null)
+    .join(" ");
+}
+
+export type VoiceInterval = {
+  user_id: UserId;
+  start_tick: number;
+  // The tick of the last voice packet
+  end_tick: number;
+};
+
+// The players who were talking within `margin` ticks of `tick`
+export function speakersAround(
+  voice: VoiceInterval[],
+  tick: number,
+  margin: number
+): UserId[] {
+  const speakers = voice
+    .filter(
+      (interval) =>
+        interval.start_tick <= tick + margin &&
+        interval.end_tick >= tick - margin
+    )
+    .map((interval) => interval.user_id);
+
+  return [...new Set(speakers)];
+}
+
+export type WaveOutcome = "Completed" | "Failed";
+
+export type WaveSummary = {
+  // Zero-based
+  wave: number;
+  max_waves: number;
+  start_tick: number;
+  end_tick: number | null;
+  // In ticks, excluding pauses
+  duration: number;
+  outcome: WaveOutcome | null;
+  credits_collected: number;
+  robot_kills: RobotKills;
+  upgrades_purchased: number;
+};
+
+export type ServerOs = "Unknown" | "Linux" | "Windows" | "MacOs";
+
+export type ConVarChange = {
+  tick: number;
+  name: string;
+  value: string;
+};
+
+export type ServerInfo = {
+  name: string;
+  version: number;
+  max_players: number;
+  os: ServerOs;
+  dedicated: boolean;
+  // Values at the end of the demo
+  convars: Record<string, string>;
+  convar_changes: ConVarChange[];
+};
+
+export type KothTimers = {
+  tick: number;
+  owner: Team;
+  // In seconds
+  red_time_remaining: number;
+  blue_time_remaining: number;
+};
+
+export type CartProgress = {
+  tick: number;
+  team: Team;
+  // From 0 to 1
+  progress: number;
+  pushers: number;
+};
+
+export type Objectives = {
+  koth_timers: KothTimers[];
+  cart_progress: CartProgress[];
+};
+
+export type BestMoment = {
+  score: number;
+  start_tick: number;
+  end_tick: number;
+  // Indices into GameSummary.highlights
+  highlights: number[];
+};
+
+export type HealTargets = Record<UserId, number>;
+
+export type HealSpread = {
+  targets: HealTargets;
+  round_targets: Record<number, HealTargets>;
+};
+
+// Keep in sync with HighlightConfig::default() in the backend
+export type HighlightConfig = {
+  killstreak_interval: number;
+  killstreak_ended_threshold: number;
+  // Weapon classes, e.g. "TF_WEAPON_ROCKETLAUNCHER"
+  airshot_weapons: string[];
+  multikill: {
+    min_kills: number;
+    window: number;
+  };
+  midair_min_ticks: number;
+  // Record midairs of every player in STV demos, not just the local player's
+  stv_midairs: boolean;
+  flick_min_angle: number;
+  flick_window_ticks: number;
+  best_moments: number;
+  // 0 disables LowHealthSurvival highlights
+  low_health_threshold: number;
+};
+
+export const defaultHighlightConfig: HighlightConfig = {
+  killstreak_interval: 5,
+  killstreak_ended_threshold: 10,
+  airshot_weapons: [
+    "TF_WEAPON_ROCKETLAUNCHER",
+    "TF_WEAPON_ROCKETLAUNCHER_DIRECTHIT",
+    "TF_WEAPON_PARTICLE_CANNON",
+    "TF_WEAPON_GRENADELAUNCHER",
+    "TF_WEAPON_CANNON",
+    "TF_WEAPON_CROSSBOW",
+  ],
+  multikill: {
+    min_kills: 2,
+    window: 200,
+  },
+  midair_min_ticks: 10,
+  stv_midairs: false,
+  flick_min_angle: 45,
+  flick_window_ticks: 10,
+  best_moments: 5,
+  low_health_threshold: 10,
+};
+
+export type TrackedCondition =
+  | "Ubercharged"
+  | "Vaccinated"
+  | "Quickfixed"
+  | "CritBoosted"
+  | "Jarated"
+  | "Milked"
+  | "MarkedForDeath"
+  | "Bleeding"
+  | "Burning"
+  | "Cloaked"
+  | "Disguised"
+  | "Charging";
+
+export type ConditionInterval = {
+  condition: TrackedCondition;
+  start_tick: number;
+  end_tick: number | null;
+  duration: number;
+};
+
+export type CritStats = {
+  crit_damage: number;
+  crit_hits: number;
+  crit_kills: number;
+  random_crit_damage: number;
+  random_crit_hits: number;
+  random_crit_kills: number;
+  minicrit_damage: number;
+  minicrit_hits: number;
+  minicrit_kills: number;
+};
+
+export type ClassName =
+  | "scout"
+  | "sniper"
+  | "soldier"
+  | "demoman"
+  | "medic"
+  | "heavy"
+  | "pyro"
+  | "spy"
+  | "engineer"
+  | "other";
+
+export type Loadout = {
+  class: ClassName;
+  // Item definition indexes, sorted
+  weapons: number[];
+};
+
+export type Life = {
+  end_tick: number;
+  duration: number;
+  loadout: Loadout;
+  wearables: number[];
+  // Includes bonuses and penalties from items
+  max_health: number;
+  // All damage taken during this life, including self-damage
+  damage_absorbed: number;
+  // In ticks, excluding pauses
+  overheal_ticks: number;
+};
+
+export type BuildingKind = "Dispenser" | "Teleporter" | "Sentry";
+
+export type TeleporterMode = "Entrance" | "Exit";
+
+export type BuildingEnd =
+  | {
+      Destroyed: {
+        attacker: UserId;
+        assister: UserId | null;
+        weapon: string;
+      };
+    }
+  | "Detonated"
+  | "Removed";
+
+export type Building = {
+  builder: UserId;
+  kind: BuildingKind;
+  teleporter_mode: TeleporterMode | null;
+  mini: boolean;
+  built_tick: number;
+  upgrades: { tick: number; level: number }[];
+  kills: number;
+  uses: number;
+  end_tick: number | null;
+  end: BuildingEnd | null;
+};
+
+export type BuildingStats = {
+  built: number;
+  upgrades: number;
+  destroyed: number;
+  kills: number;
+  uses: number;
+};
+
+export type PauseInterval = {
+  start_tick: number;
+  end_tick: number | null;
+};
+
+export type WinReason =
+  | "None"
+  | "AllPointsCaptured"
+  | "OpponentsDead"
+  | "FlagCaptureLimit"
+  | "DefendUntilTimeLimit"
+  | "Stalemate"
+  | "TimeLimit"
+  | "WinLimit"
+  | "WinDiffLimit"
+  | "ReactorCaptured"
+  | "CoresCollected"
+  | "ReactorReturned"
+  | "PlayerDestructionPoints"
+  | "Scored"
+  | "StopwatchWatchingRounds"
+  | "StopwatchWatchingFinalRound"
+  | "StopwatchPlayingRounds";
+
+export type PointCapture = {
+  tick: number;
+  point_name: string;
+  team: Team;
+  cappers: UserId[];
+};
+
+export type FirstBlood = {
+  tick: number;
+  killer: UserId;
+  victim: UserId;
+};
+
+export type RoundSummary = {
+  round: number;
+  start_tick: number;
+  end_tick: number | null;
+  duration: number;
+  winner: Team | null;
+  win_reason: WinReason | null;
+  captures: PointCapture[];
+  first_blood: FirstBlood | null;
+  stalemate: boolean;
+  sudden_death: boolean;
 };
 
 export type Scoreboard = {
@@ -250,6 +914,14 @@ export type Scoreboard = {
   damage_dealt: number;
 };
 
+export type WeaponStats = {
+  damage_dealt: number;
+  damage_taken: number;
+  kills: number;
+  deaths: number;
+  hits: number;
+};
+
 export const sortKeys = {
   birthtime: "File creation time",
   file_size: "File size",
@@ -268,6 +940,9 @@ export type DemoFilter =
   | { event: string }
   | { file_name: string }
   | { free_text: string }
+  | { game_mode: string }
   | { map_name: string }
+  | { match_format: string }
+  | { match_type: string }
   | { player_name: string }
   | { tag_name: string };
@@ -7,16 +7,29 @@ import { KillIcon } from "@/components";
 import KillstreakIcon from "@/components/KillstreakIcon";
 import {
   type AirshotHighlight,
+  type BuildingDestroyedHighlight,
+  type BuildingKind,
   type ChatMessageHighlight,
+  type ClassChangeHighlight,
+  chatKindPrefixes,
   type CrossbowAirshotHighlight,
   destructureHighlight,
+  type FlagEventKind,
+  type FlagHighlight,
+  type FlickHighlight,
   type Highlight,
   type HighlightPlayerSnapshot,
   type KillHighlight,
   type KillStreakEndedHighlight,
   type KillStreakHighlight,
+  type LastManStandingHighlight,
+  type LowHealthSurvivalHighlight,
   type MessageHighlight,
+  type MidairHighlight,
+  type MultikillHighlight,
+  type OverhealedKillHighlight,
   type PauseHighlight,
+  type PingSpikeHighlight,
   type PlayerConnectedHighlight,
   type PlayerDisconnectedHighlight,
   type PlayerTeamChangeHighlight,
@@ -25,6 +38,12 @@ import {
   type RoundStartHighlight,
   type RoundWinHighlight,
   type Team,
+  type UberBuiltHighlight,
+  type UberUsedHighlight,
+  type WaveCompleteHighlight,
+  type WaveFailedHighlight,
+  type WaveStartHighlight,
+  type WinReason,
 } from "@/demo";
 
 import classes from "./HighlightBox.module.css";
@@ -162,10 +181,20 @@ function KillHighlightBox(highlight: KillHighlight) {
       <KillIcon killIcon={highlight.kill_icon} />
       &nbsp;
       <PlayerName player={victim} />
+      {highlight.crit !== "Normal" && (
+        <>&nbsp;({formatCrit(highlight)})</>
+      )}
     </div>
   );
 }
 
+function formatCrit(highlight: KillHighlight): string {
+  if (highlight.crit === "MiniCrit") {
+    return "minicrit";
+  }
+  return highlight.random_crit ? "random crit" : "crit";
+}
+
 function KillStreakHighlightBox(highlight: KillStreakHighlight) {
   const { player, streak } = highlight;
   let message: string;
@@ -230,9 +259,109 @@ function KillStreakEndedHighlightBox(highlight: KillStreakEndedHighlight) {
   );
 }
 
+function PingSpikeHighlightBox(highlight: PingSpikeHighlight) {
+  return (
+    <div className={classes.highlightLeft}>
+      <PlayerName player={highlight.player} />
+      's ping spiked to {highlight.peak} ms (usually {highlight.baseline} ms)
+    </div>
+  );
+}
+
+function MultikillHighlightBox(highlight: MultikillHighlight) {
+  const { killer, victims } = highlight;
+
+  let name: string;
+  switch (victims.length) {
+    case 2:
+      name = "Double kill";
+      break;
+    case 3:
+      name = "Triple kill";
+      break;
+    case 4:
+      name = "Quad kill";
+      break;
+    default:
+      name = `${victims.length}x kill`;
+      break;
+  }
+
+  return (
+    <div className={classes.highlightCenter}>
+      <span>
+        {name.toUpperCase()}: <PlayerName player={killer} /> killed&nbsp;
+        <PlayerNames players={victims} />
+      </span>
+    </div>
+  );
+}
+
+function UberBuiltHighlightBox({ medic }: UberBuiltHighlight) {
+  return (
+    <div className={classes.highlightCenter}>
+      <span>
+        <PlayerName player={medic} /> has a full charge
+      </span>
+    </div>
+  );
+}
+
+function UberUsedHighlightBox({ medic, target, kind }: UberUsedHighlight) {
+  return (
+    <div className={classes.highlightCenter}>
+      <span>
+        <PlayerName player={medic} /> deployed {kind}
+        {target !== null && (
+          <>
+            &nbsp;on <PlayerName player={target} />
+          </>
+        )}
+      </span>
+    </div>
+  );
+}
+
+const buildingNames: Record<BuildingKind, string> = {
+  Dispenser: "Dispenser",
+  Teleporter: "Teleporter",
+  Sentry: "Sentry Gun",
+};
+
+function BuildingDestroyedHighlightBox(highlight: BuildingDestroyedHighlight) {
+  const { builder, attacker, assister } = highlight;
+
+  return (
+    <div className={classes.highlightRight}>
+      <PlayerName player={attacker} />
+      {assister !== null && (
+        <>
+          &nbsp;+&nbsp;
+          <PlayerName player={assister} />
+        </>
+      )}
+      &nbsp;
+      <KillIcon killIcon={highlight.weapon} />
+      &nbsp;
+      <PlayerName player={builder} />
+      's level {highlight.level} {buildingNames[highlight.kind]}
+    </div>
+  );
+}
+
 function ChatMessageHighlightBox(highlight: ChatMessageHighlight) {
+  if (highlight.kind === "NameChange") {
+    return (
+      <div className={classes.highlightLeft}>
+        <PlayerName player={highlight.sender} />
+        &nbsp;changed their name to {highlight.text}
+      </div>
+    );
+  }
+
   return (
     <div className={classes.highlightLeft}>
+      {chatKindPrefixes[highlight.kind]}
       <PlayerName player={highlight.sender} />
       :&nbsp;
       {highlight.text}
@@ -269,6 +398,70 @@ function CrossbowAirshotHighlightBox(highlight: CrossbowAirshotHighlight) {
   );
 }
 
+function MidairHighlightBox(highlight: MidairHighlight) {
+  const { killer, victim } = highlight;
+  return (
+    <div className={classes.highlightRight}>
+      MIDAIR:&nbsp;
+      <PlayerName player={killer} />
+      &nbsp;
+      <KillIcon killIcon={highlight.kill_icon} />
+      &nbsp;
+      <PlayerName player={victim} />
+    </div>
+  );
+}
+
+function FlickHighlightBox(highlight: FlickHighlight) {
+  const { killer, victim } = highlight;
+  return (
+    <div className={classes.highlightRight}>
+      FLICK ({Math.round(highlight.angle)}°):&nbsp;
+      <PlayerName player={killer} />
+      &nbsp;
+      <KillIcon killIcon={highlight.kill_icon} />
+      &nbsp;
+      <PlayerName player={victim} />
+    </div>
+  );
+}
+
+function LowHealthSurvivalHighlightBox(highlight: LowHealthSurvivalHighlight) {
+  return (
+    <div className={classes.highlightLeft}>
+      <PlayerName player={highlight.player} />
+      &nbsp;survived with {highlight.health} HP
+    </div>
+  );
+}
+
+function OverhealedKillHighlightBox(highlight: OverhealedKillHighlight) {
+  const { killer, victim, medic } = highlight;
+  return (
+    <div className={classes.highlightRight}>
+      OVERHEALED ({highlight.health}/{highlight.max_health}):&nbsp;
+      <PlayerName player={killer} />
+      {medic !== null && (
+        <>
+          &nbsp;+&nbsp;
+          <PlayerName player={medic} />
+        </>
+      )}
+      &nbsp;killed&nbsp;
+      <PlayerName player={victim} />
+    </div>
+  );
+}
+
+function ClassChangeHighlightBox(highlight: ClassChangeHighlight) {
+  return (
+    <div className={classes.highlightLeft}>
+      <PlayerName player={highlight.player} />
+      &nbsp;switched from {highlight.from} to {highlight.to}
+    </div>
+  );
+}
+
 function PointCapturedHighlightBox(highlight: PointCapturedHighlight) {
   let icon: string | undefined;
 
@@ -300,6 +493,64 @@ function PointCapturedHighlightBox(highlight: PointCapturedHighlight) {
   );
 }
 
+const flagEvents: Record<FlagEventKind, string> = {
+  PickedUp: "picked up",
+  Captured: "captured",
+  Defended: "defended",
+  Dropped: "dropped",
+  Returned: "returned",
+};
+
+function FlagHighlightBox(highlight: FlagHighlight) {
+  return (
+    <div className={classes.highlightRight}>
+      {highlight.player !== null && (
+        <>
+          <PlayerName player={highlight.player} />
+          &nbsp;
+        </>
+      )}
+      {flagEvents[highlight.kind]} the&nbsp;
+      <TeamName team={highlight.team} />
+      &nbsp;intelligence
+    </div>
+  );
+}
+
+function LastManStandingHighlightBox(highlight: LastManStandingHighlight) {
+  return (
+    <div className={classes.highlightCenter}>
+      <PlayerName player={highlight.player} />
+      &nbsp;is the last one standing against {highlight.enemies_alive}
+      {highlight.enemies_alive === 1 ? " enemy" : " enemies"}
+    </div>
+  );
+}
+
+function WaveStartHighlightBox(highlight: WaveStartHighlight) {
+  return (
+    <div className={classes.highlightCenter}>
+      Wave {highlight.wave + 1}/{highlight.max_waves} started
+    </div>
+  );
+}
+
+function WaveCompleteHighlightBox(highlight: WaveCompleteHighlight) {
+  return (
+    <div className={classes.highlightCenter}>
+      Wave {highlight.wave + 1} completed
+    </div>
+  );
+}
+
+function WaveFailedHighlightBox(highlight: WaveFailedHighlight) {
+  return (
+    <div className={classes.highlightCenter}>
+      Wave {highlight.wave + 1} failed
+    </div>
+  );
+}
+
 function RoundStalemateHighlightBox({
   reason: _reason,
 }: RoundStalemateHighlight) {
@@ -316,11 +567,20 @@ function RoundStartHighlightBox({
   return <div className={classes.highlightCenter}>New round started</div>;
 }
 
+const winReasons: Partial<Record<WinReason, string>> = {
+  AllPointsCaptured: "by capturing all control points",
+  OpponentsDead: "by killing all opponents",
+  FlagCaptureLimit: "by capturing the enemy intelligence",
+  DefendUntilTimeLimit: "by defending until the time ran out",
+};
+
 function RoundWinHighlightBox(highlight: RoundWinHighlight) {
+  const reason = highlight.win_reason && winReasons[highlight.win_reason];
+
   return (
     <div className={classes.highlightCenter}>
       <TeamName team={highlight.winner} />
-      &nbsp;won the round
+      &nbsp;won the round{reason ? ` ${reason}` : ""}
     </div>
   );
 }
@@ -384,8 +644,28 @@ export default function HighlightBox({ event }: HighlightProps) {
       return AirshotHighlightBox(highlight);
     case "CrossbowAirshot":
       return CrossbowAirshotHighlightBox(highlight);
+    case "Midair":
+      return MidairHighlightBox(highlight);
+    case "Flick":
+      return FlickHighlightBox(highlight);
+    case "LowHealthSurvival":
+      return LowHealthSurvivalHighlightBox(highlight);
+    case "OverhealedKill":
+      return OverhealedKillHighlightBox(highlight);
+    case "ClassChange":
+      return ClassChangeHighlightBox(highlight);
     case "PointCaptured":
       return PointCapturedHighlightBox(highlight);
+    case "Flag":
+      return FlagHighlightBox(highlight);
+    case "LastManStanding":
+      return LastManStandingHighlightBox(highlight);
+    case "WaveStart":
+      return WaveStartHighlightBox(highlight);
+    case "WaveComplete":
+      return WaveCompleteHighlightBox(highlight);
+    case "WaveFailed":
+      return WaveFailedHighlightBox(highlight);
     case "RoundStalemate":
       return RoundStalemateHighlightBox(highlight);
     case "RoundStart":
@@ -400,6 +680,16 @@ export default function HighlightBox({ event }: HighlightProps) {
       return PlayerTeamChangeHighlightBox(highlight);
     case "Pause":
       return PauseHighlightBox(highlight);
+    case "PingSpike":
+      return PingSpikeHighlightBox(highlight);
+    case "Multikill":
+      return MultikillHighlightBox(highlight);
+    case "UberBuilt":
+      return UberBuiltHighlightBox(highlight);
+    case "UberUsed":
+      return UberUsedHighlightBox(highlight);
+    case "BuildingDestroyed":
+      return BuildingDestroyedHighlightBox(highlight);
     default:
       log.error(`unknown highlight: ${event}`);
       return null;
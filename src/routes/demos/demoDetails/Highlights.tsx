@@ -2,16 +2,23 @@ import { useMemo, useState } from "react";
 
 import { List, type RowComponentProps } from "react-window";
 
-import { Text } from "@mantine/core";
+import { Text, Tooltip } from "@mantine/core";
+import { IconMicrophone, IconUsersGroup } from "@tabler/icons-react";
 
 import {
+  type BestMoment,
+  compositionAt,
   destructureHighlight,
+  formatComposition,
   type GameSummary,
   type HighlightEvent,
   type HighlightPlayerSnapshot,
   type HighlightType,
+  speakersAround,
   type TaggedHighlight,
+  type TeamComposition,
   type UserIdAliases,
+  type VoiceInterval,
 } from "@/demo";
 import HighlightBox from "./HighlightBox";
 import TimelineFilters, { type Filters } from "./TimelineFilters";
@@ -20,6 +27,16 @@ type TimelineProps = {
   gameSummary: GameSummary;
 };
 
+// How far around a death to look for players who were talking
+const VOICE_MARGIN_SECONDS = 2;
+
+type VoiceContext = {
+  voice: VoiceInterval[];
+  // In ticks
+  margin: number;
+  playerNames: Map<number, string>;
+};
+
 function samePlayer(
   playerId: number,
   player: HighlightPlayerSnapshot,
@@ -44,20 +61,56 @@ function involvedPlayers(
       return [highlight.player];
     case "KillStreakEnded":
       return [highlight.killer, highlight.victim];
+    case "Multikill":
+      return [highlight.killer, ...highlight.victims];
+    case "UberBuilt":
+      return [highlight.medic];
+    case "UberUsed":
+      if (highlight.target !== null) {
+        return [highlight.medic, highlight.target];
+      }
+      return [highlight.medic];
+    case "BuildingDestroyed":
+      if (highlight.assister !== null) {
+        return [highlight.assister, highlight.attacker, highlight.builder];
+      }
+      return [highlight.attacker, highlight.builder];
     case "ChatMessage":
       return [highlight.sender];
     case "Airshot":
       return [highlight.attacker, highlight.victim];
     case "CrossbowAirshot":
       return [highlight.healer, highlight.target];
+    case "Midair":
+      return [highlight.killer, highlight.victim];
+    case "Flick":
+      return [highlight.killer, highlight.victim];
+    case "LowHealthSurvival":
+      return [highlight.player];
+    case "ClassChange":
+      return [highlight.player];
+    case "OverhealedKill":
+      if (highlight.medic !== null) {
+        return [highlight.medic, highlight.killer, highlight.victim];
+      }
+      return [highlight.killer, highlight.victim];
     case "PointCaptured":
       return highlight.cappers;
+    case "Flag":
+      if (highlight.player !== null) {
+        return [highlight.player];
+      }
+      return [];
+    case "LastManStanding":
+      return [highlight.player];
     case "PlayerConnected":
       return [highlight.player];
     case "PlayerDisconnected":
       return [highlight.player];
     case "PlayerTeamChange":
       return [highlight.player];
+    case "PingSpike":
+      return [highlight.player];
     default:
       return [];
   }
@@ -83,8 +136,13 @@ function visibleHighlightTypes(filters: Filters): HighlightType[] {
     result.push("Airshot");
     result.push("CrossbowAirshot");
   }
+  if (filters.visibleHighlights.aim) {
+    result.push("Midair");
+    result.push("Flick");
+  }
   if (filters.visibleHighlights.captures) {
     result.push("PointCaptured");
+    result.push("Flag");
   }
   if (filters.visibleHighlights.chat) {
     result.push("ChatMessage");
@@ -93,6 +151,7 @@ function visibleHighlightTypes(filters: Filters): HighlightType[] {
     result.push("PlayerConnected");
     result.push("PlayerDisconnected");
     result.push("PlayerTeamChange");
+    result.push("PingSpike");
   }
   if (filters.visibleHighlights.killfeed) {
     result.push("Kill");
@@ -100,12 +159,31 @@ function visibleHighlightTypes(filters: Filters): HighlightType[] {
   if (filters.visibleHighlights.killstreaks) {
     result.push("KillStreak");
     result.push("KillStreakEnded");
+    result.push("Multikill");
+  }
+  if (filters.visibleHighlights.ubercharges) {
+    result.push("UberBuilt");
+    result.push("UberUsed");
+  }
+  if (filters.visibleHighlights.buildings) {
+    result.push("BuildingDestroyed");
+  }
+  if (filters.visibleHighlights.classChanges) {
+    result.push("ClassChange");
+  }
+  if (filters.visibleHighlights.health) {
+    result.push("LowHealthSurvival");
+    result.push("OverhealedKill");
   }
   if (filters.visibleHighlights.rounds) {
     result.push("Message");
     result.push("RoundStalemate");
     result.push("RoundStart");
     result.push("RoundWin");
+    result.push("LastManStanding");
+    result.push("WaveStart");
+    result.push("WaveComplete");
+    result.push("WaveFailed");
   }
 
   return result;
@@ -114,11 +192,17 @@ function visibleHighlightTypes(filters: Filters): HighlightType[] {
 function filterHighlights(
   unfilteredHighlights: HighlightEvent[],
   filters: Filters,
-  aliases: UserIdAliases
+  aliases: UserIdAliases,
+  bestMoments: BestMoment[]
 ): HighlightEvent[] {
   const visibleHighlights = visibleHighlightTypes(filters);
   let highlights = unfilteredHighlights;
 
+  if (filters.bestMomentsOnly) {
+    const indices = new Set(bestMoments.flatMap((moment) => moment.highlights));
+    highlights = highlights.filter((_highlight, index) => indices.has(index));
+  }
+
   highlights = highlights.filter((highlight) => {
     const { type } = destructureHighlight(highlight.event);
 
@@ -158,6 +242,7 @@ export default function HighlightsList({ gameSummary }: TimelineProps) {
   const [filters, setFilters] = useState<Filters>({
     playerIds: [],
     chatSearch: "",
+    bestMomentsOnly: false,
     visibleHighlights: {
       killfeed: true,
       captures: true,
@@ -166,15 +251,39 @@ export default function HighlightsList({ gameSummary }: TimelineProps) {
       killstreaks: true,
       rounds: true,
       airshots: true,
+      aim: true,
+      ubercharges: true,
+      buildings: true,
+      health: true,
+      classChanges: true,
     },
   });
 
   const highlights = useMemo(
     () =>
-      filterHighlights(gameSummary.highlights, filters, gameSummary.aliases),
+      filterHighlights(
+        gameSummary.highlights,
+        filters,
+        gameSummary.aliases,
+        gameSummary.best_moments
+      ),
     [gameSummary, filters]
   );
 
+  const voiceContext = useMemo<VoiceContext>(
+    () => ({
+      voice: gameSummary.voice,
+      margin:
+        gameSummary.interval_per_tick > 0
+          ? VOICE_MARGIN_SECONDS / gameSummary.interval_per_tick
+          : VOICE_MARGIN_SECONDS * 66,
+      playerNames: new Map(
+        gameSummary.players.map((player) => [player.user_id, player.name])
+      ),
+    }),
+    [gameSummary]
+  );
+
   return (
     <div
       style={{
@@ -190,7 +299,11 @@ export default function HighlightsList({ gameSummary }: TimelineProps) {
       />
       <List
         rowComponent={RowComponent}
-        rowProps={{ highlights }}
+        rowProps={{
+          highlights,
+          voiceContext,
+          compositions: gameSummary.team_compositions,
+        }}
         rowCount={highlights.length}
         rowHeight={40}
       />
@@ -198,12 +311,78 @@ export default function HighlightsList({ gameSummary }: TimelineProps) {
   );
 }
 
+function SpeakersIndicator({
+  tick,
+  voiceContext,
+}: {
+  tick: number;
+  voiceContext: VoiceContext;
+}) {
+  const speakers = speakersAround(
+    voiceContext.voice,
+    tick,
+    voiceContext.margin
+  );
+
+  if (speakers.length === 0) {
+    return null;
+  }
+
+  const names = speakers.map(
+    (userId) => voiceContext.playerNames.get(userId) ?? `#${userId}`
+  );
+
+  return (
+    <Tooltip label={`Talking: ${names.join(", ")}`}>
+      <IconMicrophone size={16} style={{ marginLeft: 8, flexShrink: 0 }} />
+    </Tooltip>
+  );
+}
+
+function CompositionIndicator({
+  highlight,
+  tick,
+  compositions,
+}: {
+  highlight: TaggedHighlight;
+  tick: number;
+  compositions: TeamComposition[];
+}) {
+  if (highlight.type !== "ClassChange") {
+    return null;
+  }
+
+  const composition = compositionAt(
+    compositions,
+    highlight.highlight.player.team,
+    tick
+  );
+
+  if (composition === undefined) {
+    return null;
+  }
+
+  return (
+    <Tooltip label={formatComposition(composition)}>
+      <IconUsersGroup size={16} style={{ marginLeft: 8, flexShrink: 0 }} />
+    </Tooltip>
+  );
+}
+
 const RowComponent = ({
   style,
   index,
   highlights,
-}: RowComponentProps<{ highlights: HighlightEvent[] }>) => {
+  voiceContext,
+  compositions,
+}: RowComponentProps<{
+  highlights: HighlightEvent[];
+  voiceContext: VoiceContext;
+  compositions: TeamComposition[];
+}>) => {
   const { event, tick } = highlights[index];
+  const taggedHighlight = destructureHighlight(event);
+  const isKill = taggedHighlight.type === "Kill";
 
   return (
     <div
@@ -226,6 +405,12 @@ const RowComponent = ({
         {tick}
       </Text>
       <HighlightBox event={event} />
+      {isKill && <SpeakersIndicator tick={tick} voiceContext={voiceContext} />}
+      <CompositionIndicator
+        highlight={taggedHighlight}
+        tick={tick}
+        compositions={compositions}
+      />
     </div>
   );
 };
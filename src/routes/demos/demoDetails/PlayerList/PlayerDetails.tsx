@@ -1,10 +1,13 @@
+import { save } from "@tauri-apps/plugin-dialog";
+import * as log from "@tauri-apps/plugin-log";
 import { openUrl } from "@tauri-apps/plugin-opener";
 
 import { useState } from "react";
 
 import { ActionIcon, Tabs, Title, Tooltip } from "@mantine/core";
-import { IconBrandSteam } from "@tabler/icons-react";
+import { IconBrandSteam, IconMicrophone } from "@tabler/icons-react";
 
+import { exportVoice } from "@/api";
 import type { GameSummary, PlayerSummary, Scoreboard } from "@/demo";
 import ClassPlaytimeIndicator from "./ClassPlaytimeIndicator";
 import { ScoreboardTable } from "./ScoreboardTable";
@@ -12,11 +15,31 @@ import { ScoreboardTable } from "./ScoreboardTable";
 import classes from "./PlayerDetails.module.css";
 
 type PlayerDetailsProps = {
+  demoPath: string;
   player: PlayerSummary;
   gameSummary: GameSummary;
 };
 
-export function PlayerDetails({ player, gameSummary }: PlayerDetailsProps) {
+async function saveVoice(demoPath: string, player: PlayerSummary) {
+  try {
+    const outputPath = await save({
+      title: `Export voice of ${player.name}`,
+      filters: [{ name: "Voice export", extensions: ["dmvoice"] }],
+    });
+
+    if (outputPath !== null) {
+      await exportVoice(demoPath, player.user_id, outputPath);
+    }
+  } catch (error) {
+    log.error(`Failed to export voice: ${error}`);
+  }
+}
+
+export function PlayerDetails({
+  demoPath,
+  player,
+  gameSummary,
+}: PlayerDetailsProps) {
   const [currentTab, setCurrentTab] = useState<string>("match");
 
   const scoreboard: Scoreboard | undefined =
@@ -28,6 +51,10 @@ export function PlayerDetails({ player, gameSummary }: PlayerDetailsProps) {
     setCurrentTab("match");
   }
 
+  const hasVoice = gameSummary.voice.some(
+    (interval) => interval.user_id === player.user_id
+  );
+
   return (
     <div className={classes.playerDetails}>
       <div className={classes.scoreboardPlayerNameHeader}>
@@ -50,6 +77,19 @@ export function PlayerDetails({ player, gameSummary }: PlayerDetailsProps) {
               </ActionIcon>
             </Tooltip>
           )}
+          {hasVoice && (
+            <Tooltip label="Export voice">
+              <ActionIcon
+                ml="xs"
+                variant="transparent"
+                color="gray"
+                size="lg"
+                onClick={() => saveVoice(demoPath, player)}
+              >
+                <IconMicrophone />
+              </ActionIcon>
+            </Tooltip>
+          )}
         </Title>
         <ClassPlaytimeIndicator
           player={player}
@@ -9,10 +9,14 @@ import { PlayerTable } from "./PlayerTable";
 import classes from "./PlayerList.module.css";
 
 export type PlayerListProps = {
+  demoPath: string;
   gameSummary: GameSummary;
 };
 
-export default function PlayerList({ gameSummary }: PlayerListProps) {
+export default function PlayerList({
+  demoPath,
+  gameSummary,
+}: PlayerListProps) {
   const mainPlayer: PlayerSummary =
     gameSummary.players.find((p) => p.user_id === gameSummary.local_user_id) ??
     gameSummary.players[0];
@@ -26,7 +30,11 @@ export default function PlayerList({ gameSummary }: PlayerListProps) {
         currentPlayer={currentPlayer}
         setCurrentPlayer={setCurrentPlayer}
       />
-      <PlayerDetails gameSummary={gameSummary} player={currentPlayer} />
+      <PlayerDetails
+        demoPath={demoPath}
+        gameSummary={gameSummary}
+        player={currentPlayer}
+      />
     </Paper>
   );
 }
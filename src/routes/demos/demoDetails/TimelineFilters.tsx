@@ -7,6 +7,8 @@ import classes from "./TimelineFilters.module.css";
 export type Filters = {
   playerIds: number[];
   chatSearch: string;
+  // Only show highlights that are part of GameSummary.best_moments
+  bestMomentsOnly: boolean;
   visibleHighlights: {
     killfeed: boolean;
     captures: boolean;
@@ -15,6 +17,11 @@ export type Filters = {
     killstreaks: boolean;
     rounds: boolean;
     airshots: boolean;
+    aim: boolean;
+    ubercharges: boolean;
+    buildings: boolean;
+    health: boolean;
+    classChanges: boolean;
   };
 };
 
@@ -90,6 +97,11 @@ export default function TimelineFilters({
                 killstreaks: checked,
                 rounds: checked,
                 airshots: checked,
+                aim: checked,
+                ubercharges: checked,
+                buildings: checked,
+                health: checked,
+                classChanges: checked,
               },
             });
           }}
@@ -129,8 +141,43 @@ export default function TimelineFilters({
           checked={filters.visibleHighlights.airshots}
           onChange={handleChange("airshots")}
         />
+        <ToggleButton
+          label="Midairs & Flicks"
+          checked={filters.visibleHighlights.aim}
+          onChange={handleChange("aim")}
+        />
+        <ToggleButton
+          label="Ubercharges"
+          checked={filters.visibleHighlights.ubercharges}
+          onChange={handleChange("ubercharges")}
+        />
+        <ToggleButton
+          label="Buildings"
+          checked={filters.visibleHighlights.buildings}
+          onChange={handleChange("buildings")}
+        />
+        <ToggleButton
+          label="Health"
+          checked={filters.visibleHighlights.health}
+          onChange={handleChange("health")}
+        />
+        <ToggleButton
+          label="Class Changes"
+          checked={filters.visibleHighlights.classChanges}
+          onChange={handleChange("classChanges")}
+        />
       </div>
 
+      <Checkbox
+        label="Only show best moments"
+        checked={filters.bestMomentsOnly}
+        onChange={(event) => {
+          setFilters({
+            ...filters,
+            bestMomentsOnly: event.currentTarget.checked,
+          });
+        }}
+      />
       <TextInput
         label={"Filter chat messages"}
         placeholder={"Enter something to filter by..."}
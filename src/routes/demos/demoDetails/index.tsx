@@ -1,3 +1,4 @@
+import { save } from "@tauri-apps/plugin-dialog";
 import * as log from "@tauri-apps/plugin-log";
 import { revealItemInDir } from "@tauri-apps/plugin-opener";
 
@@ -32,6 +33,7 @@ import {
   IconClock,
   IconFileAnalytics,
   IconFolder,
+  IconMessages,
   IconPencil,
   IconServer,
   IconTimeline,
@@ -41,14 +43,15 @@ import {
 } from "@tabler/icons-react";
 
 import { HeaderPortal } from "@/AppShell";
-import { getDemo, getDemoDetails, setDemoTags } from "@/api";
+import { exportChatLog, getDemo, getDemoDetails, setDemoTags } from "@/api";
 import { Fill, HeaderButton, LoaderFallback, MapThumbnail } from "@/components";
-import type { Demo, GameSummary } from "@/demo";
+import type { ChatLogFormat, Demo, GameSummary } from "@/demo";
 import useLocationState from "@/hooks/useLocationState";
 import useStore from "@/hooks/useStore";
 import { openDeleteDemoModal } from "@/modals/DeleteDemoModal";
 import { openRenameDemoModal } from "@/modals/RenameDemoModal";
 import { RconContext } from "@/RconContext";
+import { getStoreValue } from "@/store";
 import { decodeParam, formatDuration, formatFileSize } from "@/util";
 import DemoTagsInput from "./DemoTagsInput";
 import EventsList from "./EventsList";
@@ -92,6 +95,33 @@ function DemoTitle({ demo }: { demo: Demo }) {
   );
 }
 
+async function saveChatLog(demo: Demo) {
+  try {
+    const outputPath = await save({
+      title: "Export chat log",
+      defaultPath: `${demo.name}.txt`,
+      filters: [
+        { name: "Text", extensions: ["txt"] },
+        { name: "JSON", extensions: ["json"] },
+      ],
+    });
+
+    if (outputPath !== null) {
+      const format: ChatLogFormat = outputPath.endsWith(".json")
+        ? "json"
+        : "text";
+      await exportChatLog(
+        demo.path,
+        getStoreValue("highlightConfig"),
+        format,
+        outputPath
+      );
+    }
+  } catch (error) {
+    log.error(`Failed to export chat log: ${error}`);
+  }
+}
+
 type LoaderData = {
   demo: Demo;
   details: Promise<GameSummary>;
@@ -130,6 +160,11 @@ export default function DemoDetailsView() {
                 <IconFolder />
               </HeaderButton>
             </Tooltip>
+            <Tooltip label="Export chat log">
+              <HeaderButton onClick={() => saveChatLog(demo)}>
+                <IconMessages />
+              </HeaderButton>
+            </Tooltip>
             <DemoTagsInput
               tags={demo.tags}
               setTags={(tags: string[]) => {
@@ -256,7 +291,10 @@ export default function DemoDetailsView() {
                           </Tabs.List>
 
                           <Tabs.Panel value="players" pt="xs">
-                            <PlayerList gameSummary={gameSummary} />
+                            <PlayerList
+                              demoPath={demo.path}
+                              gameSummary={gameSummary}
+                            />
                           </Tabs.Panel>
 
                           <Tabs.Panel value="events" pt="xs">
@@ -309,6 +347,6 @@ export const loader: LoaderFunction = async ({ params }) => {
 
   return {
     demo,
-    details: getDemoDetails(demoPath),
+    details: getDemoDetails(demoPath, getStoreValue("highlightConfig")),
   } satisfies LoaderData;
 };
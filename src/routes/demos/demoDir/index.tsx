@@ -18,7 +18,15 @@ import {
   getKnownTags,
 } from "@/api";
 import { HeaderButton, LoaderFallback } from "@/components";
-import type { Demo, DemoFilter, SortKey, SortOrder } from "@/demo";
+import {
+  type Demo,
+  type DemoFilter,
+  gameModeNames,
+  matchFormatNames,
+  matchTypeNames,
+  type SortKey,
+  type SortOrder,
+} from "@/demo";
 import useLocationState from "@/hooks/useLocationState";
 import type { Path } from "@/store";
 import DemoList from "./DemoList";
@@ -37,7 +45,16 @@ type DemoListLoaderArgs = {
   filters: DemoFilter[];
 };
 
-type FilterPatternKey = "type" | "event" | "name" | "map" | "player" | "tag";
+type FilterPatternKey =
+  | "type"
+  | "event"
+  | "name"
+  | "map"
+  | "mode"
+  | "format"
+  | "match"
+  | "player"
+  | "tag";
 
 const reassembleFilter = (filter: { key: string; value: string }) =>
   `${filter.key}:${filter.value}`;
@@ -61,6 +78,16 @@ function tokenToDemoFilter(token: Token): DemoFilter | null {
           return { file_name: replaceBackslashPlaceholder(token.value.value) };
         case "map":
           return { map_name: replaceBackslashPlaceholder(token.value.value) };
+        case "mode":
+          return { game_mode: replaceBackslashPlaceholder(token.value.value) };
+        case "format":
+          return {
+            match_format: replaceBackslashPlaceholder(token.value.value),
+          };
+        case "match":
+          return {
+            match_type: replaceBackslashPlaceholder(token.value.value),
+          };
         case "player":
           return {
             player_name: replaceBackslashPlaceholder(token.value.value),
@@ -144,6 +171,9 @@ export default () => {
       event: knownEvents,
       name: knownDemoNames,
       map: knownMaps,
+      mode: Object.keys(gameModeNames),
+      format: Object.keys(matchFormatNames),
+      match: Object.keys(matchTypeNames),
       player: knownPlayers,
       tag: knownTags,
     } satisfies Record<FilterPatternKey, string[]>;
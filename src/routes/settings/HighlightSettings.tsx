@@ -0,0 +1,189 @@
+import {
+  Button,
+  Checkbox,
+  Group,
+  MultiSelect,
+  NumberInput,
+  Text,
+} from "@mantine/core";
+
+import { defaultHighlightConfig } from "@/demo";
+import useStore from "@/hooks/useStore";
+
+import classes from "./settings.module.css";
+
+const airshotWeaponOptions = [
+  { value: "TF_WEAPON_ROCKETLAUNCHER", label: "Rocket Launcher" },
+  { value: "TF_WEAPON_ROCKETLAUNCHER_DIRECTHIT", label: "Direct Hit" },
+  { value: "TF_WEAPON_PARTICLE_CANNON", label: "Cow Mangler" },
+  { value: "TF_WEAPON_GRENADELAUNCHER", label: "Grenade Launcher" },
+  { value: "TF_WEAPON_CANNON", label: "Loose Cannon" },
+  { value: "TF_WEAPON_PIPEBOMBLAUNCHER", label: "Stickybomb Launcher" },
+  { value: "TF_WEAPON_CROSSBOW", label: "Crusader's Crossbow" },
+  { value: "TF_WEAPON_COMPOUND_BOW", label: "Huntsman" },
+  { value: "TF_WEAPON_FLAREGUN", label: "Flare Gun" },
+  { value: "TF_WEAPON_SNIPERRIFLE", label: "Sniper Rifle" },
+];
+
+export default function HighlightSettings() {
+  const [config, setConfig] = useStore("highlightConfig");
+
+  // Keep the current value while the input is empty
+  const toNumber = (value: string | number, current: number) =>
+    typeof value === "number" ? value : current;
+
+  return (
+    <div className={classes.setting}>
+      <div className={classes.labelRow}>
+        <Text className={classes.label}>Highlights</Text>
+        <Button
+          variant="subtle"
+          color="gray"
+          size="compact-sm"
+          onClick={() => setConfig(defaultHighlightConfig)}
+        >
+          Reset
+        </Button>
+      </div>
+      <Text c="dimmed">
+        Changing these causes demos to be analyzed again when opened.
+      </Text>
+      <Group grow pt="md">
+        <NumberInput
+          label="Killstreak interval"
+          description="0 disables killstreaks"
+          min={0}
+          value={config.killstreak_interval}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              killstreak_interval: toNumber(value, config.killstreak_interval),
+            })
+          }
+        />
+        <NumberInput
+          label="Ended killstreak threshold"
+          min={1}
+          value={config.killstreak_ended_threshold}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              killstreak_ended_threshold: toNumber(
+                value,
+                config.killstreak_ended_threshold
+              ),
+            })
+          }
+        />
+      </Group>
+      <Group grow pt="sm">
+        <NumberInput
+          label="Multikill minimum kills"
+          min={2}
+          value={config.multikill.min_kills}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              multikill: {
+                ...config.multikill,
+                min_kills: toNumber(value, config.multikill.min_kills),
+              },
+            })
+          }
+        />
+        <NumberInput
+          label="Multikill window (ticks)"
+          min={1}
+          value={config.multikill.window}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              multikill: {
+                ...config.multikill,
+                window: toNumber(value, config.multikill.window),
+              },
+            })
+          }
+        />
+      </Group>
+      <Group grow pt="sm">
+        <NumberInput
+          label="Midair minimum airtime (ticks)"
+          min={0}
+          value={config.midair_min_ticks}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              midair_min_ticks: toNumber(value, config.midair_min_ticks),
+            })
+          }
+        />
+        <NumberInput
+          label="Flick minimum angle (°)"
+          min={0}
+          max={360}
+          value={config.flick_min_angle}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              flick_min_angle: toNumber(value, config.flick_min_angle),
+            })
+          }
+        />
+        <NumberInput
+          label="Flick window (ticks)"
+          min={1}
+          value={config.flick_window_ticks}
+          onChange={(value) =>
+            setConfig({
+              ...config,
+              flick_window_ticks: toNumber(value, config.flick_window_ticks),
+            })
+          }
+        />
+      </Group>
+      <Checkbox
+        pt="sm"
+        label="Record midairs of every player in STV demos"
+        checked={config.stv_midairs}
+        onChange={(event) =>
+          setConfig({ ...config, stv_midairs: event.currentTarget.checked })
+        }
+      />
+      <NumberInput
+        pt="sm"
+        label="Number of best moments"
+        min={0}
+        value={config.best_moments}
+        onChange={(value) =>
+          setConfig({
+            ...config,
+            best_moments: toNumber(value, config.best_moments),
+          })
+        }
+      />
+      <NumberInput
+        pt="sm"
+        label="Low health survival threshold (HP, 0 to disable)"
+        min={0}
+        value={config.low_health_threshold}
+        onChange={(value) =>
+          setConfig({
+            ...config,
+            low_health_threshold: toNumber(
+              value,
+              config.low_health_threshold
+            ),
+          })
+        }
+      />
+      <MultiSelect
+        pt="sm"
+        label="Airshot weapons"
+        data={airshotWeaponOptions}
+        value={config.airshot_weapons}
+        onChange={(airshot_weapons) => setConfig({ ...config, airshot_weapons })}
+      />
+    </div>
+  );
+}
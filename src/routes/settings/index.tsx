@@ -14,6 +14,7 @@ import { HeaderPortal } from "@/AppShell";
 import useStore from "@/hooks/useStore";
 import BooleanSetting from "./BooleanSetting";
 import DemoDirsSetting from "./DemoDirsSetting";
+import HighlightSettings from "./HighlightSettings";
 
 import classes from "./settings.module.css";
 
@@ -69,6 +70,8 @@ export default function SettingsView() {
             />
             <Divider mt="md" />
             <DemoDirsSetting />
+            <Divider mt="md" />
+            <HighlightSettings />
             {import.meta.env.DEV && (
               /* Dev-only settings */
               <>
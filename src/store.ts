@@ -1,3 +1,5 @@
+import { defaultHighlightConfig, type HighlightConfig } from "@/demo";
+
 export type Path = string;
 export type Label = string;
 
@@ -7,6 +9,7 @@ export type StoreSchema = {
   enableLocationOverlay: boolean;
   skipTrash: boolean;
   preferredPlayOption: "rcon" | "copyCommand";
+  highlightConfig: HighlightConfig;
 };
 
 const storeDefaults: {
@@ -17,6 +20,7 @@ const storeDefaults: {
   enableLocationOverlay: false,
   skipTrash: false,
   preferredPlayOption: "rcon",
+  highlightConfig: defaultHighlightConfig,
 };
 
 export function storeDefault<K extends keyof StoreSchema>(
@@ -52,6 +56,7 @@ const storeValidators: {
   enableLocationOverlay: isBool,
   skipTrash: isBool,
   preferredPlayOption: (value) => value === "rcon" || value === "copyCommand",
+  highlightConfig: isObject,
 };
 
 export function validateStoreValue<K extends keyof StoreSchema>(